@@ -0,0 +1,285 @@
+//! Ring buffer of raw run output under the user cache dir, so a compressed
+//! result can be expanded later (`cx last --raw`, `cx show <id>`) without
+//! re-running the command.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One archived run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u64,
+    /// Unix timestamp (seconds) of the run.
+    pub timestamp: u64,
+    pub tool: String,
+    pub command: Vec<String>,
+    pub exit_code: i32,
+    pub elapsed_ms: u128,
+    pub stdout: String,
+    pub stderr: String,
+    /// What `cx` printed for this run.
+    pub compressed: String,
+}
+
+impl Entry {
+    /// stdout + stderr, as fed to the compressor.
+    pub fn raw(&self) -> String {
+        let mut buf = self.stdout.clone();
+        if !self.stderr.is_empty() {
+            if !buf.is_empty() {
+                buf.push('\n');
+            }
+            buf.push_str(&self.stderr);
+        }
+        buf
+    }
+}
+
+/// Directory of `<id>.json` entries, keeping at most `capacity` of them.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+    capacity: usize,
+}
+
+impl Archive {
+    pub fn new(dir: PathBuf, capacity: usize) -> Self {
+        Self { dir, capacity }
+    }
+
+    /// The archive under `~/.cache/cx/runs`, or `None` when disabled (`capacity` 0).
+    pub fn open(capacity: usize) -> Option<Self> {
+        if capacity == 0 {
+            return None;
+        }
+        let dir = dirs::cache_dir()?.join("cx").join("runs");
+        Some(Self::new(dir, capacity))
+    }
+
+    /// Store a run, assigning it the next id; evicts the oldest entries.
+    /// Ids are claimed by creating the file, so concurrent runs never share
+    /// one.
+    pub fn save(&self, mut entry: Entry) -> io::Result<u64> {
+        create_private_dir(&self.dir)?;
+        entry.id = self.ids()?.last().map_or(1, |last| last + 1);
+        let mut file = loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(self.path(entry.id))
+            {
+                Ok(file) => break file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => entry.id += 1,
+                Err(e) => return Err(e),
+            }
+        };
+        entry.timestamp = now_secs();
+
+        let json = serde_json::to_string(&entry).map_err(io::Error::other)?;
+        file.write_all(json.as_bytes())?;
+
+        let ids = self.ids()?;
+        let excess = ids.len().saturating_sub(self.capacity);
+        for id in ids.iter().take(excess) {
+            let _ = fs::remove_file(self.path(*id));
+        }
+        Ok(entry.id)
+    }
+
+    pub fn load(&self, id: u64) -> Option<Entry> {
+        let content = fs::read_to_string(self.path(id)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// The most recent entry.
+    pub fn last(&self) -> Option<Entry> {
+        let ids = self.ids().ok()?;
+        self.load(*ids.last()?)
+    }
+
+    /// Stored ids, oldest first.
+    fn ids(&self) -> io::Result<Vec<u64>> {
+        let mut ids: Vec<u64> = match fs::read_dir(&self.dir) {
+            Ok(rd) => rd
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let name = e.file_name();
+                    let name = name.to_str()?;
+                    name.strip_suffix(".json")?.parse().ok()
+                })
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// Create `dir` (and missing parents) readable only by the user: raw output
+/// and logged command lines can hold secrets.
+pub fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// An inclusive, 1-based line range parsed from `a..b`, `a..`, `..b` or `a`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl std::str::FromStr for LineRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| -> Result<usize, String> {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid line number `{n}`"))
+        };
+        let (start, end) = match s.split_once("..") {
+            Some((a, b)) => (
+                if a.is_empty() { 1 } else { parse(a)? },
+                if b.is_empty() { None } else { Some(parse(b)?) },
+            ),
+            None => {
+                let n = parse(s)?;
+                (n, Some(n))
+            }
+        };
+        if start == 0 || end.is_some_and(|e| e < start) {
+            return Err(format!("invalid line range `{s}` (lines start at 1)"));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl LineRange {
+    pub fn contains(&self, line_no: usize) -> bool {
+        line_no >= self.start && self.end.is_none_or(|e| line_no <= e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_archive(name: &str, capacity: usize) -> Archive {
+        let dir = std::env::temp_dir().join(format!("cx-archive-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Archive::new(dir, capacity)
+    }
+
+    fn entry(stdout: &str) -> Entry {
+        Entry {
+            id: 0,
+            timestamp: 0,
+            tool: "run".into(),
+            command: vec!["echo".into()],
+            exit_code: 0,
+            elapsed_ms: 1,
+            stdout: stdout.into(),
+            stderr: String::new(),
+            compressed: "ok".into(),
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let archive = temp_archive("load", 5);
+        let id = archive.save(entry("hello")).unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(archive.load(id).unwrap().stdout, "hello");
+        assert_eq!(archive.last().unwrap().id, 1);
+        let _ = fs::remove_dir_all(archive.dir());
+    }
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let archive = temp_archive("ring", 2);
+        for i in 0..4 {
+            archive.save(entry(&format!("run {i}"))).unwrap();
+        }
+        assert!(archive.load(1).is_none());
+        assert!(archive.load(2).is_none());
+        assert_eq!(archive.load(3).unwrap().stdout, "run 2");
+        assert_eq!(archive.last().unwrap().id, 4);
+        let _ = fs::remove_dir_all(archive.dir());
+    }
+
+    #[test]
+    fn test_concurrent_saves_get_distinct_ids() {
+        let archive = temp_archive("concurrent", 50);
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let archive = archive.clone();
+                std::thread::spawn(move || archive.save(entry(&format!("run {i}"))).unwrap())
+            })
+            .collect();
+        let mut ids: Vec<u64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (1..=8).collect::<Vec<_>>());
+        let _ = fs::remove_dir_all(archive.dir());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dir_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let archive = temp_archive("private", 2);
+        archive.save(entry("secret")).unwrap();
+        let mode = fs::metadata(archive.dir()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        let _ = fs::remove_dir_all(archive.dir());
+    }
+
+    #[test]
+    fn test_last_empty() {
+        let archive = temp_archive("empty", 2);
+        assert!(archive.last().is_none());
+    }
+
+    #[test]
+    fn test_entry_raw_merges_streams() {
+        let mut e = entry("out");
+        e.stderr = "err".into();
+        assert_eq!(e.raw(), "out\nerr");
+    }
+
+    #[test]
+    fn test_line_range_parse() {
+        let r: LineRange = "81..120".parse().unwrap();
+        assert!(r.contains(81) && r.contains(120) && !r.contains(121));
+        let open: LineRange = "81..".parse().unwrap();
+        assert!(open.contains(10_000) && !open.contains(80));
+        let head: LineRange = "..3".parse().unwrap();
+        assert!(head.contains(1) && !head.contains(4));
+        let single: LineRange = "7".parse().unwrap();
+        assert!(single.contains(7) && !single.contains(8));
+        assert!("0..3".parse::<LineRange>().is_err());
+        assert!("5..2".parse::<LineRange>().is_err());
+        assert!("a..b".parse::<LineRange>().is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! Opt-in cache of results (`cache_ttl_secs`): a command repeated with the
+//! same arguments, directory and environment while the files it reads are
+//! unchanged is answered from the previous run instead of being re-run.
+//!
+//! Each tool decides what may be cached and what invalidates it
+//! ([`Tool::cache_spec`]); everything else always runs.
+
+use crate::tools::{Outcome, Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What a tool's cached result depends on, besides argv and the cwd.
+#[derive(Debug, Clone, Default)]
+pub struct CacheSpec {
+    /// Environment variables (by prefix) that change the result.
+    pub env: &'static [&'static str],
+    pub inputs: Vec<Input>,
+}
+
+/// A file or tree whose modification times invalidate a cached result.
+#[derive(Debug, Clone)]
+pub enum Input {
+    /// One file; a missing file counts as a state too.
+    File(PathBuf),
+    /// Every file and directory under `root`, except entries named in `skip`.
+    Tree { root: PathBuf, skip: Vec<String> },
+}
+
+/// A stored result.
+#[derive(Debug, Serialize, Deserialize)]
+struct Stored {
+    /// Unix timestamp (seconds) of the run.
+    timestamp: u64,
+    fingerprint: u64,
+    exit_code: i32,
+    text: String,
+    json: Option<Value>,
+}
+
+/// Directory of `<key>.json` results, each valid for `ttl`.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// The cache under `~/.cache/cx/results`, or `None` when disabled.
+    pub fn open(ttl_secs: Option<u64>) -> Option<Self> {
+        let ttl = Duration::from_secs(ttl_secs.filter(|s| *s > 0)?);
+        let dir = dirs::cache_dir()?.join("cx").join("results");
+        Some(Self::new(dir, ttl))
+    }
+
+    /// Run `tool`, or replay its previous result when nothing it depends on
+    /// has changed since.
+    pub fn run(&self, tool: &dyn Tool) -> Outcome {
+        let Some(mut spec) = tool.cache_spec() else {
+            return tool.run();
+        };
+        // A different config means different limits, hence different output.
+        spec.inputs.push(Input::File(PathBuf::from(".cx.toml")));
+        if let Some(global) = crate::config::global_config_path() {
+            spec.inputs.push(Input::File(global));
+        }
+
+        let path = self.dir.join(format!("{:016x}.json", key(&spec)));
+        // Taken before the run: a file edited while it runs invalidates it.
+        let fingerprint = fingerprint(&spec.inputs);
+        if let Some(outcome) = self.replay(&path, fingerprint) {
+            return outcome;
+        }
+
+        let outcome = tool.run();
+        if is_cacheable(outcome.exit_code) {
+            // Caching is best effort, like archiving.
+            let _ = self.store(
+                &path,
+                &Stored {
+                    timestamp: now_secs(),
+                    fingerprint,
+                    exit_code: outcome.exit_code,
+                    text: outcome.text.clone(),
+                    json: outcome.json.clone(),
+                },
+            );
+        }
+        outcome
+    }
+
+    /// The stored result at `path` if it is fresh and its inputs unchanged.
+    fn replay(&self, path: &Path, fingerprint: u64) -> Option<Outcome> {
+        let stored: Stored = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        let age = now_secs().saturating_sub(stored.timestamp);
+        if stored.fingerprint != fingerprint || age > self.ttl.as_secs() {
+            return None;
+        }
+        let mut outcome = Outcome::new(mark_cached(&stored.text, age), stored.exit_code);
+        outcome.json = stored.json.map(|mut json| {
+            if let Some(object) = json.as_object_mut() {
+                object.insert("cached_secs_ago".into(), age.into());
+            }
+            json
+        });
+        Some(outcome)
+    }
+
+    fn store(&self, path: &Path, stored: &Stored) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string(stored).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+/// Timeouts, spawn failures and signals say nothing about the inputs.
+fn is_cacheable(exit_code: i32) -> bool {
+    !matches!(exit_code, crate::runner::EXIT_TIMEOUT | 126 | 127) && exit_code < 128
+}
+
+/// Identify a command: `cx`'s own argv (flags included), the cwd and the
+/// environment variables the tool reads.
+fn key(spec: &CacheSpec) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    std::env::args().skip(1).for_each(|a| a.hash(&mut hasher));
+    std::env::current_dir().ok().hash(&mut hasher);
+    let mut env: Vec<(String, String)> = std::env::vars()
+        .filter(|(k, _)| spec.env.iter().any(|p| k.starts_with(p)))
+        .collect();
+    env.sort();
+    env.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Combined modification times of the inputs.
+fn fingerprint(inputs: &[Input]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for input in inputs {
+        match input {
+            Input::File(path) => {
+                path.hash(&mut hasher);
+                mtime(path).hash(&mut hasher);
+            }
+            Input::Tree { root, skip } => {
+                root.hash(&mut hasher);
+                tree_state(root, skip).hash(&mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// Order-independent digest of every path and mtime under `dir`. Directory
+/// mtimes catch files being added, removed or renamed.
+fn tree_state(dir: &Path, skip: &[String]) -> u64 {
+    let mut state = {
+        let mut hasher = DefaultHasher::new();
+        mtime(dir).hash(&mut hasher);
+        hasher.finish()
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return state;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        if skip.iter().any(|s| entry.file_name() == s.as_str()) {
+            continue;
+        }
+        let path = entry.path();
+        let digest = match entry.file_type() {
+            Ok(t) if t.is_dir() => tree_state(&path, skip),
+            _ => {
+                let mut hasher = DefaultHasher::new();
+                mtime(&path).hash(&mut hasher);
+                hasher.finish()
+            }
+        };
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        digest.hash(&mut hasher);
+        state = state.wrapping_add(hasher.finish());
+    }
+    state
+}
+
+/// Modification time without following symlinks; `None` if missing.
+fn mtime(path: &Path) -> Option<SystemTime> {
+    fs::symlink_metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Mark a replayed result: the footer's timing becomes `cached, 12s ago`;
+/// output without a footer gets the note on its first line.
+pub fn mark_cached(text: &str, age_secs: u64) -> String {
+    let note = format!("cached, {} ago", format_age(age_secs));
+    let (body, last) = match text.rsplit_once('\n') {
+        Some((body, last)) => (Some(body), last),
+        None => (None, text),
+    };
+    // `[label] ok (812ms, exit 0) …`
+    if let Some((head, rest)) = last.split_once(" (")
+        && head.starts_with('[')
+        && let Some((_, rest)) = rest.split_once("ms, exit ")
+    {
+        let footer = format!("{head} ({note}, exit {rest}");
+        return match body {
+            Some(body) => format!("{body}\n{footer}"),
+            None => footer,
+        };
+    }
+    match text.split_once('\n') {
+        Some((first, rest)) => format!("{first} ({note})\n{rest}"),
+        None => format!("{text} ({note})"),
+    }
+}
+
+/// `12s`, `3m`, `2h`.
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cx-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Counts its runs; depends on one file.
+    struct Counter {
+        runs: Cell<u32>,
+        input: PathBuf,
+    }
+
+    impl Tool for Counter {
+        fn run(&self) -> Outcome {
+            self.runs.set(self.runs.get() + 1);
+            Outcome::new(format!("run {}\n[x] ok (5ms, exit 0)", self.runs.get()), 0)
+        }
+
+        fn cache_spec(&self) -> Option<CacheSpec> {
+            Some(CacheSpec {
+                env: &[],
+                inputs: vec![Input::File(self.input.clone())],
+            })
+        }
+    }
+
+    #[test]
+    fn test_replays_until_input_changes() {
+        let dir = temp_dir("replay");
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.txt");
+        fs::write(&input, "a").unwrap();
+        let cache = Cache::new(dir.join("results"), Duration::from_secs(60));
+        let tool = Counter {
+            runs: Cell::new(0),
+            input: input.clone(),
+        };
+
+        assert_eq!(cache.run(&tool).text, "run 1\n[x] ok (5ms, exit 0)");
+        assert_eq!(
+            cache.run(&tool).text,
+            "run 1\n[x] ok (cached, 0s ago, exit 0)"
+        );
+        assert_eq!(tool.runs.get(), 1);
+
+        let later = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&input)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(cache.run(&tool).text, "run 2\n[x] ok (5ms, exit 0)");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_tree_state_sees_new_files_and_skips() {
+        let dir = temp_dir("tree");
+        fs::create_dir_all(dir.join("target")).unwrap();
+        let skip = vec!["target".to_string()];
+        let before = tree_state(&dir, &skip);
+        fs::write(dir.join("target/out.o"), "x").unwrap();
+        let with_skipped = tree_state(&dir, &skip);
+        assert_eq!(before, with_skipped);
+        fs::write(dir.join("new.rs"), "x").unwrap();
+        assert_ne!(with_skipped, tree_state(&dir, &skip));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_mark_cached() {
+        assert_eq!(
+            mark_cached("body\n[git] ok (20ms, exit 0) → cx show 3", 12),
+            "body\n[git] ok (cached, 12s ago, exit 0) → cx show 3"
+        );
+        assert_eq!(
+            mark_cached("[ls] src (3 entries)\n├── a", 150),
+            "[ls] src (3 entries) (cached, 2m ago)\n├── a"
+        );
+    }
+
+    #[test]
+    fn test_only_meaningful_exits_are_cached() {
+        assert!(is_cacheable(0) && is_cacheable(101));
+        assert!(!is_cacheable(124) && !is_cacheable(127) && !is_cacheable(130));
+    }
+}
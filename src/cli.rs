@@ -1,3 +1,6 @@
+use crate::archive::LineRange;
+use crate::config::OutputFormat;
+use crate::stats::{GroupBy, Since};
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -7,6 +10,30 @@ use clap::{Parser, Subcommand};
     about = "CLI proxy — compresses shell outputs for AI context (Cursor, Claude, Copilot, …)"
 )]
 pub struct Cli {
+    /// Output format (overrides `format` in config)
+    #[arg(long, global = true, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Shrink output to about this many tokens (overrides `token_budget`)
+    #[arg(long, global = true, value_name = "TOKENS")]
+    pub budget: Option<usize>,
+
+    /// Kill the command after this many seconds (overrides `timeout_secs`)
+    #[arg(long, global = true, value_name = "SECS")]
+    pub timeout: Option<u64>,
+
+    /// Run commands the safety policy asks to confirm (git reset --hard, rm -rf, …)
+    #[arg(long, global = true)]
+    pub allow_destructive: bool,
+
+    /// Run the command even if the cache (`cache_ttl_secs`) has its result
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// Print only what changed since the previous run of the command (overrides `delta`)
+    #[arg(long, global = true)]
+    pub delta: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -49,6 +76,64 @@ pub enum Commands {
         args: Vec<String>,
     },
 
+    /// Node proxy (npm / pnpm / yarn / bun by lockfile): install, run, test, ls, outdated
+    Node {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// `cx node` with npm, whatever the lockfile
+    #[command(hide = true)]
+    Npm {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// `cx node` with pnpm, whatever the lockfile
+    #[command(hide = true)]
+    Pnpm {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// `cx node` with yarn, whatever the lockfile
+    #[command(hide = true)]
+    Yarn {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// `cx node` with bun, whatever the lockfile
+    #[command(hide = true)]
+    Bun {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Go proxy: build, test, vet, mod, …
+    Go {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Make proxy: runs a target, summarized by the tool it ran; lists targets without one
+    Make {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// just proxy: runs a recipe like `cx make`; lists recipes without one
+    Just {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Task (go-task) proxy: runs a task like `cx make`; lists tasks without one
+    Task {
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
     /// Docker / container commands
     Docker {
         #[arg(trailing_var_arg = true)]
@@ -61,6 +146,41 @@ pub enum Commands {
         args: Vec<String>,
     },
 
+    /// Replay the last run (compressed, or its raw output with --raw)
+    Last {
+        /// Show the full raw output instead of the compressed view
+        #[arg(long)]
+        raw: bool,
+        /// Raw line range, 1-based and inclusive: 81..120, 81.., ..40
+        #[arg(long, value_name = "A..B")]
+        lines: Option<LineRange>,
+        /// Only raw lines containing this text
+        #[arg(long, value_name = "TEXT")]
+        grep: Option<String>,
+    },
+
+    /// Show the raw output of an archived run (id from the footer)
+    Show {
+        /// Run id, as printed in the footer (`→ cx show <id>`)
+        id: u64,
+        /// Raw line range, 1-based and inclusive: 81..120, 81.., ..40
+        #[arg(long, value_name = "A..B")]
+        lines: Option<LineRange>,
+        /// Only raw lines containing this text
+        #[arg(long, value_name = "TEXT")]
+        grep: Option<String>,
+    },
+
+    /// Compression ratios of past runs, from the stats log
+    Stats {
+        /// Only runs in this period: 30m, 12h, 7d, 2w
+        #[arg(long, value_name = "PERIOD")]
+        since: Option<Since>,
+        /// Group by tool (`cargo`) or subcommand (`cargo test`)
+        #[arg(long, value_enum, default_value_t = GroupBy::Tool)]
+        by: GroupBy,
+    },
+
     /// Show detected project type and current config
     Info,
 
@@ -71,3 +191,99 @@ pub enum Commands {
         global: bool,
     },
 }
+
+impl Cli {
+    /// Whether `--allow-destructive` was given, before the subcommand or
+    /// after it: clap hands a flag placed after a proxied subcommand to the
+    /// wrapped tool, so it is taken back out of those arguments (up to `--`).
+    pub fn take_allow_destructive(&mut self) -> bool {
+        let mut found = self.allow_destructive;
+        if let Some(args) = self.command.proxied_args_mut() {
+            let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+            let before = args.len();
+            let mut i = 0;
+            args.retain(|a| {
+                i += 1;
+                !(i <= end && a == "--allow-destructive")
+            });
+            found |= args.len() < before;
+        }
+        found
+    }
+}
+
+impl Commands {
+    /// The tool name and arguments of a proxied command, for the safety policy.
+    pub fn proxied(&self) -> Option<(&'static str, &[String])> {
+        match self {
+            Self::Git { args } => Some(("git", args)),
+            Self::Cargo { args } => Some(("cargo", args)),
+            Self::Python { args } => Some(("python", args)),
+            Self::Node { args }
+            | Self::Npm { args }
+            | Self::Pnpm { args }
+            | Self::Yarn { args }
+            | Self::Bun { args } => Some(("node", args)),
+            Self::Go { args } => Some(("go", args)),
+            Self::Make { args } => Some(("make", args)),
+            Self::Just { args } => Some(("just", args)),
+            Self::Task { args } => Some(("task", args)),
+            Self::Docker { args } => Some(("docker", args)),
+            Self::Run { args } => Some(("run", args)),
+            _ => None,
+        }
+    }
+
+    fn proxied_args_mut(&mut self) -> Option<&mut Vec<String>> {
+        match self {
+            Self::Git { args }
+            | Self::Cargo { args }
+            | Self::Python { args }
+            | Self::Node { args }
+            | Self::Npm { args }
+            | Self::Pnpm { args }
+            | Self::Yarn { args }
+            | Self::Bun { args }
+            | Self::Go { args }
+            | Self::Make { args }
+            | Self::Just { args }
+            | Self::Task { args }
+            | Self::Docker { args }
+            | Self::Run { args } => Some(args),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(argv: &str) -> (bool, Vec<String>) {
+        let mut cli = Cli::try_parse_from(argv.split_whitespace()).unwrap();
+        let allowed = cli.take_allow_destructive();
+        let args = cli.command.proxied().map(|(_, a)| a.to_vec()).unwrap();
+        (allowed, args)
+    }
+
+    #[test]
+    fn test_allow_destructive_before_or_after_the_subcommand() {
+        let reset = vec!["reset".to_string(), "--hard".to_string()];
+        assert_eq!(
+            allowed("cx --allow-destructive git reset --hard"),
+            (true, reset.clone())
+        );
+        assert_eq!(
+            allowed("cx git reset --hard --allow-destructive"),
+            (true, reset.clone())
+        );
+        assert_eq!(allowed("cx git reset --hard"), (false, reset));
+        assert_eq!(
+            allowed("cx run echo -- --allow-destructive"),
+            (
+                false,
+                vec!["echo".into(), "--".into(), "--allow-destructive".into()]
+            )
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Token budget: estimate tokens offline and shrink output to fit a target.
+//!
+//! Compressed output is a list of sections — an unindented header line such as
+//! `[errors: 3]` followed by its indented entries. When over budget, whole
+//! low-priority sections go first (passing tests, progress, warnings), then
+//! entries of more important ones; errors and failures are dropped last.
+
+use super::truncate::truncate_with;
+
+/// Tokens reserved for the `[cx] … dropped` note appended after fitting.
+const NOTE_TOKENS: usize = 32;
+
+/// Rough BPE-style token estimate: letter runs cost ~1 token per 4 chars,
+/// digit runs ~1 per 3, every punctuation or non-ASCII char ~1, newlines 1.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0usize;
+    let mut run = 0usize;
+    let mut run_is_digit = false;
+
+    let flush = |run: &mut usize, is_digit: bool, tokens: &mut usize| {
+        if *run > 0 {
+            let per = if is_digit { 3 } else { 4 };
+            *tokens += run.div_ceil(per);
+            *run = 0;
+        }
+    };
+
+    for c in text.chars() {
+        if c.is_ascii_alphabetic() || c == '_' {
+            if run_is_digit {
+                flush(&mut run, true, &mut tokens);
+            }
+            run_is_digit = false;
+            run += 1;
+        } else if c.is_ascii_digit() {
+            if !run_is_digit {
+                flush(&mut run, false, &mut tokens);
+            }
+            run_is_digit = true;
+            run += 1;
+        } else {
+            flush(&mut run, run_is_digit, &mut tokens);
+            if c == '\n' || !c.is_whitespace() {
+                tokens += 1;
+            }
+        }
+    }
+    flush(&mut run, run_is_digit, &mut tokens);
+    tokens
+}
+
+/// How important a section is; lower is dropped first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    /// Passing tests, progress and bookkeeping lines.
+    Noise,
+    /// Warnings, notes, deprecations.
+    Warning,
+    /// Anything unclassified.
+    Normal,
+    /// Errors, failures, panics, conflicts.
+    Error,
+}
+
+fn classify(header: &str) -> Priority {
+    let lower = header.to_lowercase();
+    if ["error", "fail", "panic", "conflict", "traceback"]
+        .iter()
+        .any(|k| lower.contains(k))
+    {
+        Priority::Error
+    } else if ["warning", "warn", "note", "deprecat"]
+        .iter()
+        .any(|k| lower.contains(k))
+    {
+        Priority::Warning
+    } else if lower.contains("passed")
+        || lower.ends_with(" ok")
+        || lower.ends_with("... ok")
+        || [
+            "compiling",
+            "running ",
+            "checking",
+            "downloading",
+            "finished",
+        ]
+        .iter()
+        .any(|k| lower.trim_start().starts_with(k))
+    {
+        Priority::Noise
+    } else {
+        Priority::Normal
+    }
+}
+
+/// Shrink `text` until its estimated token count fits `budget`.
+pub fn fit(text: &str, budget: usize) -> String {
+    if estimate_tokens(text) <= budget {
+        return text.to_string();
+    }
+    let target = budget.saturating_sub(NOTE_TOKENS);
+
+    let lines: Vec<&str> = text.lines().collect();
+    let costs: Vec<usize> = lines.iter().map(|l| estimate_tokens(l) + 1).collect();
+
+    // Each line inherits its section's priority; headers outlive their entries.
+    let mut section = Priority::Normal;
+    let mut order: Vec<(Priority, bool, usize)> = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        let is_header = !line.starts_with(' ') && !line.starts_with('\t');
+        if is_header {
+            section = classify(line);
+        }
+        // Entries inherit their section's priority unless they classify themselves.
+        let own = match (is_header, classify(line)) {
+            (true, _) | (false, Priority::Normal) => section,
+            (false, p) => p,
+        };
+        order.push((own, is_header, i));
+    }
+    // Lowest priority first, entries before headers, later lines before earlier ones.
+    order.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)).then(b.2.cmp(&a.2)));
+
+    let mut keep = vec![true; lines.len()];
+    let mut total: usize = costs.iter().sum();
+    let mut dropped = [0usize; 4];
+    for &(prio, _, i) in &order {
+        if total <= target || keep.iter().filter(|k| **k).count() <= 1 {
+            break;
+        }
+        keep[i] = false;
+        total -= costs[i];
+        dropped[prio as usize] += 1;
+    }
+
+    let kept: Vec<&str> = lines
+        .iter()
+        .zip(&keep)
+        .filter(|(_, k)| **k)
+        .map(|(l, _)| *l)
+        .collect();
+    let mut out = kept.join("\n");
+    // A single surviving line may still be too long on its own.
+    if estimate_tokens(&out) > target {
+        out = truncate_with(&out, 1, target.saturating_mul(4).max(16));
+    }
+
+    let labels = ["noise", "warnings", "other", "errors"];
+    let detail: Vec<String> = dropped
+        .iter()
+        .zip(labels)
+        .filter(|(n, _)| **n > 0)
+        .map(|(n, label)| format!("{n} {label}"))
+        .collect();
+    out.push_str(&format!(
+        "\n[cx] … dropped {} lines ({}) to fit ~{budget} tokens",
+        dropped.iter().sum::<usize>(),
+        detail.join(", ")
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_empty() {
+        assert_eq!(estimate_tokens(""), 0);
+    }
+
+    #[test]
+    fn test_estimate_words_and_punctuation() {
+        // "hello" → 2, "," → 1, "world" → 2
+        assert_eq!(estimate_tokens("hello, world"), 5);
+        // digits are split in groups of 3
+        assert_eq!(estimate_tokens("123456"), 2);
+        assert_eq!(estimate_tokens("a\nb"), 3);
+    }
+
+    #[test]
+    fn test_estimate_grows_with_text() {
+        let short = estimate_tokens("error[E0308]: mismatched types");
+        let long = estimate_tokens(&"error[E0308]: mismatched types\n".repeat(10));
+        assert!(long >= short * 10);
+    }
+
+    #[test]
+    fn test_fit_under_budget_unchanged() {
+        let text = "[errors: 1]\n  error: boom";
+        assert_eq!(fit(text, 1000), text);
+    }
+
+    #[test]
+    fn test_fit_drops_warnings_before_errors() {
+        let mut text = String::from("[errors: 2]\n  error: first\n  error: second\n");
+        text.push_str("[warnings: 40]\n");
+        for i in 0..40 {
+            text.push_str(&format!("  warning: unused variable number {i}\n"));
+        }
+        let result = fit(&text, 60);
+        assert!(result.contains("error: first"));
+        assert!(result.contains("error: second"));
+        assert!(!result.contains("variable number 39"));
+        assert!(result.contains("[cx] … dropped"));
+        assert!(result.contains("warnings"));
+        assert!(estimate_tokens(&result) <= 60);
+    }
+
+    #[test]
+    fn test_fit_drops_passing_tests_before_failures() {
+        let mut text = String::new();
+        for i in 0..30 {
+            text.push_str(&format!("test passing_{i} ... ok\n"));
+        }
+        text.push_str("---- failing_test stdout ----\n");
+        text.push_str("  assertion failed: left == right\n");
+        let result = fit(&text, 70);
+        assert!(result.contains("failing_test"));
+        assert!(result.contains("assertion failed"));
+        assert!(!result.contains("passing_29"));
+    }
+
+    #[test]
+    fn test_fit_tiny_budget_keeps_one_line() {
+        let text = (0..50)
+            .map(|i| format!("error: number {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let result = fit(&text, 5);
+        assert!(result.contains("error: number 0"));
+        assert!(result.contains("dropped 49 lines"));
+    }
+}
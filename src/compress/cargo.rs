@@ -1,65 +1,802 @@
-use super::Compressor;
-use super::truncate::truncate;
+use super::junit;
+use super::summary::{
+    self, Diagnostic, SLOWEST_TESTS, SuiteReport, TestFailure, TestReport, TestTiming,
+    parse_location,
+};
+use super::truncate::{cut, truncate};
+use super::{CompressCtx, Compressor};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Longest suggested replacement shown inline after a diagnostic.
+const SUGGESTION_LEN: usize = 60;
 
 /// Pure compressor for cargo command output.
 pub struct CargoCompressor;
 
 impl Compressor for CargoCompressor {
-    fn compress(&self, raw: &str, sub: Option<&str>) -> String {
+    fn compress(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> String {
         match sub.unwrap_or("") {
-            "test" | "nextest" => compress_test(raw),
-            "build" | "check" => compress_build(raw),
-            "clippy" => compress_clippy(raw),
-            "fmt" => compress_fmt(raw),
-            "run" => compress_run(raw),
-            "bench" => compress_bench(raw),
-            "doc" => compress_doc(raw),
-            "add" | "remove" => compress_dep_change(sub.unwrap_or(""), raw),
-            "update" => compress_update(raw),
+            "test" | "nextest" => compress_test(raw, ctx),
+            "build" | "check" => compress_build(raw, ctx),
+            "clippy" => compress_clippy(raw, ctx),
+            "fmt" => compress_fmt(raw, ctx),
+            "run" => compress_run(raw, ctx),
+            "bench" => compress_bench(raw, ctx),
+            "doc" => compress_doc(raw, ctx),
+            "add" | "remove" => compress_dep_change(sub.unwrap_or(""), raw, ctx),
+            "update" => compress_update(raw, ctx),
             "install" => compress_install(raw),
-            "publish" => compress_publish(raw),
-            _ => truncate(raw),
+            "publish" => compress_publish(raw, ctx),
+            _ => truncate(raw, ctx),
         }
     }
+
+    fn summarize(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> Value {
+        match sub.unwrap_or("") {
+            "test" | "nextest" => {
+                let report = parse_test_report(raw);
+                let diagnostics = parse_diagnostics(raw);
+                // Nothing ran because the tests did not compile: the errors
+                // are the result.
+                if report == TestReport::default() && !diagnostics.errors.is_empty() {
+                    json!(diagnostics)
+                } else {
+                    json!(report)
+                }
+            }
+            "build" | "check" | "clippy" => json!(parse_diagnostics(raw)),
+            _ => summary::lines(&self.compress(raw, sub, ctx)),
+        }
+    }
+}
+
+/// Test counts, per-binary results, failures and timings, from whichever
+/// format the run produced: a JUnit report, libtest JSON, nextest's human
+/// output or plain libtest output.
+fn parse_test_report(raw: &str) -> TestReport {
+    structured_report(raw).unwrap_or_else(|| parse_libtest(junit::split(raw).0))
+}
+
+/// Reports with exact per-test results (everything but plain libtest output),
+/// trimmed to the slowest few timings.
+fn structured_report(raw: &str) -> Option<TestReport> {
+    let (text, xml) = junit::split(raw);
+    let mut report = xml
+        .and_then(junit::parse)
+        .or_else(|| parse_libtest_json(text))
+        .or_else(|| parse_nextest(text))?;
+    report.keep_slowest(SLOWEST_TESTS);
+    Some(report)
+}
+
+/// The test binary a `Running …` / `Doc-tests …` line announces:
+/// `unittests src/lib.rs`, `tests/api.rs`, `doctests demo`.
+fn binary_label(line: &str) -> Option<String> {
+    let line = line.trim_start();
+    if let Some(rest) = line.strip_prefix("Running ") {
+        return Some(rest.split(" (").next().unwrap_or(rest).trim().to_string());
+    }
+    line.strip_prefix("Doc-tests ")
+        .map(|krate| format!("doctests {}", krate.trim()))
+}
+
+/// Names for the `runs` test binaries of a run, in order. cargo announces
+/// each binary on stderr while libtest reports on stdout, so the two streams
+/// are matched by position; binaries are numbered when the counts disagree.
+fn binary_names(raw: &str, runs: usize) -> Vec<String> {
+    let labels: Vec<String> = raw.lines().filter_map(binary_label).collect();
+    if labels.len() == runs {
+        labels
+    } else {
+        (1..=runs).map(|i| format!("binary {i}")).collect()
+    }
+}
+
+/// Plain libtest output: counts from every `test result:` line, one per test
+/// binary, plus the failing tests' panics.
+fn parse_libtest(raw: &str) -> TestReport {
+    let mut report = TestReport::default();
+    let lines: Vec<&str> = raw.lines().collect();
+    let runs = lines
+        .iter()
+        .filter(|l| l.starts_with("test result:"))
+        .count();
+    let mut names = binary_names(raw, runs).into_iter();
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with("test result:") {
+            let suite = SuiteReport {
+                name: names.next().unwrap_or_default(),
+                passed: summary::count_of(line, "passed"),
+                failed: summary::count_of(line, "failed"),
+                skipped: summary::count_of(line, "ignored"),
+            };
+            report.passed += suite.passed;
+            report.failed += suite.failed;
+            report.skipped += suite.skipped;
+            if suite.passed + suite.failed + suite.skipped > 0 {
+                report.suites.push(suite);
+            }
+        } else if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" ----"))
+        {
+            let name = name.trim_end_matches(" stdout").trim_end_matches(" stderr");
+            let block = &lines[i + 1..];
+            let end = block
+                .iter()
+                .position(|l| l.starts_with("---- ") || *l == "failures:")
+                .unwrap_or(block.len());
+            report.failures.push(failure(name, &block[..end]));
+        }
+    }
+    report
+}
+
+/// A failing test, with the message and location of its panic when found.
+fn failure(name: &str, output: &[&str]) -> TestFailure {
+    let (message, loc) = match summary::parse_panic(output) {
+        Some((message, loc)) => (Some(message), loc),
+        None => (None, None),
+    };
+    TestFailure {
+        name: name.to_string(),
+        message,
+        file: loc.as_ref().map(|l| l.file.clone()),
+        line: loc.and_then(|l| l.line),
+    }
+}
+
+// ── libtest JSON (`-- -Z unstable-options --format json --report-time`) ──
+
+/// One line of libtest's JSON output.
+#[derive(Deserialize)]
+struct LibtestEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    name: Option<String>,
+    exec_time: Option<f64>,
+    stdout: Option<String>,
+    passed: Option<u32>,
+    failed: Option<u32>,
+    ignored: Option<u32>,
+}
+
+/// `None` when `raw` holds no libtest JSON events.
+fn parse_libtest_json(raw: &str) -> Option<TestReport> {
+    let events: Vec<LibtestEvent> = raw
+        .lines()
+        .filter(|l| l.starts_with('{'))
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect();
+    if events.is_empty() {
+        return None;
+    }
+    let is_suite_end =
+        |e: &LibtestEvent| e.kind == "suite" && matches!(e.event.as_str(), "ok" | "failed");
+    let runs = events.iter().filter(|e| is_suite_end(e)).count();
+    let mut names = binary_names(raw, runs).into_iter();
+    let mut report = TestReport::default();
+
+    for event in events {
+        match (event.kind.as_str(), event.event.as_str()) {
+            ("test", "ok" | "failed") => {
+                let name = event.name.unwrap_or_default();
+                if event.event == "failed" {
+                    let stdout = event.stdout.unwrap_or_default();
+                    let output: Vec<&str> = stdout.lines().collect();
+                    report.failures.push(failure(&name, &output));
+                }
+                if let Some(secs) = event.exec_time {
+                    report.slowest.push(TestTiming { name, secs });
+                }
+            }
+            ("suite", "ok" | "failed") => {
+                let suite = SuiteReport {
+                    name: names.next().unwrap_or_default(),
+                    passed: event.passed.unwrap_or(0),
+                    failed: event.failed.unwrap_or(0),
+                    skipped: event.ignored.unwrap_or(0),
+                };
+                report.passed += suite.passed;
+                report.failed += suite.failed;
+                report.skipped += suite.skipped;
+                if suite.passed + suite.failed + suite.skipped > 0 {
+                    report.suites.push(suite);
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(report)
+}
+
+// ── cargo nextest ──
+
+/// A nextest status line: `PASS [   0.004s] demo tests::a`, with retries
+/// reported as `TRY 2 PASS [ … ]`. Returns (status, seconds, binary, test).
+fn nextest_status(line: &str) -> Option<(&str, Option<f64>, &str, &str)> {
+    let (head, rest) = line.trim().split_once('[')?;
+    let mut status = head.trim();
+    if let Some(retry) = status.strip_prefix("TRY ") {
+        status = retry.split_once(' ')?.1.trim();
+    }
+    if status.is_empty() || !status.chars().all(|c| c.is_ascii_uppercase() || c == '-') {
+        return None;
+    }
+    let (time, rest) = rest.split_once(']')?;
+    let time = time.trim().trim_start_matches("> ");
+    let secs = match time {
+        "" => None,
+        time => Some(time.strip_suffix('s')?.parse().ok()?),
+    };
+    let (binary, test) = rest.trim().split_once(' ')?;
+    Some((status, secs, binary, test.trim()))
+}
+
+/// Final result of one test in a nextest run.
+enum NextestResult {
+    Passed,
+    Skipped,
+    Failed(TestFailure),
+}
+
+/// `None` unless `raw` looks like nextest output. Counts come from the final
+/// `Summary` line when present; the last status of each test wins, so a
+/// retried test that eventually passes is not reported as failing.
+fn parse_nextest(raw: &str) -> Option<TestReport> {
+    let lines: Vec<&str> = raw.lines().collect();
+    let mut report = TestReport::default();
+    // Tests in order of first appearance, with their latest result.
+    let mut tests: Vec<(&str, &str, NextestResult)> = Vec::new();
+    let mut totals = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("Summary [") {
+            totals = Some((
+                summary::count_of(trimmed, "passed"),
+                summary::count_of(trimmed, "failed"),
+                summary::count_of(trimmed, "skipped"),
+            ));
+            break;
+        }
+        let Some((status, secs, binary, test)) = nextest_status(line) else {
+            continue;
+        };
+        let result = match status {
+            "PASS" | "LEAK" | "FLAKY" => NextestResult::Passed,
+            "SKIP" => NextestResult::Skipped,
+            "SLOW" | "RETRY" | "START" | "SETUP" | "TERMINATING" | "CANCEL" => continue,
+            _ => {
+                let output = &lines[i + 1..];
+                let end = output
+                    .iter()
+                    .position(|l| {
+                        nextest_status(l).is_some() || l.trim_start().starts_with("Summary [")
+                    })
+                    .unwrap_or(output.len());
+                NextestResult::Failed(failure(test, &output[..end]))
+            }
+        };
+        if let Some(secs) = secs {
+            report.slowest.retain(|t| t.name != test);
+            report.slowest.push(TestTiming {
+                name: test.to_string(),
+                secs,
+            });
+        }
+        match tests
+            .iter_mut()
+            .find(|(b, t, _)| *b == binary && *t == test)
+        {
+            Some(entry) => entry.2 = result,
+            None => tests.push((binary, test, result)),
+        }
+    }
+    if tests.is_empty() && totals.is_none() {
+        return None;
+    }
+
+    for (binary, _, result) in tests {
+        let index = match report.suites.iter().position(|s| s.name == binary) {
+            Some(index) => index,
+            None => {
+                report.suites.push(SuiteReport {
+                    name: binary.to_string(),
+                    ..SuiteReport::default()
+                });
+                report.suites.len() - 1
+            }
+        };
+        let suite = &mut report.suites[index];
+        match result {
+            NextestResult::Passed => suite.passed += 1,
+            NextestResult::Skipped => suite.skipped += 1,
+            NextestResult::Failed(failure) => {
+                suite.failed += 1;
+                report.failures.push(failure);
+            }
+        }
+    }
+    let (passed, failed, skipped) = totals.unwrap_or_else(|| {
+        report.suites.iter().fold((0, 0, 0), |(p, f, s), suite| {
+            (p + suite.passed, f + suite.failed, s + suite.skipped)
+        })
+    });
+    report.passed = passed;
+    report.failed = failed;
+    report.skipped = skipped;
+    Some(report)
+}
+
+/// Diagnostics from `cargo build`/`check`/`clippy`, split by level.
+#[derive(Debug, Default, Serialize)]
+struct DiagnosticSummary {
+    errors: Vec<Diagnostic>,
+    warnings: Vec<Diagnostic>,
+}
+
+/// Parse human (`error[E0308]: …` + ` --> file:line:col`) and short
+/// (`file:line:col: warning: …`) diagnostics. Skips cargo's own summary lines.
+fn parse_diagnostics(raw: &str) -> DiagnosticSummary {
+    if let Some(diagnostics) = parse_json_diagnostics(raw) {
+        let (errors, warnings) = diagnostics.into_iter().partition(|d| d.level == "error");
+        return DiagnosticSummary { errors, warnings };
+    }
+
+    let mut summary = DiagnosticSummary::default();
+    let lines: Vec<&str> = raw.lines().collect();
+
+    for (i, line) in lines.iter().enumerate() {
+        let (loc, header) = match parse_location(line) {
+            Some((loc, rest)) => (Some(loc), rest),
+            None => (None, *line),
+        };
+        let Some((level, code, message)) = parse_diagnostic_header(header) else {
+            continue;
+        };
+        if message.starts_with("could not compile")
+            || message.starts_with("aborting due to")
+            || (message.contains("generated ") && message.contains(" warning"))
+        {
+            continue;
+        }
+
+        let mut diag = Diagnostic {
+            level: level.to_string(),
+            code: code.map(str::to_string),
+            message: message.to_string(),
+            ..Diagnostic::default()
+        };
+        let loc = loc.or_else(|| {
+            lines[i + 1..]
+                .iter()
+                .take(3)
+                .find_map(|l| l.trim_start().strip_prefix("--> "))
+                .and_then(parse_location)
+                .map(|(loc, _)| loc)
+        });
+        if let Some(loc) = loc {
+            diag = diag.at(loc);
+        }
+
+        if level == "error" {
+            summary.errors.push(diag);
+        } else {
+            summary.warnings.push(diag);
+        }
+    }
+    summary
+}
+
+/// Split `error[E0308]: msg` into (level, code, message).
+fn parse_diagnostic_header(line: &str) -> Option<(&str, Option<&str>, &str)> {
+    let level = ["error", "warning"]
+        .into_iter()
+        .find(|l| line.starts_with(l))?;
+    let rest = &line[level.len()..];
+    let (code, rest) = match rest.strip_prefix('[') {
+        Some(r) => {
+            let (code, after) = r.split_once(']')?;
+            (Some(code), after)
+        }
+        None => (None, rest),
+    };
+    let message = rest.strip_prefix(": ")?;
+    Some((level, code, message.trim()))
+}
+
+// ── --message-format=json ──
+
+/// One line of `cargo … --message-format=json`.
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    code: Option<RustcCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: u32,
+    column_start: u32,
+    is_primary: bool,
+    suggested_replacement: Option<String>,
+}
+
+/// Errors and warnings from cargo's JSON messages, deduplicated across
+/// targets (a lib and its test target report the same warning twice) and
+/// without rustc's own `aborting due to …` / `N warnings emitted` summaries.
+/// `None` when `raw` holds no cargo JSON messages at all.
+fn parse_json_diagnostics(raw: &str) -> Option<Vec<Diagnostic>> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut any = false;
+
+    for line in raw.lines().filter(|l| l.starts_with('{')) {
+        let Ok(msg) = serde_json::from_str::<CargoMessage>(line) else {
+            continue;
+        };
+        any = true;
+        if msg.reason != "compiler-message" {
+            continue;
+        }
+        let Some(d) = msg.message else { continue };
+        if !matches!(d.level.as_str(), "error" | "warning")
+            || (d.spans.is_empty() && is_rustc_summary(&d.message))
+        {
+            continue;
+        }
+
+        let primary = d.spans.iter().find(|s| s.is_primary);
+        let diag = Diagnostic {
+            level: d.level.clone(),
+            code: d.code.as_ref().map(|c| c.code.clone()),
+            message: d.message.clone(),
+            file: primary.map(|s| s.file_name.clone()),
+            line: primary.map(|s| s.line_start),
+            column: primary.map(|s| s.column_start),
+            suggestion: suggestion(&d),
+        };
+        if !diagnostics.contains(&diag) {
+            diagnostics.push(diag);
+        }
+    }
+    any.then_some(diagnostics)
+}
+
+fn is_rustc_summary(message: &str) -> bool {
+    message.starts_with("aborting due to")
+        || message.ends_with("warnings emitted")
+        || message.ends_with("warning emitted")
+}
+
+/// The first short, single-line replacement rustc suggests in a `help` child.
+fn suggestion(d: &RustcDiagnostic) -> Option<String> {
+    d.children
+        .iter()
+        .flat_map(|c| &c.spans)
+        .filter_map(|s| s.suggested_replacement.as_deref())
+        .find(|r| !r.contains('\n') && r.chars().count() <= SUGGESTION_LEN)
+        .map(str::to_string)
+}
+
+/// `E0308 45:12 mismatched types`: code and position when known; the file
+/// is the header the line is grouped under.
+fn diagnostic_line(d: &Diagnostic) -> String {
+    let mut line = String::new();
+    if let Some(code) = &d.code {
+        line.push_str(code);
+        line.push(' ');
+    }
+    if let Some(l) = d.line {
+        line.push_str(&l.to_string());
+        if let Some(c) = d.column {
+            line.push_str(&format!(":{c}"));
+        }
+        line.push(' ');
+    }
+    line.push_str(&d.message);
+    match &d.suggestion {
+        Some(s) if s.is_empty() => line.push_str(" → remove"),
+        Some(s) => line.push_str(&format!(" → `{s}`")),
+        None => {}
+    }
+    line
+}
+
+/// Render JSON diagnostics: errors, then warnings (capped to `max`), each
+/// grouped under a header per file, then the count per lint and cargo's own
+/// lines (`Finished`, `could not compile`, build script warnings).
+fn compress_json_diagnostics(
+    diagnostics: &[Diagnostic],
+    raw: &str,
+    max: usize,
+    ctx: &CompressCtx,
+) -> String {
+    let mut out = String::new();
+    for level in ["error", "warning"] {
+        let group: Vec<&Diagnostic> = diagnostics.iter().filter(|d| d.level == level).collect();
+        if group.is_empty() {
+            continue;
+        }
+        // Files in order of first appearance, diagnostics without one first.
+        let mut files: Vec<(Option<&str>, Vec<&Diagnostic>)> = Vec::new();
+        for d in &group {
+            let file = d.file.as_deref();
+            match files.iter_mut().find(|(f, _)| *f == file) {
+                Some((_, ds)) => ds.push(d),
+                None => files.push((file, vec![d])),
+            }
+        }
+        files.sort_by_key(|(f, _)| f.is_some());
+
+        let cap = if level == "error" { usize::MAX } else { max };
+        let mut shown = 0;
+        out.push_str(&format!("[{level}s: {}]\n", group.len()));
+        for (file, mut ds) in files {
+            if shown >= cap {
+                break;
+            }
+            ds.sort_by_key(|d| (d.line, d.column));
+            let indent = match file {
+                Some(file) => {
+                    out.push_str(&format!("  {file} ({})\n", ds.len()));
+                    "    "
+                }
+                None => "  ",
+            };
+            for d in ds.iter().take(cap - shown) {
+                out.push_str(&format!(
+                    "{indent}{}\n",
+                    cut(&diagnostic_line(d), ctx.max_line_len)
+                ));
+                shown += 1;
+            }
+        }
+        if group.len() > shown {
+            out.push_str(&format!("  … +{} more\n", group.len() - shown));
+        }
+    }
+
+    // Count per lint name (rustc lints and clippy::*), not per error code.
+    let mut lints: Vec<(&str, usize)> = Vec::new();
+    for code in diagnostics.iter().filter_map(|d| d.code.as_deref()) {
+        if is_error_code(code) {
+            continue;
+        }
+        match lints.iter_mut().find(|(c, _)| *c == code) {
+            Some((_, n)) => *n += 1,
+            None => lints.push((code, 1)),
+        }
+    }
+    if !lints.is_empty() {
+        lints.sort_by_key(|l| std::cmp::Reverse(l.1));
+        out.push_str(&format!("[lints: {}]\n", lints.len()));
+        for (code, n) in lints.iter().take(ctx.max_items) {
+            out.push_str(&format!("  {code} ×{n}\n"));
+        }
+        if lints.len() > ctx.max_items {
+            out.push_str(&format!("  … +{} more\n", lints.len() - ctx.max_items));
+        }
+    }
+
+    for line in raw.lines().filter(|l| !l.starts_with('{')) {
+        // Cargo's own status: `Finished`, `could not compile`, manifest errors,
+        // build script `cargo:warning=` lines, … but not its per-crate
+        // `generated N warnings` counts, which the sections above replace.
+        let trimmed = line.trim();
+        let generated = trimmed.contains(" generated ") && trimmed.contains(" warning");
+        if trimmed.starts_with("Finished")
+            || trimmed.starts_with("error")
+            || (trimmed.starts_with("warning") && !generated)
+        {
+            out.push_str(&format!("{trimmed}\n"));
+        }
+    }
+
+    if out.is_empty() {
+        return truncate(raw, ctx);
+    }
+    out
+}
+
+/// `E0308`-style compiler error codes, as opposed to lint names.
+fn is_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with('E') && code[1..].chars().all(|c| c.is_ascii_digit())
 }
 
 /// Compress `cargo test`: keep summary + failures only.
-fn compress_test(raw: &str) -> String {
+fn compress_test(raw: &str, ctx: &CompressCtx) -> String {
+    if let Some(report) = structured_report(raw) {
+        return render_report(&report, ctx);
+    }
+    let raw = junit::split(raw).0;
+
     let lines: Vec<&str> = raw.lines().collect();
-    let mut out = Vec::new();
-    let mut in_failure = false;
+    let mut out: Vec<String> = Vec::new();
+    // Lines of the failure block being read, from its `---- name stdout ----`
+    // header to the next header or the `failures:` list.
+    let mut block: Option<Vec<&str>> = None;
+    let runs = lines.iter().filter(|l| l.starts_with("running ")).count();
+    let mut names = binary_names(raw, runs).into_iter();
 
     for line in &lines {
-        if line.starts_with("---- ") && line.ends_with(" ----") {
-            in_failure = true;
+        let header = line.starts_with("---- ") && line.ends_with(" ----");
+        if (header || *line == "failures:" || line.starts_with("test result:"))
+            && let Some(block) = block.take()
+        {
+            push_failure_block(&mut out, &block, ctx);
         }
-        if in_failure {
-            out.push(*line);
-            if line.is_empty() {
-                in_failure = false;
-            }
+        if header {
+            block = Some(vec![line]);
+            continue;
         }
-        if (line.starts_with("test result:")
-            || line.starts_with("failures:")
-            || line.contains("FAILED")
-            || line.starts_with("running "))
-            && !out.contains(line)
+        if let Some(block) = &mut block {
+            block.push(line);
+            continue;
+        }
+        // Every binary has its own `running` / `test result:` pair; keep them
+        // all, except for binaries without tests.
+        if line.starts_with("running ") {
+            let name = names.next();
+            if *line != "running 0 tests" {
+                match name.filter(|_| runs > 1) {
+                    Some(name) => out.push(format!("{line} ({name})")),
+                    None => out.push(line.to_string()),
+                }
+            }
+        } else if line.starts_with("test result:") {
+            if !line.contains(" 0 passed; 0 failed; 0 ignored") {
+                out.push(line.to_string());
+            }
+        } else if (line.starts_with("failures:") || line.contains("FAILED"))
+            && !out.iter().any(|l| l == line)
         {
-            out.push(*line);
+            out.push(line.to_string());
         }
     }
 
+    if let Some(block) = block {
+        push_failure_block(&mut out, &block, ctx);
+    }
+
     if out.is_empty() {
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
 
-    let mut result = String::from("[cargo test]\n");
+    let report = parse_libtest(raw);
+    let mut result = if ran_suites(&report).count() > 1 {
+        report_header(&report)
+    } else {
+        String::from("[cargo test]\n")
+    };
     result.push_str(&out.join("\n"));
     result
 }
 
+/// A failure block without its blank lines (libtest puts one right after the
+/// header) and backtrace hint, capped at `max_block_lines`.
+fn push_failure_block(out: &mut Vec<String>, block: &[&str], ctx: &CompressCtx) {
+    let kept: Vec<&str> = block
+        .iter()
+        .copied()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with("note: run with `RUST_BACKTRACE"))
+        .collect();
+    let shown = kept.len().min(ctx.max_block_lines.max(1));
+    out.extend(kept[..shown].iter().map(|l| l.to_string()));
+    if kept.len() > shown {
+        out.push(format!("… +{} more lines", kept.len() - shown));
+    }
+}
+
+/// `[cargo test] 12 passed, 1 failed, 2 ignored (3 binaries)`.
+fn report_header(report: &TestReport) -> String {
+    let mut out = format!(
+        "[cargo test] {} passed, {} failed",
+        report.passed, report.failed
+    );
+    if report.skipped > 0 {
+        out.push_str(&format!(", {} ignored", report.skipped));
+    }
+    let binaries = ran_suites(report).count();
+    if binaries > 1 {
+        out.push_str(&format!(" ({binaries} binaries)"));
+    }
+    out.push('\n');
+    out
+}
+
+/// Binaries that ran at least one test.
+fn ran_suites(report: &TestReport) -> impl Iterator<Item = &SuiteReport> {
+    report
+        .suites
+        .iter()
+        .filter(|s| s.passed + s.failed + s.skipped > 0)
+}
+
+/// Render an exact report: counts, each failure with its panic message and
+/// location, results per binary and the slowest tests.
+fn render_report(report: &TestReport, ctx: &CompressCtx) -> String {
+    let mut out = report_header(report);
+
+    if !report.failures.is_empty() {
+        out.push_str(&format!("[failures: {}]\n", report.failures.len()));
+        for f in report.failures.iter().take(ctx.max_items) {
+            let at = match (&f.file, f.line) {
+                (Some(file), Some(line)) => format!(" ({file}:{line})"),
+                (Some(file), None) => format!(" ({file})"),
+                _ => String::new(),
+            };
+            out.push_str(&format!("  {}{at}\n", f.name));
+            for line in f
+                .message
+                .iter()
+                .flat_map(|m| m.lines())
+                .take(ctx.max_block_lines)
+            {
+                out.push_str(&format!("    {line}\n"));
+            }
+        }
+        if report.failures.len() > ctx.max_items {
+            out.push_str(&format!(
+                "  … +{} more\n",
+                report.failures.len() - ctx.max_items
+            ));
+        }
+    }
+
+    let suites: Vec<&SuiteReport> = ran_suites(report).collect();
+    if suites.len() > 1 {
+        out.push_str(&format!("[binaries: {}]\n", suites.len()));
+        for suite in suites.iter().take(ctx.max_items) {
+            out.push_str(&format!(
+                "  {}: {} passed, {} failed",
+                suite.name, suite.passed, suite.failed
+            ));
+            if suite.skipped > 0 {
+                out.push_str(&format!(", {} ignored", suite.skipped));
+            }
+            out.push('\n');
+        }
+        if suites.len() > ctx.max_items {
+            out.push_str(&format!("  … +{} more\n", suites.len() - ctx.max_items));
+        }
+    }
+
+    if !report.slowest.is_empty() {
+        out.push_str("[slowest]\n");
+        for t in &report.slowest {
+            out.push_str(&format!("  {:.3}s {}\n", t.secs, t.name));
+        }
+    }
+    out
+}
+
 /// Compress `cargo build`/`check`: keep errors + warnings summary.
-fn compress_build(raw: &str) -> String {
+fn compress_build(raw: &str, ctx: &CompressCtx) -> String {
+    if let Some(diagnostics) = parse_json_diagnostics(raw) {
+        return compress_json_diagnostics(&diagnostics, raw, ctx.max_hits, ctx);
+    }
+
     let mut errors: Vec<&str> = Vec::new();
     let mut warnings: Vec<&str> = Vec::new();
     let mut summary: Vec<&str> = Vec::new();
@@ -92,22 +829,26 @@ fn compress_build(raw: &str) -> String {
     }
     if !warnings.is_empty() {
         out.push_str(&format!("[warnings: {}]\n", warnings.len()));
-        for w in warnings.iter().take(10) {
+        for w in warnings.iter().take(ctx.max_hits) {
             out.push_str(&format!("  {w}\n"));
         }
-        if warnings.len() > 10 {
-            out.push_str(&format!("  … +{} more\n", warnings.len() - 10));
+        if warnings.len() > ctx.max_hits {
+            out.push_str(&format!("  … +{} more\n", warnings.len() - ctx.max_hits));
         }
     }
 
     if out.is_empty() {
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
     out
 }
 
 /// Compress `cargo clippy`: group diagnostics.
-fn compress_clippy(raw: &str) -> String {
+fn compress_clippy(raw: &str, ctx: &CompressCtx) -> String {
+    if let Some(diagnostics) = parse_json_diagnostics(raw) {
+        return compress_json_diagnostics(&diagnostics, raw, ctx.max_items, ctx);
+    }
+
     let mut lints: Vec<&str> = Vec::new();
 
     for line in raw.lines() {
@@ -117,21 +858,21 @@ fn compress_clippy(raw: &str) -> String {
     }
 
     if lints.is_empty() {
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
 
     let mut out = format!("[clippy: {} diagnostics]\n", lints.len());
-    for lint in lints.iter().take(30) {
+    for lint in lints.iter().take(ctx.max_items) {
         out.push_str(&format!("  {lint}\n"));
     }
-    if lints.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", lints.len() - 30));
+    if lints.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", lints.len() - ctx.max_items));
     }
     out
 }
 
 /// Compress `cargo fmt` — show reformatted files or confirm clean.
-fn compress_fmt(raw: &str) -> String {
+fn compress_fmt(raw: &str, ctx: &CompressCtx) -> String {
     if raw.trim().is_empty() {
         return "[cargo fmt] clean".into();
     }
@@ -143,17 +884,17 @@ fn compress_fmt(raw: &str) -> String {
 
     if !diffs.is_empty() {
         let mut out = format!("[cargo fmt] {} files need formatting\n", diffs.len());
-        for d in diffs.iter().take(20) {
+        for d in diffs.iter().take(ctx.max_block_lines) {
             out.push_str(&format!("  {d}\n"));
         }
         return out;
     }
 
-    truncate(raw)
+    truncate(raw, ctx)
 }
 
 /// Compress `cargo run` — keep program output, strip compilation noise.
-fn compress_run(raw: &str) -> String {
+fn compress_run(raw: &str, ctx: &CompressCtx) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     let mut out = Vec::new();
 
@@ -175,11 +916,11 @@ fn compress_run(raw: &str) -> String {
         return "[cargo run] ok".into();
     }
 
-    truncate(&out.join("\n"))
+    truncate(&out.join("\n"), ctx)
 }
 
 /// Compress `cargo bench` — keep results summary.
-fn compress_bench(raw: &str) -> String {
+fn compress_bench(raw: &str, ctx: &CompressCtx) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     let mut results = Vec::new();
     let mut summary: Option<&str> = None;
@@ -193,15 +934,15 @@ fn compress_bench(raw: &str) -> String {
     }
 
     if results.is_empty() {
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
 
     let mut out = format!("[cargo bench] {} benchmarks\n", results.len());
-    for r in results.iter().take(30) {
+    for r in results.iter().take(ctx.max_items) {
         out.push_str(&format!("  {r}\n"));
     }
-    if results.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", results.len() - 30));
+    if results.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", results.len() - ctx.max_items));
     }
     if let Some(s) = summary {
         out.push_str(&format!("{s}\n"));
@@ -210,7 +951,7 @@ fn compress_bench(raw: &str) -> String {
 }
 
 /// Compress `cargo doc` — just keep summary.
-fn compress_doc(raw: &str) -> String {
+fn compress_doc(raw: &str, ctx: &CompressCtx) -> String {
     let mut documenting = Vec::new();
     let mut finished: Option<&str> = None;
     let mut warnings = 0usize;
@@ -238,13 +979,13 @@ fn compress_doc(raw: &str) -> String {
     }
 
     if out.is_empty() {
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
     out
 }
 
 /// Compress `cargo add` / `cargo remove` — show dependency changes.
-fn compress_dep_change(sub: &str, raw: &str) -> String {
+fn compress_dep_change(sub: &str, raw: &str, ctx: &CompressCtx) -> String {
     let meaningful: Vec<&str> = raw
         .lines()
         .filter(|l| {
@@ -261,14 +1002,14 @@ fn compress_dep_change(sub: &str, raw: &str) -> String {
     }
 
     let mut out = format!("[cargo {sub}]\n");
-    for line in meaningful.iter().take(10) {
+    for line in meaningful.iter().take(ctx.max_hits) {
         out.push_str(&format!("  {}\n", line.trim()));
     }
     out
 }
 
 /// Compress `cargo update` — show updated packages.
-fn compress_update(raw: &str) -> String {
+fn compress_update(raw: &str, ctx: &CompressCtx) -> String {
     let updates: Vec<&str> = raw
         .lines()
         .filter(|l| {
@@ -283,15 +1024,15 @@ fn compress_update(raw: &str) -> String {
         if raw.trim().is_empty() {
             return "[cargo update] already up to date".into();
         }
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
 
     let mut out = format!("[cargo update] {} changes\n", updates.len());
-    for u in updates.iter().take(30) {
+    for u in updates.iter().take(ctx.max_items) {
         out.push_str(&format!("  {}\n", u.trim()));
     }
-    if updates.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", updates.len() - 30));
+    if updates.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", updates.len() - ctx.max_items));
     }
     out
 }
@@ -326,7 +1067,7 @@ fn compress_install(raw: &str) -> String {
 }
 
 /// Compress `cargo publish` — keep result.
-fn compress_publish(raw: &str) -> String {
+fn compress_publish(raw: &str, ctx: &CompressCtx) -> String {
     let meaningful: Vec<&str> = raw
         .lines()
         .filter(|l| {
@@ -341,7 +1082,7 @@ fn compress_publish(raw: &str) -> String {
         .collect();
 
     if meaningful.is_empty() {
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
 
     let mut out = String::from("[cargo publish]\n");
@@ -356,6 +1097,10 @@ mod tests {
     use super::*;
     use crate::compress::Compressor;
 
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
     // ── compress_test ──
 
     #[test]
@@ -370,7 +1115,7 @@ test e ... ok
 
 test result: ok. 5 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out
 ";
-        let result = compress_test(raw);
+        let result = compress_test(raw, &ctx());
         assert!(result.contains("[cargo test]"));
         assert!(result.contains("running 5 tests"));
         assert!(result.contains("test result: ok."));
@@ -394,26 +1139,331 @@ failures:
 
 test result: FAILED. 1 passed; 1 failed; 0 ignored
 ";
-        let result = compress_test(raw);
+        let result = compress_test(raw, &ctx());
         assert!(result.contains("[cargo test]"));
         assert!(result.contains("FAILED"));
         assert!(result.contains("---- bad_test stdout ----"));
         assert!(result.contains("assertion failed"));
     }
 
+    /// `cargo test` on rustc 1.95 with `RUST_BACKTRACE=0`.
+    const LIBTEST_FAILURES: &str = "\
+   Compiling demo v0.1.0 (/tmp/demo)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.21s
+     Running unittests src/lib.rs (target/debug/deps/demo-1c2d3e4f5a6b7c8d)
+
+running 3 tests
+test tests::adds ... ok
+test tests::overflows ... FAILED
+test tests::prints ... FAILED
+
+failures:
+
+---- tests::overflows stdout ----
+
+thread 'tests::overflows' (16843) panicked at src/lib.rs:11:22:
+assertion `left == right` failed
+  left: 4
+ right: 5
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+---- tests::prints stdout ----
+debug line
+
+thread 'tests::prints' (16844) panicked at src/lib.rs:14:43:
+boom
+
+
+failures:
+    tests::overflows
+    tests::prints
+
+test result: FAILED. 1 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+error: test failed, to rerun pass `--lib`
+";
+
+    #[test]
+    fn test_failure_blocks_span_blank_lines() {
+        let result = compress_test(LIBTEST_FAILURES, &ctx());
+        assert_eq!(
+            result,
+            "\
+[cargo test]
+running 3 tests
+test tests::overflows ... FAILED
+test tests::prints ... FAILED
+failures:
+---- tests::overflows stdout ----
+thread 'tests::overflows' (16843) panicked at src/lib.rs:11:22:
+assertion `left == right` failed
+  left: 4
+ right: 5
+---- tests::prints stdout ----
+debug line
+thread 'tests::prints' (16844) panicked at src/lib.rs:14:43:
+boom
+test result: FAILED. 1 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s"
+        );
+
+        let report = parse_test_report(LIBTEST_FAILURES);
+        assert_eq!((report.passed, report.failed), (1, 2));
+        assert_eq!(
+            report.failures[0].message.as_deref(),
+            Some("assertion `left == right` failed\n  left: 4\n right: 5")
+        );
+        assert_eq!(report.failures[0].line, Some(11));
+        assert_eq!(report.failures[1].message.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn test_long_failure_block_is_capped() {
+        let mut raw = String::from("running 1 test\n\nfailures:\n\n---- big stdout ----\n");
+        for i in 0..30 {
+            raw.push_str(&format!("line {i}\n"));
+        }
+        raw.push_str("\nfailures:\n    big\n\ntest result: FAILED. 0 passed; 1 failed\n");
+        let result = compress_test(&raw, &ctx());
+        assert!(result.contains("line 18\n… +11 more lines\n"));
+    }
+
     #[test]
     fn test_no_recognizable_output_fallback() {
         let raw = "some unrelated output\nno test keywords here\n";
-        let result = compress_test(raw);
+        let result = compress_test(raw, &ctx());
         assert!(result.contains("some unrelated output"));
     }
 
+    #[test]
+    fn test_report_counts_and_failures() {
+        let raw = "\
+running 2 tests
+test ok_test ... ok
+test bad_test ... FAILED
+
+failures:
+
+---- bad_test stdout ----
+thread 'bad_test' panicked at src/lib.rs:10:5:
+assertion failed: false
+
+failures:
+    bad_test
+
+test result: FAILED. 1 passed; 1 failed; 2 ignored; 0 measured
+
+running 3 tests
+test result: ok. 3 passed; 0 failed; 0 ignored; 0 measured
+";
+        let report = parse_test_report(raw);
+        assert_eq!(report.passed, 4);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.skipped, 2);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].name, "bad_test");
+        assert_eq!(
+            report.failures[0].message.as_deref(),
+            Some("assertion failed: false")
+        );
+    }
+
+    #[test]
+    fn test_report_old_panic_format() {
+        let raw = "---- t stdout ----\nthread 't' panicked at 'boom', src/lib.rs:1:1\n\n";
+        let report = parse_test_report(raw);
+        assert_eq!(report.failures[0].message.as_deref(), Some("boom"));
+        assert_eq!(report.failures[0].file.as_deref(), Some("src/lib.rs"));
+    }
+
+    #[test]
+    fn test_report_per_binary() {
+        let raw = "\
+     Running unittests src/lib.rs (target/debug/deps/demo-1a2b)
+
+running 2 tests
+test a ... ok
+test b ... FAILED
+
+failures:
+
+---- b stdout ----
+thread 'b' panicked at src/lib.rs:9:5:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+failures:
+    b
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured
+
+     Running tests/api.rs (target/debug/deps/api-3c4d)
+
+running 3 tests
+test result: ok. 3 passed; 0 failed; 1 ignored; 0 measured
+
+   Doc-tests demo
+
+running 0 tests
+test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured
+";
+        let report = parse_test_report(raw);
+        assert_eq!((report.passed, report.failed, report.skipped), (4, 1, 1));
+        assert_eq!(report.suites.len(), 2);
+        assert_eq!(report.suites[0].name, "unittests src/lib.rs");
+        assert_eq!(report.suites[1].name, "tests/api.rs");
+        let f = &report.failures[0];
+        assert_eq!(
+            f.message.as_deref(),
+            Some("assertion `left == right` failed\n  left: 1\n right: 2")
+        );
+        assert_eq!((f.file.as_deref(), f.line), (Some("src/lib.rs"), Some(9)));
+
+        let result = compress_test(raw, &ctx());
+        assert!(result.starts_with("[cargo test] 4 passed, 1 failed, 1 ignored (2 binaries)\n"));
+        assert!(result.contains("running 3 tests (tests/api.rs)"));
+        assert!(result.contains("test result: ok. 3 passed"));
+        assert!(!result.contains("running 0 tests"));
+    }
+
+    #[test]
+    fn test_report_binaries_from_stderr() {
+        // cargo's `Running` lines arrive on stderr, after libtest's stdout.
+        let raw = "\
+running 1 test
+test result: ok. 1 passed; 0 failed; 0 ignored
+
+running 2 tests
+test result: ok. 2 passed; 0 failed; 0 ignored
+
+     Running unittests src/lib.rs (target/debug/deps/demo-1a2b)
+     Running tests/api.rs (target/debug/deps/api-3c4d)
+";
+        let report = parse_test_report(raw);
+        assert_eq!(report.suites[0].name, "unittests src/lib.rs");
+        assert_eq!(report.suites[1].passed, 2);
+        assert!(compress_test(raw, &ctx()).contains("running 2 tests (tests/api.rs)"));
+    }
+
+    #[test]
+    fn test_libtest_json() {
+        let raw = r#"     Running unittests src/lib.rs (target/debug/deps/demo-1a2b)
+{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "tests::a" }
+{ "type": "test", "name": "tests::a", "event": "ok", "exec_time": 0.002 }
+{ "type": "test", "name": "tests::slow", "event": "ok", "exec_time": 1.5 }
+{ "type": "test", "name": "tests::b", "event": "failed", "exec_time": 0.01, "stdout": "\nthread 'tests::b' panicked at src/lib.rs:20:9:\nboom\nnote: run with `RUST_BACKTRACE=1`\n" }
+{ "type": "suite", "event": "failed", "passed": 2, "failed": 1, "ignored": 0, "measured": 0, "filtered_out": 0, "exec_time": 1.6 }
+"#;
+        let report = parse_test_report(raw);
+        assert_eq!((report.passed, report.failed), (2, 1));
+        assert_eq!(report.suites[0].name, "unittests src/lib.rs");
+        assert_eq!(report.failures[0].name, "tests::b");
+        assert_eq!(report.failures[0].message.as_deref(), Some("boom"));
+        assert_eq!(report.failures[0].line, Some(20));
+        assert_eq!(report.slowest[0].name, "tests::slow");
+
+        let result = compress_test(raw, &ctx());
+        assert!(result.starts_with("[cargo test] 2 passed, 1 failed\n[failures: 1]\n"));
+        assert!(result.contains("  tests::b (src/lib.rs:20)\n    boom\n"));
+        assert!(result.contains("[slowest]\n  1.500s tests::slow\n"));
+    }
+
+    #[test]
+    fn test_nextest_output() {
+        let raw = "\
+    Starting 4 tests across 2 binaries (1 test skipped)
+        PASS [   0.004s] demo tests::a
+        SKIP [         ] demo tests::c
+        FAIL [   0.105s] demo tests::b
+──── STDOUT:             demo tests::b
+
+running 1 test
+test tests::b ... FAILED
+──── STDERR:             demo tests::b
+thread 'tests::b' panicked at src/lib.rs:9:5:
+explicit panic
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+  TRY 1 FAIL [   0.020s] demo::api get
+  TRY 2 PASS [   0.011s] demo::api get
+────────────
+     Summary [   0.140s] 3 tests run: 2 passed (1 flaky), 1 failed, 1 skipped
+        FAIL [   0.105s] demo tests::b
+error: test run failed
+";
+        let report = parse_test_report(raw);
+        assert_eq!((report.passed, report.failed, report.skipped), (2, 1, 1));
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].name, "tests::b");
+        assert_eq!(
+            report.failures[0].message.as_deref(),
+            Some("explicit panic")
+        );
+        assert_eq!(report.suites.len(), 2);
+        assert_eq!(report.suites[1].passed, 1);
+
+        let result = compress_test(raw, &ctx());
+        assert!(result.starts_with("[cargo test] 2 passed, 1 failed, 1 ignored (2 binaries)"));
+        assert!(result.contains("[binaries: 2]\n  demo: 1 passed, 1 failed, 1 ignored\n"));
+        assert!(!result.contains("demo::api get\n"));
+    }
+
+    #[test]
+    fn test_summary_of_a_test_run_that_failed_to_build() {
+        let raw = "\
+   Compiling demo v0.1.0 (/tmp/demo)
+error[E0308]: mismatched types
+ --> src/lib.rs:3:5
+  |
+3 |     \"x\"
+  |     ^^^ expected `u32`, found `&str`
+
+For more information about this error, try `rustc --explain E0308`.
+error: could not compile `demo` (lib test) due to 1 previous error
+";
+        let v = CargoCompressor.summarize(raw, Some("test"), &ctx());
+        assert_eq!(v["errors"][0]["code"], "E0308");
+        assert_eq!(v["errors"][0]["file"], "src/lib.rs");
+        assert!(compress_test(raw, &ctx()).contains("E0308"));
+    }
+
+    #[test]
+    fn test_nextest_prefers_junit_report() {
+        let raw = "\
+        FAIL [   0.105s] demo tests::b
+     Summary [   0.140s] 1 test run: 0 passed, 1 failed, 0 skipped
+# cx.report
+<?xml version=\"1.0\"?>
+<testsuites><testsuite name=\"demo\"><testcase name=\"tests::b\" classname=\"demo\" time=\"0.105\"><failure>thread 'tests::b' panicked at src/lib.rs:9:5:
+explicit panic</failure></testcase></testsuite></testsuites>
+";
+        let report = parse_test_report(raw);
+        assert_eq!(report.failures[0].line, Some(9));
+        assert_eq!(report.slowest[0].secs, 0.105);
+    }
+
+    #[test]
+    fn test_xml_in_test_output_is_not_a_report() {
+        let raw = "\
+running 1 test
+<testsuite name=\"fixture\"><testcase name=\"x\"/></testsuite>
+test tests::renders_xml ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        let result = compress_test(raw, &ctx());
+        assert!(result.contains("test result: ok. 1 passed"));
+        assert!(!result.contains("fixture"));
+    }
+
     // ── compress_build ──
 
     #[test]
     fn test_build_success() {
         let raw = "   Compiling my-crate v0.1.0\n    Finished `dev` profile in 1.2s\n";
-        let result = compress_build(raw);
+        let result = compress_build(raw, &ctx());
         assert!(result.contains("Compiling my-crate v0.1.0"));
         assert!(result.contains("Finished"));
     }
@@ -426,7 +1476,7 @@ error[E0308]: mismatched types
   --> src/main.rs:5:10
 error: could not compile `my-crate`
 ";
-        let result = compress_build(raw);
+        let result = compress_build(raw, &ctx());
         assert!(result.contains("[errors: 2]"));
         assert!(result.contains("error[E0308]"));
         assert!(result.contains("could not compile"));
@@ -440,7 +1490,7 @@ warning: variable `x` is never used
 warning: function `foo` is never used
     Finished `dev` profile in 0.5s
 ";
-        let result = compress_build(raw);
+        let result = compress_build(raw, &ctx());
         assert!(result.contains("[warnings: 2]"));
         assert!(result.contains("Finished"));
     }
@@ -452,7 +1502,7 @@ warning: unused import: `std::io`
 warning: real problem here
     Finished `dev` profile in 0.5s
 ";
-        let result = compress_build(raw);
+        let result = compress_build(raw, &ctx());
         assert!(result.contains("[warnings: 1]"));
         assert!(result.contains("real problem"));
         assert!(!result.contains("unused import"));
@@ -465,7 +1515,7 @@ warning: real problem here
             raw.push_str(&format!("warning: lint {i}\n"));
         }
         raw.push_str("    Finished `dev` profile in 1.0s\n");
-        let result = compress_build(&raw);
+        let result = compress_build(&raw, &ctx());
         assert!(result.contains("[warnings: 15]"));
         assert!(result.contains("… +5 more"));
     }
@@ -473,10 +1523,41 @@ warning: real problem here
     #[test]
     fn test_build_empty_fallback() {
         let raw = "nothing recognizable here";
-        let result = compress_build(raw);
+        let result = compress_build(raw, &ctx());
         assert!(result.contains("nothing recognizable here"));
     }
 
+    #[test]
+    fn test_diagnostics_with_locations() {
+        let raw = "\
+   Compiling my-crate v0.1.0
+error[E0308]: mismatched types
+  --> src/main.rs:5:10
+   |
+warning: unused variable: `x`
+ --> src/lib.rs:2:9
+warning: `my-crate` (bin \"my-crate\") generated 1 warning
+error: could not compile `my-crate`
+";
+        let v = CargoCompressor.summarize(raw, Some("build"), &ctx());
+        assert_eq!(v["errors"].as_array().unwrap().len(), 1);
+        assert_eq!(v["errors"][0]["code"], "E0308");
+        assert_eq!(v["errors"][0]["message"], "mismatched types");
+        assert_eq!(v["errors"][0]["file"], "src/main.rs");
+        assert_eq!(v["errors"][0]["line"], 5);
+        assert_eq!(v["errors"][0]["column"], 10);
+        assert_eq!(v["warnings"].as_array().unwrap().len(), 1);
+        assert_eq!(v["warnings"][0]["file"], "src/lib.rs");
+    }
+
+    #[test]
+    fn test_diagnostics_short_format() {
+        let raw = "src/main.rs:10:5: warning: redundant clone\n";
+        let summary = parse_diagnostics(raw);
+        assert_eq!(summary.warnings[0].message, "redundant clone");
+        assert_eq!(summary.warnings[0].line, Some(10));
+    }
+
     // ── compress_clippy ──
 
     #[test]
@@ -489,7 +1570,7 @@ warning: redundant clone
 error: unused must_use
   --> src/utils.rs:3:1
 ";
-        let result = compress_clippy(raw);
+        let result = compress_clippy(raw, &ctx());
         assert!(result.contains("[clippy: 3 diagnostics]"));
         assert!(result.contains("warning: this could be simplified"));
         assert!(result.contains("warning: redundant clone"));
@@ -499,7 +1580,7 @@ error: unused must_use
     #[test]
     fn test_clippy_clean() {
         let raw = "    Checking my-crate v0.1.0\n    Finished `dev` profile in 0.3s\n";
-        let result = compress_clippy(raw);
+        let result = compress_clippy(raw, &ctx());
         assert!(!result.contains("[clippy:"));
         assert!(result.contains("Checking"));
     }
@@ -510,23 +1591,123 @@ error: unused must_use
         for i in 0..35 {
             raw.push_str(&format!("warning: lint number {i}\n"));
         }
-        let result = compress_clippy(&raw);
+        let result = compress_clippy(&raw, &ctx());
         assert!(result.contains("[clippy: 35 diagnostics]"));
         assert!(result.contains("… +5 more"));
     }
 
+    // ── --message-format=json ──
+
+    const JSON_BUILD: &str = r#"{"reason":"compiler-artifact","package_id":"dep 0.1.0","target":{"name":"dep"}}
+{"reason":"compiler-message","package_id":"demo 0.1.0","target":{"name":"demo"},"message":{"message":"unused import: `std::io`","code":{"code":"unused_imports","explanation":null},"level":"warning","spans":[{"file_name":"src/lib.rs","line_start":1,"column_start":5,"is_primary":true,"suggested_replacement":null}],"children":[{"message":"remove the unused import","code":null,"level":"help","spans":[{"file_name":"src/lib.rs","line_start":1,"column_start":1,"is_primary":true,"suggested_replacement":""}],"children":[]}]}}
+{"reason":"compiler-message","package_id":"demo 0.1.0","target":{"name":"demo"},"message":{"message":"mismatched types","code":{"code":"E0308","explanation":"..."},"level":"error","spans":[{"file_name":"src/main.rs","line_start":45,"column_start":12,"is_primary":true,"suggested_replacement":null}],"children":[]}}
+{"reason":"compiler-message","package_id":"demo 0.1.0","target":{"name":"demo"},"message":{"message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/lib.rs","line_start":9,"column_start":5,"is_primary":true,"suggested_replacement":null}],"children":[{"message":"remove `return`","code":null,"level":"help","spans":[{"file_name":"src/lib.rs","line_start":9,"column_start":5,"is_primary":true,"suggested_replacement":"x"}],"children":[]}]}}
+{"reason":"compiler-message","package_id":"demo 0.1.0","target":{"name":"demo","test":true},"message":{"message":"unused import: `std::io`","code":{"code":"unused_imports","explanation":null},"level":"warning","spans":[{"file_name":"src/lib.rs","line_start":1,"column_start":5,"is_primary":true,"suggested_replacement":null}],"children":[{"message":"remove the unused import","code":null,"level":"help","spans":[{"file_name":"src/lib.rs","line_start":1,"column_start":1,"is_primary":true,"suggested_replacement":""}],"children":[]}]}}
+{"reason":"compiler-message","package_id":"demo 0.1.0","target":{"name":"demo"},"message":{"message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/a.rs","line_start":3,"column_start":5,"is_primary":true,"suggested_replacement":null}],"children":[]}}
+{"reason":"compiler-message","package_id":"demo 0.1.0","target":{"name":"demo"},"message":{"message":"aborting due to 1 previous error; 3 warnings emitted","code":null,"level":"error","spans":[],"children":[]}}
+error: could not compile `demo` (lib) due to 1 previous error; 3 warnings emitted
+{"reason":"build-finished","success":false}
+"#;
+
+    #[test]
+    fn test_json_diagnostics_grouped_and_deduplicated() {
+        let result = compress_build(JSON_BUILD, &ctx());
+        assert_eq!(
+            result,
+            "\
+[errors: 1]
+  src/main.rs (1)
+    E0308 45:12 mismatched types
+[warnings: 3]
+  src/lib.rs (2)
+    unused_imports 1:5 unused import: `std::io` → remove
+    clippy::needless_return 9:5 unneeded `return` statement → `x`
+  src/a.rs (1)
+    clippy::needless_return 3:5 unneeded `return` statement
+[lints: 2]
+  clippy::needless_return ×2
+  unused_imports ×1
+error: could not compile `demo` (lib) due to 1 previous error; 3 warnings emitted
+"
+        );
+    }
+
+    #[test]
+    fn test_json_diagnostics_summary() {
+        let v = CargoCompressor.summarize(JSON_BUILD, Some("clippy"), &ctx());
+        assert_eq!(v["errors"].as_array().unwrap().len(), 1);
+        assert_eq!(v["errors"][0]["code"], "E0308");
+        assert_eq!(v["errors"][0]["line"], 45);
+        assert_eq!(v["warnings"].as_array().unwrap().len(), 3);
+        assert_eq!(v["warnings"][1]["suggestion"], "x");
+    }
+
+    #[test]
+    fn test_json_clean_build() {
+        let raw = "{\"reason\":\"compiler-artifact\",\"target\":{\"name\":\"demo\"}}\n    Finished `dev` profile [unoptimized] target(s) in 0.2s\n{\"reason\":\"build-finished\",\"success\":true}\n";
+        let result = CargoCompressor.compress(raw, Some("check"), &ctx());
+        assert_eq!(
+            result,
+            "Finished `dev` profile [unoptimized] target(s) in 0.2s\n"
+        );
+    }
+
+    #[test]
+    fn test_json_warnings_capped() {
+        let raw: String = (0..15)
+            .map(|i| format!("{{\"reason\":\"compiler-message\",\"message\":{{\"message\":\"lint {i}\",\"code\":{{\"code\":\"dead_code\"}},\"level\":\"warning\",\"spans\":[],\"children\":[]}}}}\n"))
+            .collect();
+        let result = compress_build(&raw, &ctx());
+        assert!(result.starts_with("[warnings: 15]\n  dead_code lint 0\n"));
+        assert!(result.contains("  … +5 more\n[lints: 1]\n  dead_code ×15\n"));
+    }
+
+    #[test]
+    fn test_json_groups_interleaved_files_and_keeps_build_script_warnings() {
+        let message = |file: &str, line: u32| {
+            format!(
+                "{{\"reason\":\"compiler-message\",\"message\":{{\"message\":\"unused\",\"code\":{{\"code\":\"dead_code\"}},\"level\":\"warning\",\"spans\":[{{\"file_name\":\"{file}\",\"line_start\":{line},\"column_start\":1,\"is_primary\":true}}],\"children\":[]}}}}\n"
+            )
+        };
+        let raw = [
+            "warning: demo@0.1.0: protoc not found, using bundled copy\n".to_string(),
+            message("src/b.rs", 7),
+            message("src/a.rs", 3),
+            message("src/b.rs", 2),
+            "warning: `demo` (lib) generated 3 warnings\n".to_string(),
+            "    Finished `dev` profile [unoptimized] target(s) in 0.4s\n".to_string(),
+        ]
+        .concat();
+        let result = compress_build(&raw, &ctx());
+        assert_eq!(
+            result,
+            "\
+[warnings: 3]
+  src/b.rs (2)
+    dead_code 2:1 unused
+    dead_code 7:1 unused
+  src/a.rs (1)
+    dead_code 3:1 unused
+[lints: 1]
+  dead_code ×3
+warning: demo@0.1.0: protoc not found, using bundled copy
+Finished `dev` profile [unoptimized] target(s) in 0.4s
+"
+        );
+    }
+
     // ── compress_fmt ──
 
     #[test]
     fn test_fmt_clean() {
-        let result = compress_fmt("");
+        let result = compress_fmt("", &ctx());
         assert_eq!(result, "[cargo fmt] clean");
     }
 
     #[test]
     fn test_fmt_with_diffs() {
         let raw = "Diff in /src/main.rs\nDiff in /src/lib.rs\n";
-        let result = compress_fmt(raw);
+        let result = compress_fmt(raw, &ctx());
         assert!(result.contains("[cargo fmt] 2 files need formatting"));
     }
 
@@ -541,7 +1722,7 @@ error: unused must_use
 Hello, world!
 result: 42
 ";
-        let result = compress_run(raw);
+        let result = compress_run(raw, &ctx());
         assert!(!result.contains("Compiling"));
         assert!(!result.contains("Finished"));
         assert!(!result.contains("Running"));
@@ -552,7 +1733,7 @@ result: 42
     #[test]
     fn test_run_empty_output() {
         let raw = "   Compiling x v0.1.0\n    Finished `dev` profile in 1s\n     Running `target/debug/x`\n";
-        let result = compress_run(raw);
+        let result = compress_run(raw, &ctx());
         assert_eq!(result, "[cargo run] ok");
     }
 
@@ -567,7 +1748,7 @@ test bench_mul ... bench:      200 ns/iter (+/- 10)
 
 test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
 ";
-        let result = compress_bench(raw);
+        let result = compress_bench(raw, &ctx());
         assert!(result.contains("[cargo bench] 2 benchmarks"));
         assert!(result.contains("bench_add"));
         assert!(result.contains("bench_mul"));
@@ -579,7 +1760,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     #[test]
     fn test_doc_success() {
         let raw = " Documenting my-crate v0.1.0\n    Finished `doc` profile in 2.0s\n";
-        let result = compress_doc(raw);
+        let result = compress_doc(raw, &ctx());
         assert!(result.contains("[cargo doc] 1 crates"));
         assert!(result.contains("Finished"));
     }
@@ -587,7 +1768,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     #[test]
     fn test_doc_with_warnings() {
         let raw = " Documenting my-crate v0.1.0\nwarning: missing docs\nwarning: broken link\n    Finished `doc` profile in 2.0s\n";
-        let result = compress_doc(raw);
+        let result = compress_doc(raw, &ctx());
         assert!(result.contains("[warnings: 2]"));
     }
 
@@ -596,7 +1777,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     #[test]
     fn test_cargo_add() {
         let raw = "    Adding serde v1.0.193 to dependencies\n      Features: +derive\n";
-        let result = compress_dep_change("add", raw);
+        let result = compress_dep_change("add", raw, &ctx());
         assert!(result.contains("[cargo add]"));
         assert!(result.contains("Adding serde"));
     }
@@ -604,7 +1785,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     #[test]
     fn test_cargo_remove() {
         let raw = "    Removing serde from dependencies\n";
-        let result = compress_dep_change("remove", raw);
+        let result = compress_dep_change("remove", raw, &ctx());
         assert!(result.contains("[cargo remove]"));
         assert!(result.contains("Removing serde"));
     }
@@ -619,7 +1800,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     Updating tokio v1.33.0 -> v1.35.0
     Adding new-dep v0.1.0
 ";
-        let result = compress_update(raw);
+        let result = compress_update(raw, &ctx());
         assert!(result.contains("[cargo update] 4 changes"));
         assert!(result.contains("serde"));
         assert!(result.contains("tokio"));
@@ -627,7 +1808,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
 
     #[test]
     fn test_update_already_up_to_date() {
-        let result = compress_update("");
+        let result = compress_update("", &ctx());
         assert_eq!(result, "[cargo update] already up to date");
     }
 
@@ -656,7 +1837,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
    Uploaded my-crate v0.1.0
    Published my-crate v0.1.0 at registry crates-io
 ";
-        let result = compress_publish(raw);
+        let result = compress_publish(raw, &ctx());
         assert!(result.contains("[cargo publish]"));
         assert!(result.contains("Uploading"));
         assert!(result.contains("Published"));
@@ -668,7 +1849,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_test() {
         let c = CargoCompressor;
         let raw = "running 1 tests\ntest a ... ok\n\ntest result: ok. 1 passed; 0 failed\n";
-        let result = c.compress(raw, Some("test"));
+        let result = c.compress(raw, Some("test"), &ctx());
         assert!(result.contains("[cargo test]"));
     }
 
@@ -676,7 +1857,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_nextest() {
         let c = CargoCompressor;
         let raw = "running 1 tests\ntest a ... ok\n\ntest result: ok. 1 passed; 0 failed\n";
-        let result = c.compress(raw, Some("nextest"));
+        let result = c.compress(raw, Some("nextest"), &ctx());
         assert!(result.contains("[cargo test]"));
     }
 
@@ -684,7 +1865,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_build() {
         let c = CargoCompressor;
         let raw = "   Compiling x v0.1.0\n    Finished `dev` profile in 1s\n";
-        let result = c.compress(raw, Some("build"));
+        let result = c.compress(raw, Some("build"), &ctx());
         assert!(result.contains("Compiling"));
     }
 
@@ -692,14 +1873,14 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_clippy() {
         let c = CargoCompressor;
         let raw = "warning: something\n";
-        let result = c.compress(raw, Some("clippy"));
+        let result = c.compress(raw, Some("clippy"), &ctx());
         assert!(result.contains("[clippy: 1 diagnostics]"));
     }
 
     #[test]
     fn test_trait_dispatches_fmt() {
         let c = CargoCompressor;
-        let result = c.compress("", Some("fmt"));
+        let result = c.compress("", Some("fmt"), &ctx());
         assert!(result.contains("[cargo fmt] clean"));
     }
 
@@ -707,7 +1888,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_run() {
         let c = CargoCompressor;
         let raw = "Hello, world!\n";
-        let result = c.compress(raw, Some("run"));
+        let result = c.compress(raw, Some("run"), &ctx());
         assert!(result.contains("Hello, world!"));
     }
 
@@ -715,7 +1896,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_bench() {
         let c = CargoCompressor;
         let raw = "test b ... bench:  100 ns/iter (+/- 5)\ntest result: ok. 0 passed; 0 failed; 0 ignored; 1 measured\n";
-        let result = c.compress(raw, Some("bench"));
+        let result = c.compress(raw, Some("bench"), &ctx());
         assert!(result.contains("[cargo bench]"));
     }
 
@@ -723,7 +1904,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_doc() {
         let c = CargoCompressor;
         let raw = " Documenting x v0.1.0\n    Finished `doc` profile in 1s\n";
-        let result = c.compress(raw, Some("doc"));
+        let result = c.compress(raw, Some("doc"), &ctx());
         assert!(result.contains("[cargo doc]"));
     }
 
@@ -731,14 +1912,14 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_add() {
         let c = CargoCompressor;
         let raw = "    Adding serde v1.0 to dependencies\n";
-        let result = c.compress(raw, Some("add"));
+        let result = c.compress(raw, Some("add"), &ctx());
         assert!(result.contains("[cargo add]"));
     }
 
     #[test]
     fn test_trait_dispatches_update() {
         let c = CargoCompressor;
-        let result = c.compress("", Some("update"));
+        let result = c.compress("", Some("update"), &ctx());
         assert!(result.contains("[cargo update]"));
     }
 
@@ -746,7 +1927,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_install() {
         let c = CargoCompressor;
         let raw = "  Installing /home/user/.cargo/bin/x\n";
-        let result = c.compress(raw, Some("install"));
+        let result = c.compress(raw, Some("install"), &ctx());
         assert!(result.contains("[cargo install]"));
     }
 
@@ -754,7 +1935,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_dispatches_publish() {
         let c = CargoCompressor;
         let raw = "   Uploading x v0.1.0\n";
-        let result = c.compress(raw, Some("publish"));
+        let result = c.compress(raw, Some("publish"), &ctx());
         assert!(result.contains("[cargo publish]"));
     }
 
@@ -762,7 +1943,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_fallback() {
         let c = CargoCompressor;
         let raw = "some random output";
-        let result = c.compress(raw, Some("tree"));
+        let result = c.compress(raw, Some("tree"), &ctx());
         assert!(result.contains("some random output"));
     }
 
@@ -770,7 +1951,7 @@ test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured
     fn test_trait_none_sub() {
         let c = CargoCompressor;
         let raw = "fallback output";
-        let result = c.compress(raw, None);
+        let result = c.compress(raw, None, &ctx());
         assert!(result.contains("fallback output"));
     }
 }
@@ -1,22 +1,86 @@
-use super::Compressor;
 use super::truncate::{dedup_lines, truncate};
+use super::{CompressCtx, Compressor, summary};
+use serde::Serialize;
+use serde_json::{Value, json};
 
 /// Pure compressor for docker / kubectl output.
 pub struct DockerCompressor;
 
 impl Compressor for DockerCompressor {
-    fn compress(&self, raw: &str, sub: Option<&str>) -> String {
+    fn compress(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> String {
         match sub.unwrap_or("") {
-            "ps" => compress_ps(raw),
-            "images" => compress_images(raw),
-            "logs" => dedup_lines(raw),
-            _ => truncate(raw),
+            "ps" => compress_ps(raw, ctx),
+            "images" => compress_images(raw, ctx),
+            "logs" => dedup_lines(raw, ctx),
+            _ => truncate(raw, ctx),
         }
     }
+
+    fn summarize(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> Value {
+        match sub.unwrap_or("") {
+            "ps" | "images" => json!(parse_table(raw)),
+            _ => summary::lines(&self.compress(raw, sub, ctx)),
+        }
+    }
+}
+
+/// A docker table listing: column names + one object per row.
+#[derive(Debug, Default, Serialize)]
+struct TableSummary {
+    count: usize,
+    rows: Vec<serde_json::Map<String, Value>>,
+}
+
+/// Split docker's column-aligned output using the header's column offsets.
+fn parse_table(raw: &str) -> TableSummary {
+    let mut lines = raw.lines().filter(|l| !l.trim().is_empty());
+    let Some(header) = lines.next() else {
+        return TableSummary::default();
+    };
+
+    // Columns start where a header word follows 2+ spaces (or at 0).
+    // Offsets are in chars: docker aligns columns by character, not byte.
+    let header: Vec<char> = header.chars().collect();
+    let mut starts = vec![0];
+    for i in 2..header.len() {
+        if header[i] != ' ' && header[i - 1] == ' ' && header[i - 2] == ' ' {
+            starts.push(i);
+        }
+    }
+    let column = |chars: &[char], i: usize| -> String {
+        let start = starts[i].min(chars.len());
+        let end = starts
+            .get(i + 1)
+            .map_or(chars.len(), |&e| e.min(chars.len()));
+        chars[start..end]
+            .iter()
+            .collect::<String>()
+            .trim()
+            .to_string()
+    };
+    let names: Vec<String> = (0..starts.len())
+        .map(|i| column(&header, i).to_lowercase().replace(' ', "_"))
+        .collect();
+
+    let rows: Vec<_> = lines
+        .map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            let mut row = serde_json::Map::new();
+            for (i, name) in names.iter().enumerate() {
+                row.insert(name.clone(), Value::String(column(&chars, i)));
+            }
+            row
+        })
+        .collect();
+
+    TableSummary {
+        count: rows.len(),
+        rows,
+    }
 }
 
 /// Compress `docker ps` — keep header + compact rows.
-fn compress_ps(raw: &str) -> String {
+fn compress_ps(raw: &str, ctx: &CompressCtx) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     if lines.is_empty() {
         return "[docker ps] no containers".into();
@@ -27,17 +91,17 @@ fn compress_ps(raw: &str) -> String {
     if let Some(header) = lines.first() {
         out.push_str(&format!("{header}\n"));
     }
-    for line in lines.iter().skip(1).take(30) {
+    for line in lines.iter().skip(1).take(ctx.max_items) {
         out.push_str(&format!("{line}\n"));
     }
-    if lines.len() > 31 {
-        out.push_str(&format!("  … +{} more\n", lines.len() - 31));
+    if lines.len() > ctx.max_items + 1 {
+        out.push_str(&format!("  … +{} more\n", lines.len() - ctx.max_items - 1));
     }
     out
 }
 
 /// Compress `docker images` — similar approach.
-fn compress_images(raw: &str) -> String {
+fn compress_images(raw: &str, ctx: &CompressCtx) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     if lines.is_empty() {
         return "[docker images] none".into();
@@ -47,11 +111,11 @@ fn compress_images(raw: &str) -> String {
     if let Some(header) = lines.first() {
         out.push_str(&format!("{header}\n"));
     }
-    for line in lines.iter().skip(1).take(30) {
+    for line in lines.iter().skip(1).take(ctx.max_items) {
         out.push_str(&format!("{line}\n"));
     }
-    if lines.len() > 31 {
-        out.push_str(&format!("  … +{} more\n", lines.len() - 31));
+    if lines.len() > ctx.max_items + 1 {
+        out.push_str(&format!("  … +{} more\n", lines.len() - ctx.max_items - 1));
     }
     out
 }
@@ -61,11 +125,15 @@ mod tests {
     use super::*;
     use crate::compress::Compressor;
 
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
     // ── compress_ps ──
 
     #[test]
     fn test_ps_empty() {
-        let result = compress_ps("");
+        let result = compress_ps("", &ctx());
         assert_eq!(result, "[docker ps] no containers");
     }
 
@@ -76,7 +144,7 @@ CONTAINER ID   IMAGE     COMMAND   STATUS
 abc123         nginx     nginx     Up 2 hours
 def456         redis     redis     Up 5 min
 ";
-        let result = compress_ps(raw);
+        let result = compress_ps(raw, &ctx());
         assert!(result.contains("[containers: 2]"));
         assert!(result.contains("CONTAINER ID"));
         assert!(result.contains("abc123"));
@@ -86,7 +154,7 @@ def456         redis     redis     Up 5 min
     #[test]
     fn test_ps_header_only() {
         let raw = "CONTAINER ID   IMAGE     COMMAND   STATUS\n";
-        let result = compress_ps(raw);
+        let result = compress_ps(raw, &ctx());
         assert!(result.contains("[containers: 0]"));
         assert!(result.contains("CONTAINER ID"));
     }
@@ -97,7 +165,7 @@ def456         redis     redis     Up 5 min
         for i in 0..35 {
             raw.push_str(&format!("container_{i}  image  cmd  Up\n"));
         }
-        let result = compress_ps(&raw);
+        let result = compress_ps(&raw, &ctx());
         // 36 lines total (1 header + 35 data), show header + 30 data = 31
         assert!(result.contains("[containers: 35]"));
         assert!(result.contains("… +5 more"));
@@ -110,7 +178,7 @@ def456         redis     redis     Up 5 min
 
     #[test]
     fn test_images_empty() {
-        let result = compress_images("");
+        let result = compress_images("", &ctx());
         assert_eq!(result, "[docker images] none");
     }
 
@@ -121,13 +189,41 @@ REPOSITORY   TAG       IMAGE ID       SIZE
 nginx        latest    abc123         150MB
 redis        7.0       def456         120MB
 ";
-        let result = compress_images(raw);
+        let result = compress_images(raw, &ctx());
         assert!(result.contains("[images: 2]"));
         assert!(result.contains("REPOSITORY"));
         assert!(result.contains("nginx"));
         assert!(result.contains("redis"));
     }
 
+    #[test]
+    fn test_ps_summary_rows() {
+        let raw = "\
+CONTAINER ID   IMAGE     STATUS
+abc123         nginx     Up 2 hours
+def456         redis     Exited (0)
+";
+        let v = DockerCompressor.summarize(raw, Some("ps"), &ctx());
+        assert_eq!(v["count"], 2);
+        assert_eq!(v["rows"][0]["container_id"], "abc123");
+        assert_eq!(v["rows"][0]["status"], "Up 2 hours");
+        assert_eq!(v["rows"][1]["image"], "redis");
+    }
+
+    #[test]
+    fn test_summary_non_ascii_cells_keep_alignment() {
+        let raw = "\
+CONTAINER ID   NAMES     STATUS
+abc123         café      Up 2 hours
+def456         日本        Exited (0)
+";
+        let v = DockerCompressor.summarize(raw, Some("ps"), &ctx());
+        assert_eq!(v["rows"][0]["names"], "café");
+        assert_eq!(v["rows"][0]["status"], "Up 2 hours");
+        assert_eq!(v["rows"][1]["names"], "日本");
+        assert_eq!(v["rows"][1]["status"], "Exited (0)");
+    }
+
     // ── logs dedup ──
 
     #[test]
@@ -140,7 +236,7 @@ redis        7.0       def456         120MB
 [INFO] Request handled
 [INFO] Shutting down
 ";
-        let result = c.compress(raw, Some("logs"));
+        let result = c.compress(raw, Some("logs"), &ctx());
         assert!(result.contains("Request handled  (×3)"));
         assert!(result.contains("Starting server"));
         assert!(result.contains("Shutting down"));
@@ -151,14 +247,14 @@ redis        7.0       def456         120MB
     #[test]
     fn test_trait_dispatches_ps() {
         let c = DockerCompressor;
-        let result = c.compress("", Some("ps"));
+        let result = c.compress("", Some("ps"), &ctx());
         assert!(result.contains("[docker ps] no containers"));
     }
 
     #[test]
     fn test_trait_dispatches_images() {
         let c = DockerCompressor;
-        let result = c.compress("", Some("images"));
+        let result = c.compress("", Some("images"), &ctx());
         assert!(result.contains("[docker images] none"));
     }
 
@@ -166,14 +262,14 @@ redis        7.0       def456         120MB
     fn test_trait_fallback() {
         let c = DockerCompressor;
         let raw = "some docker output";
-        let result = c.compress(raw, Some("inspect"));
+        let result = c.compress(raw, Some("inspect"), &ctx());
         assert!(result.contains("some docker output"));
     }
 
     #[test]
     fn test_trait_none_sub() {
         let c = DockerCompressor;
-        let result = c.compress("fallback", None);
+        let result = c.compress("fallback", None, &ctx());
         assert!(result.contains("fallback"));
     }
 }
@@ -1,12 +1,15 @@
-use super::Compressor;
-use super::truncate::truncate;
+use super::truncate::{Strategy, truncate_by};
+use super::{CompressCtx, Compressor};
 
 /// Fallback compressor: just truncate.
+/// Keeps head + tail unless configured otherwise: the end of an arbitrary
+/// command's output (final error, summary) is usually what matters.
 pub struct GenericCompressor;
 
 impl Compressor for GenericCompressor {
-    fn compress(&self, raw: &str, _sub: Option<&str>) -> String {
-        truncate(raw)
+    fn compress(&self, raw: &str, _sub: Option<&str>, ctx: &CompressCtx) -> String {
+        let strategy = ctx.truncate.unwrap_or(Strategy::HeadTail);
+        truncate_by(raw, ctx.max_lines, ctx.max_line_len, strategy)
     }
 }
 
@@ -15,11 +18,15 @@ mod tests {
     use super::*;
     use crate::compress::Compressor;
 
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
     #[test]
     fn test_short_passthrough() {
         let c = GenericCompressor;
         let raw = "hello world\nsecond line";
-        let result = c.compress(raw, None);
+        let result = c.compress(raw, None, &ctx());
         assert_eq!(result, "hello world\nsecond line");
     }
 
@@ -30,7 +37,23 @@ mod tests {
             .map(|i| format!("line {i}"))
             .collect::<Vec<_>>()
             .join("\n");
-        let result = c.compress(&raw, None);
+        let result = c.compress(&raw, None, &ctx());
+        assert!(result.contains("[cx] … 50 lines omitted (200 total)"));
+        assert!(result.contains("line 0\n"));
+        assert!(result.ends_with("line 199"));
+    }
+
+    #[test]
+    fn test_configured_strategy_wins() {
+        let raw = (0..200)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let ctx = CompressCtx {
+            truncate: Some(Strategy::Head),
+            ..ctx()
+        };
+        let result = GenericCompressor.compress(&raw, None, &ctx);
         assert!(result.contains("200 lines total, showing first 150"));
     }
 
@@ -38,15 +61,15 @@ mod tests {
     fn test_ignores_sub() {
         let c = GenericCompressor;
         let raw = "test";
-        let a = c.compress(raw, None);
-        let b = c.compress(raw, Some("anything"));
+        let a = c.compress(raw, None, &ctx());
+        let b = c.compress(raw, Some("anything"), &ctx());
         assert_eq!(a, b);
     }
 
     #[test]
     fn test_empty_input() {
         let c = GenericCompressor;
-        let result = c.compress("", None);
+        let result = c.compress("", None, &ctx());
         assert_eq!(result, "");
     }
 }
@@ -1,51 +1,282 @@
-use super::Compressor;
-use super::truncate::truncate;
+use super::truncate::{Strategy, cut, truncate, truncate_by};
+use super::{CompressCtx, Compressor, summary};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// Blame lines shown by default (still capped by `max_lines`).
+const BLAME_LINES: usize = 80;
+/// Characters kept per blame line (still capped by `max_line_len`).
+const BLAME_LINE_LEN: usize = 120;
+/// Untracked files in one directory beyond which it is listed as `dir/ (N files)`.
+const UNTRACKED_DIR_FILES: usize = 5;
+/// Lock files whose hunks are collapsed to a one-line note.
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+    "poetry.lock",
+    "uv.lock",
+    "Pipfile.lock",
+    "go.sum",
+    "Gemfile.lock",
+    "composer.lock",
+];
 
 /// Pure compressor for git command output.
 pub struct GitCompressor;
 
 impl Compressor for GitCompressor {
-    fn compress(&self, raw: &str, sub: Option<&str>) -> String {
+    fn compress(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> String {
         match sub.unwrap_or("") {
-            "status" => compress_status(raw),
+            "status" => compress_status(raw, ctx),
+            // `-s` / `--porcelain` (v1) / `-z`: compact already, passed through.
+            "status-short" => truncate(raw, ctx),
             "diff" => compress_diff(raw),
-            "log" => truncate(raw),
+            "patch" => compress_patch(raw, ctx),
+            "log" => compress_log(raw, false, ctx),
+            "log-author" => compress_log(raw, true, ctx),
             "push" | "pull" | "fetch" => compress_transfer(sub.unwrap_or(""), raw),
             "add" | "commit" | "reset" | "restore" | "rm" | "mv" => {
                 compress_write_op(sub.unwrap_or(""), raw)
             }
-            "branch" => compress_branch(raw),
-            "tag" => compress_tag(raw),
-            "stash" => compress_stash(sub.unwrap_or(""), raw),
-            "merge" | "rebase" | "cherry-pick" => compress_merge_like(sub.unwrap_or(""), raw),
+            "branch" => compress_branch(raw, ctx),
+            "tag" => compress_tag(raw, ctx),
+            "stash" => compress_stash(sub.unwrap_or(""), raw, ctx),
+            "merge" | "rebase" | "cherry-pick" | "revert" => {
+                let sub = sub.unwrap_or("");
+                match raw.find(OPERATION_HEADER) {
+                    Some(at) => compress_stopped(sub, &raw[..at], &raw[at..], ctx),
+                    None => compress_merge_like(sub, raw),
+                }
+            }
             "checkout" | "switch" => compress_checkout(sub.unwrap_or(""), raw),
-            "remote" => compress_remote(raw),
-            "blame" => compress_blame(raw),
-            "show" => truncate(raw),
-            "clean" => compress_clean(raw),
+            "remote" => compress_remote(raw, ctx),
+            "blame" => compress_blame(raw, ctx),
+            "show" => compress_show(raw, ctx),
+            "show-file" => compress_file_content(raw, ctx),
+            "clean" => compress_clean(raw, ctx),
             "clone" => compress_transfer("clone", raw),
             "init" => compress_write_op("init", raw),
-            _ => truncate(raw),
+            _ => truncate(raw, ctx),
+        }
+    }
+
+    fn summarize(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> Value {
+        match sub.unwrap_or("") {
+            "status" => json!(parse_status(raw)),
+            "diff" | "patch" => json!(parse_diff(raw)),
+            "show" => match parse_show(raw) {
+                Some(show) => json!(show),
+                None => summary::lines(&self.compress(raw, sub, ctx)),
+            },
+            "log" | "log-author" => match parse_log(raw) {
+                Some(commits) => json!({ "commits": commits }),
+                None => summary::lines(&self.compress(raw, sub, ctx)),
+            },
+            _ => summary::lines(&self.compress(raw, sub, ctx)),
         }
     }
 }
 
-/// Compress `git status` into a structured summary.
-fn compress_status(raw: &str) -> String {
+/// Parsed `git status`.
+#[derive(Debug, Default, Serialize)]
+struct StatusSummary<'a> {
+    branch: String,
+    /// The `Your branch is …` line of human-readable status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracking: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upstream: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ahead: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    behind: Option<u32>,
+    staged: Vec<String>,
+    modified: Vec<String>,
+    untracked: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    conflicted: Vec<String>,
+    /// `old → new`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    renamed: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    submodules: Vec<String>,
+}
+
+impl StatusSummary<'_> {
+    fn is_clean(&self) -> bool {
+        self.staged.is_empty()
+            && self.modified.is_empty()
+            && self.untracked.is_empty()
+            && self.conflicted.is_empty()
+            && self.renamed.is_empty()
+            && self.submodules.is_empty()
+    }
+}
+
+/// Whether `raw` is `git status --porcelain=v2` output.
+fn is_porcelain_v2(raw: &str) -> bool {
+    raw.lines().any(|l| !l.is_empty())
+        && raw.lines().filter(|l| !l.is_empty()).all(|l| {
+            ["# ", "1 ", "2 ", "u ", "? ", "! "]
+                .iter()
+                .any(|p| l.starts_with(p))
+        })
+}
+
+fn parse_status(raw: &str) -> StatusSummary<'_> {
+    if is_porcelain_v2(raw) {
+        parse_porcelain_v2(raw)
+    } else {
+        parse_status_text(raw)
+    }
+}
+
+/// Parse `git status --porcelain=v2 --branch`: stable across git versions
+/// and locales, and explicit about renames, conflicts and submodules.
+fn parse_porcelain_v2(raw: &str) -> StatusSummary<'_> {
+    let mut summary = StatusSummary::default();
+    let mut oid = None;
+
+    for line in raw.lines() {
+        if let Some(header) = line.strip_prefix("# branch.") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "oid" => oid = Some(value),
+                "head" => summary.branch = value.to_string(),
+                "upstream" => summary.upstream = Some(value),
+                "ab" => {
+                    let mut counts = value.split(' ');
+                    let mut next = |sign: char| {
+                        counts
+                            .next()
+                            .and_then(|c| c.strip_prefix(sign))
+                            .and_then(|c| c.parse().ok())
+                    };
+                    summary.ahead = next('+');
+                    summary.behind = next('-');
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("? ") {
+            summary.untracked.push(path);
+            continue;
+        }
+
+        let kind = line.chars().next().unwrap_or(' ');
+        // Fields before the path: 8 for ordinary, 9 for renames, 10 for conflicts.
+        let fields = match kind {
+            '1' => 8,
+            '2' => 9,
+            'u' => 10,
+            _ => continue,
+        };
+        let parts: Vec<&str> = line.splitn(fields + 1, ' ').collect();
+        let (Some(xy), Some(sub), Some(path)) = (parts.get(1), parts.get(2), parts.get(fields))
+        else {
+            continue;
+        };
+        let mut xy = xy.chars();
+        let (x, y) = (xy.next().unwrap_or('.'), xy.next().unwrap_or('.'));
+
+        if kind == 'u' {
+            summary
+                .conflicted
+                .push(format!("{path} ({})", conflict_kind(x, y)));
+        } else if let Some(state) = submodule_state(sub) {
+            summary.submodules.push(format!("{path} ({state})"));
+        } else if kind == '2' {
+            // `path<TAB>origPath`.
+            let (new, old) = path.split_once('\t').unwrap_or((path, "?"));
+            summary.renamed.push(format!("{old} → {new}"));
+            if y != '.' {
+                summary.modified.push(change_entry(new, y));
+            }
+        } else {
+            if x != '.' {
+                summary.staged.push(change_entry(path, x));
+            }
+            if y != '.' {
+                summary.modified.push(change_entry(path, y));
+            }
+        }
+    }
+
+    if summary.branch.is_empty() || summary.branch == "(detached)" {
+        summary.branch = match oid.filter(|o| *o != "(initial)") {
+            Some(oid) => format!("(detached at {})", &oid[..oid.len().min(7)]),
+            None => "(detached)".into(),
+        };
+    }
+    summary
+}
+
+/// `src/lib.rs`, with the change spelled out unless it is a modification.
+fn change_entry(path: &str, code: char) -> String {
+    match code {
+        'A' => format!("{path} (new)"),
+        'D' => format!("{path} (deleted)"),
+        'T' => format!("{path} (type changed)"),
+        _ => path.to_string(),
+    }
+}
+
+/// What both sides did to an unmerged path, in `git status` wording.
+fn conflict_kind(x: char, y: char) -> &'static str {
+    match (x, y) {
+        ('D', 'D') => "both deleted",
+        ('A', 'U') => "added by us",
+        ('U', 'D') => "deleted by them",
+        ('U', 'A') => "added by them",
+        ('D', 'U') => "deleted by us",
+        ('A', 'A') => "both added",
+        _ => "both modified",
+    }
+}
+
+/// `S<c><m><u>` submodule field → what changed inside it; `None` for files.
+fn submodule_state(field: &str) -> Option<String> {
+    let flags: Vec<char> = field.strip_prefix('S')?.chars().collect();
+    let states: Vec<&str> = [
+        (flags.first(), 'C', "new commits"),
+        (flags.get(1), 'M', "modified content"),
+        (flags.get(2), 'U', "untracked content"),
+    ]
+    .iter()
+    .filter(|(flag, set, _)| *flag == Some(set))
+    .map(|(_, _, state)| *state)
+    .collect();
+    Some(if states.is_empty() {
+        "changed".into()
+    } else {
+        states.join(", ")
+    })
+}
+
+/// Parse human-readable `git status` (what `make` recipes and `--long` print).
+fn parse_status_text(raw: &str) -> StatusSummary<'_> {
     let branch = raw
         .lines()
         .find(|l| l.starts_with("On branch"))
         .map(|l| l.trim_start_matches("On branch "))
         .unwrap_or("(detached)");
 
-    let ahead_behind = raw
+    let tracking = raw
         .lines()
         .find(|l| l.starts_with("Your branch"))
-        .unwrap_or("");
+        .map(str::trim)
+        .filter(|l| !l.contains("up to date"));
 
-    let mut staged = Vec::new();
-    let mut unstaged = Vec::new();
-    let mut untracked = Vec::new();
+    let mut summary = StatusSummary {
+        branch: branch.to_string(),
+        tracking,
+        ..StatusSummary::default()
+    };
 
     #[derive(PartialEq)]
     enum Section {
@@ -68,91 +299,156 @@ fn compress_status(raw: &str) -> String {
         } else if line.starts_with('\t') || line.starts_with("  ") {
             let trimmed = line.trim();
             match section {
-                Section::Staged => staged.push(trimmed),
-                Section::Unstaged => unstaged.push(trimmed),
-                Section::Untracked => untracked.push(trimmed),
+                Section::Staged => summary.staged.push(trimmed.to_string()),
+                Section::Unstaged => summary.modified.push(trimmed.to_string()),
+                Section::Untracked => summary.untracked.push(trimmed),
                 Section::None => {}
             }
         }
     }
+    summary
+}
 
-    let mut out = format!("[branch] {branch}");
-    if !ahead_behind.is_empty() && !ahead_behind.contains("up to date") {
-        out.push_str(&format!(" | {}", ahead_behind.trim()));
+/// Untracked paths, with directories holding more than `UNTRACKED_DIR_FILES`
+/// of them collapsed to `build/ (42 files)`.
+fn collapse_untracked(paths: &[&str]) -> Vec<String> {
+    let parent = |p: &str| {
+        p.trim_end_matches('/')
+            .rsplit_once('/')
+            .map(|(dir, _)| dir.to_string())
+    };
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for dir in paths.iter().filter_map(|p| parent(p)) {
+        match counts.iter_mut().find(|(d, _)| *d == dir) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((dir, 1)),
+        }
     }
-    out.push('\n');
 
-    if !staged.is_empty() {
-        out.push_str(&format!(
-            "[staged {}] {}\n",
-            staged.len(),
-            staged.join(", ")
-        ));
+    let mut out: Vec<String> = Vec::new();
+    for path in paths {
+        let collapsed = parent(path).and_then(|dir| {
+            counts
+                .iter()
+                .find(|(d, n)| *d == dir && *n > UNTRACKED_DIR_FILES)
+                .map(|(d, n)| format!("{d}/ ({n} files)"))
+        });
+        match collapsed {
+            Some(entry) if out.contains(&entry) => {}
+            Some(entry) => out.push(entry),
+            None => out.push(path.to_string()),
+        }
     }
-    if !unstaged.is_empty() {
-        out.push_str(&format!(
-            "[modified {}] {}\n",
-            unstaged.len(),
-            unstaged.join(", ")
-        ));
+    out
+}
+
+/// `[staged 2] a, b` capped to `max` entries.
+fn status_group<S: AsRef<str>>(name: &str, entries: &[S], max: usize) -> String {
+    let shown: Vec<&str> = entries.iter().take(max).map(AsRef::as_ref).collect();
+    let mut line = format!("[{name} {}] {}", entries.len(), shown.join(", "));
+    if entries.len() > max {
+        line.push_str(&format!(", … +{} more", entries.len() - max));
     }
-    if !untracked.is_empty() {
-        out.push_str(&format!(
-            "[untracked {}] {}\n",
-            untracked.len(),
-            untracked.join(", ")
-        ));
+    line.push('\n');
+    line
+}
+
+/// Compress `git status` into a structured summary.
+fn compress_status(raw: &str, ctx: &CompressCtx) -> String {
+    let status = parse_status(raw);
+
+    let mut out = format!("[branch] {}", status.branch);
+    if let Some(tracking) = status.tracking {
+        out.push_str(&format!(" | {tracking}"));
+    }
+    if let Some(upstream) = status.upstream {
+        out.push_str(&format!(" → {upstream}"));
+        match (status.ahead.unwrap_or(0), status.behind.unwrap_or(0)) {
+            (0, 0) => {}
+            (ahead, 0) => out.push_str(&format!(" (ahead {ahead})")),
+            (0, behind) => out.push_str(&format!(" (behind {behind})")),
+            (ahead, behind) => out.push_str(&format!(" (ahead {ahead}, behind {behind})")),
+        }
+    }
+    out.push('\n');
+
+    let untracked = collapse_untracked(&status.untracked);
+    let groups: [(&str, &[String]); 6] = [
+        ("conflicted", &status.conflicted),
+        ("staged", &status.staged),
+        ("renamed", &status.renamed),
+        ("modified", &status.modified),
+        ("submodules", &status.submodules),
+        ("untracked", &untracked),
+    ];
+    for (name, entries) in groups {
+        if !entries.is_empty() {
+            out.push_str(&status_group(name, entries, ctx.max_items));
+        }
     }
-    if staged.is_empty() && unstaged.is_empty() && untracked.is_empty() {
+    if status.is_clean() {
         out.push_str("[clean]\n");
     }
     out
 }
 
-/// Compress `git diff` — keep stat summary + truncate patch.
-fn compress_diff(raw: &str) -> String {
-    let lines: Vec<&str> = raw.lines().collect();
-    let mut stat_lines = Vec::new();
-    let mut diff_files = Vec::new();
-    let mut current_file: Option<String> = None;
-    let mut adds = 0usize;
-    let mut dels = 0usize;
+/// Per-file line counts from a patch.
+#[derive(Debug, PartialEq, Serialize)]
+struct DiffFile {
+    path: String,
+    adds: usize,
+    dels: usize,
+}
+
+/// Parsed `git diff`: `--stat` lines and/or per-file counts from hunks.
+#[derive(Debug, Default, Serialize)]
+struct DiffSummary<'a> {
+    stat: Vec<&'a str>,
+    files: Vec<DiffFile>,
+}
+
+fn parse_diff(raw: &str) -> DiffSummary<'_> {
+    let mut summary = DiffSummary::default();
+    let mut current: Option<DiffFile> = None;
 
-    for line in &lines {
+    for line in raw.lines() {
         // Stat section (from --stat)
         if line.contains('|') && (line.contains('+') || line.contains('-')) && line.len() < 120 {
-            stat_lines.push(*line);
+            summary.stat.push(line.trim());
             continue;
         }
         // Diff hunks
         if line.starts_with("diff --git") {
-            if let Some(f) = current_file.take() {
-                diff_files.push(format!("  {f}: +{adds} -{dels}"));
+            summary.files.extend(current.take());
+            current = line.split(' ').next_back().map(|s| DiffFile {
+                path: s.trim_start_matches("b/").to_string(),
+                adds: 0,
+                dels: 0,
+            });
+        } else if let Some(file) = current.as_mut() {
+            if line.starts_with('+') && !line.starts_with("+++") {
+                file.adds += 1;
+            } else if line.starts_with('-') && !line.starts_with("---") {
+                file.dels += 1;
             }
-            current_file = line
-                .split(' ')
-                .next_back()
-                .map(|s| s.trim_start_matches("b/").to_string());
-            adds = 0;
-            dels = 0;
-        } else if line.starts_with('+') && !line.starts_with("+++") {
-            adds += 1;
-        } else if line.starts_with('-') && !line.starts_with("---") {
-            dels += 1;
         }
     }
-    if let Some(f) = current_file {
-        diff_files.push(format!("  {f}: +{adds} -{dels}"));
-    }
+    summary.files.extend(current);
+    summary
+}
+
+/// Compress `git diff` — keep stat summary + truncate patch.
+fn compress_diff(raw: &str) -> String {
+    let diff = parse_diff(raw);
 
     let mut out = String::from("[diff]\n");
-    if !stat_lines.is_empty() {
-        for s in &stat_lines {
-            out.push_str(&format!("  {}\n", s.trim()));
+    if !diff.stat.is_empty() {
+        for s in &diff.stat {
+            out.push_str(&format!("  {s}\n"));
         }
-    } else if !diff_files.is_empty() {
-        for f in &diff_files {
-            out.push_str(&format!("{f}\n"));
+    } else if !diff.files.is_empty() {
+        for f in &diff.files {
+            out.push_str(&format!("  {}: +{} -{}\n", f.path, f.adds, f.dels));
         }
     } else {
         out.push_str("  (no changes)\n");
@@ -160,6 +456,601 @@ fn compress_diff(raw: &str) -> String {
     out
 }
 
+/// One file of a patch: its `diff --git` header lines and hunks.
+struct PatchFile<'a> {
+    path: &'a str,
+    /// `new file`, `deleted`, `renamed from …`, `binary`.
+    notes: Vec<String>,
+    hunks: Vec<Hunk<'a>>,
+}
+
+struct Hunk<'a> {
+    /// `@@ -10,7 +10,8 @@ fn main()`.
+    header: &'a str,
+    lines: Vec<&'a str>,
+}
+
+impl PatchFile<'_> {
+    /// Added and removed lines over all hunks.
+    fn changes(&self) -> (usize, usize) {
+        self.hunks
+            .iter()
+            .map(Hunk::changes)
+            .fold((0, 0), |(a, d), (ha, hd)| (a + ha, d + hd))
+    }
+}
+
+impl Hunk<'_> {
+    fn changes(&self) -> (usize, usize) {
+        let adds = self.lines.iter().filter(|l| l.starts_with('+')).count();
+        let dels = self.lines.iter().filter(|l| l.starts_with('-')).count();
+        (adds, dels)
+    }
+
+    /// Removed and added lines differ only in whitespace.
+    fn whitespace_only(&self) -> bool {
+        let squash = |sign: char| -> String {
+            self.lines
+                .iter()
+                .filter_map(|l| l.strip_prefix(sign))
+                .flat_map(|l| l.chars().filter(|c| !c.is_whitespace()))
+                .collect()
+        };
+        squash('-') == squash('+')
+    }
+}
+
+fn parse_patch(raw: &str) -> Vec<PatchFile<'_>> {
+    let mut files: Vec<PatchFile> = Vec::new();
+    for line in raw.lines() {
+        let combined = line
+            .strip_prefix("diff --cc ")
+            .or_else(|| line.strip_prefix("diff --combined "));
+        if let Some(paths) = line.strip_prefix("diff --git ").or(combined) {
+            // Combined diffs (merge commits) name the file once.
+            let path = match combined {
+                Some(path) => path,
+                None => paths.rsplit_once(" b/").map_or(paths, |(_, b)| b),
+            }
+            .trim_matches('"');
+            files.push(PatchFile {
+                path,
+                notes: Vec::new(),
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@") {
+            file.hunks.push(Hunk {
+                header: line,
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            if line.starts_with(['+', '-', ' ']) || line.is_empty() {
+                hunk.lines.push(line);
+            }
+        } else if line.starts_with("new file") {
+            file.notes.push("new file".into());
+        } else if line.starts_with("deleted file") {
+            file.notes.push("deleted".into());
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            file.notes.push(format!("renamed from {from}"));
+        } else if line.starts_with("Binary files") {
+            file.notes.push("binary".into());
+        }
+    }
+    files
+}
+
+/// Lock files and generated / minified artifacts: their hunks are noise.
+fn is_generated(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    LOCKFILES.contains(&name)
+        || name.contains(".min.")
+        || name.ends_with(".pb.go")
+        || name.ends_with("_pb2.py")
+        || name.contains(".generated.")
+}
+
+/// Compress a full patch: hunk headers with their function context, changed
+/// lines with one line of context around them, at most `max_block_lines`
+/// changed lines per file and `max_items` files. Whitespace-only hunks and
+/// lock / generated files shrink to one line.
+fn compress_patch(raw: &str, ctx: &CompressCtx) -> String {
+    let files = parse_patch(raw);
+    if files.is_empty() {
+        return compress_diff(raw);
+    }
+
+    let totals: Vec<(usize, usize)> = files.iter().map(PatchFile::changes).collect();
+    let (adds, dels) = totals
+        .iter()
+        .fold((0, 0), |(a, d), (fa, fd)| (a + fa, d + fd));
+    let noun = if files.len() == 1 { "file" } else { "files" };
+    let mut out = format!("[diff] {} {noun}, +{adds} -{dels}\n", files.len());
+
+    let budget = ctx.max_block_lines;
+    for (file, (adds, dels)) in files.iter().zip(totals).take(ctx.max_items) {
+        out.push_str(&format!("{} (+{adds} -{dels})", file.path));
+        for note in &file.notes {
+            out.push_str(&format!(", {note}"));
+        }
+        if is_generated(file.path) && !file.hunks.is_empty() {
+            out.push_str(", generated, hunks collapsed\n");
+            continue;
+        }
+        out.push('\n');
+
+        let mut shown = 0;
+        let mut hidden = 0;
+        for hunk in &file.hunks {
+            if shown >= budget {
+                let (a, d) = hunk.changes();
+                hidden += a + d;
+                continue;
+            }
+            if hunk.whitespace_only() {
+                out.push_str(&format!(
+                    "  {} (whitespace only)\n",
+                    cut(hunk.header, ctx.max_line_len)
+                ));
+                continue;
+            }
+            out.push_str(&format!("  {}\n", cut(hunk.header, ctx.max_line_len)));
+            let changed = |i: usize| hunk.lines.get(i).is_some_and(|l| l.starts_with(['+', '-']));
+            let mut prev_shown = false;
+            for (i, line) in hunk.lines.iter().enumerate() {
+                let keep = if changed(i) {
+                    prev_shown = shown < budget;
+                    if prev_shown {
+                        shown += 1;
+                    } else {
+                        hidden += 1;
+                    }
+                    prev_shown
+                } else {
+                    // Unchanged lines only right next to a shown change.
+                    (i > 0 && changed(i - 1) && prev_shown) || (changed(i + 1) && shown < budget)
+                };
+                if keep {
+                    out.push_str(&format!("  {}\n", cut(line, ctx.max_line_len)));
+                }
+            }
+        }
+        if hidden > 0 {
+            out.push_str(&format!("  … {hidden} more changed lines\n"));
+        }
+    }
+    if files.len() > ctx.max_items {
+        out.push_str(&format!("… +{} files\n", files.len() - ctx.max_items));
+    }
+    out
+}
+
+/// `--format` the tool layer injects for `git log`: one tab-separated line per
+/// commit, subject last. Tabs rather than control characters, which
+/// `normalize` strips before the compressor sees the output.
+pub const LOG_FORMAT: &str = "--format=@cx%x09%H%x09%P%x09%an%x09%ad%x09%s";
+/// Start of each commit line written with [`LOG_FORMAT`].
+const LOG_MARKER: &str = "@cx\t";
+/// Shortest hash prefix shown in a log listing.
+const MIN_HASH_LEN: usize = 7;
+
+/// One commit of a `git log` run with [`LOG_FORMAT`].
+#[derive(Debug, Serialize)]
+struct Commit<'a> {
+    hash: &'a str,
+    parents: Vec<&'a str>,
+    author: &'a str,
+    date: &'a str,
+    subject: &'a str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    merge: bool,
+    /// From `--shortstat`, when given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stat: Option<ShortStat>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct ShortStat {
+    files: u32,
+    insertions: u32,
+    deletions: u32,
+}
+
+/// Commits of a log written with [`LOG_FORMAT`]; `None` for any other format.
+fn parse_log(raw: &str) -> Option<Vec<Commit<'_>>> {
+    if !raw.lines().any(|l| l.starts_with(LOG_MARKER)) {
+        return None;
+    }
+    let mut commits: Vec<Commit> = Vec::new();
+    for line in raw.lines() {
+        if let Some(rest) = line.strip_prefix(LOG_MARKER) {
+            let mut fields = rest.splitn(5, '\t');
+            let (Some(hash), Some(parents), Some(author), Some(date)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let parents: Vec<&str> = parents.split_whitespace().collect();
+            commits.push(Commit {
+                hash,
+                merge: parents.len() > 1,
+                parents,
+                author,
+                date,
+                subject: fields.next().unwrap_or(""),
+                stat: None,
+            });
+        } else if let Some(stat) = parse_shortstat(line)
+            && let Some(commit) = commits.last_mut()
+        {
+            commit.stat = Some(stat);
+        }
+    }
+    Some(commits)
+}
+
+/// ` 3 files changed, 10 insertions(+), 2 deletions(-)`.
+fn parse_shortstat(line: &str) -> Option<ShortStat> {
+    if !line.contains(" changed") {
+        return None;
+    }
+    let mut stat = ShortStat::default();
+    for part in line.split(',') {
+        let (n, what) = part.trim().split_once(' ')?;
+        let n: u32 = n.parse().ok()?;
+        if what.starts_with("file") {
+            stat.files = n;
+        } else if what.starts_with("insertion") {
+            stat.insertions = n;
+        } else if what.starts_with("deletion") {
+            stat.deletions = n;
+        }
+    }
+    Some(stat)
+}
+
+/// Shortest prefix length (at least [`MIN_HASH_LEN`]) that still tells every
+/// hash of the listing apart, so all of them are shown at the same length.
+fn short_hash_len(commits: &[Commit]) -> usize {
+    let mut hashes: Vec<&str> = commits.iter().map(|c| c.hash).collect();
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+        .windows(2)
+        .map(|w| {
+            w[0].bytes()
+                .zip(w[1].bytes())
+                .take_while(|(a, b)| a == b)
+                .count()
+                + 1
+        })
+        .fold(MIN_HASH_LEN, usize::max)
+}
+
+/// Compress `git log`: commits grouped by day (or by author), hashes shortened
+/// to one length, merges flagged and `--shortstat` counts inline. Logs in a
+/// format the user chose are truncated as-is.
+fn compress_log(raw: &str, by_author: bool, ctx: &CompressCtx) -> String {
+    let Some(commits) = parse_log(raw) else {
+        if raw.trim().is_empty() {
+            return "[log] no commits".into();
+        }
+        return truncate(raw, ctx);
+    };
+
+    let mut groups: Vec<(&str, Vec<&Commit>)> = Vec::new();
+    for commit in &commits {
+        let key = if by_author {
+            commit.author
+        } else {
+            commit.date
+        };
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(commit),
+            None => groups.push((key, vec![commit])),
+        }
+    }
+
+    let mut authors: Vec<&str> = commits.iter().map(|c| c.author).collect();
+    authors.sort_unstable();
+    authors.dedup();
+    let noun = |n: usize, one: &str, many: &str| format!("{n} {}", if n == 1 { one } else { many });
+    let mut out = format!(
+        "[log: {}, {}",
+        noun(commits.len(), "commit", "commits"),
+        noun(authors.len(), "author", "authors")
+    );
+    let merges = commits.iter().filter(|c| c.merge).count();
+    if merges > 0 {
+        out.push_str(&format!(", {}", noun(merges, "merge", "merges")));
+    }
+    out.push_str("]\n");
+
+    let hash_len = short_hash_len(&commits);
+    let mut shown = 0;
+    for (key, group) in &groups {
+        if shown >= ctx.max_items {
+            break;
+        }
+        out.push_str(&format!("{key} ({})\n", group.len()));
+        for commit in group.iter().take(ctx.max_items - shown) {
+            let hash = commit.hash.get(..hash_len).unwrap_or(commit.hash);
+            let merge = if commit.merge { "[merge] " } else { "" };
+            let mut details = vec![
+                if by_author {
+                    commit.date
+                } else {
+                    commit.author
+                }
+                .to_string(),
+            ];
+            if let Some(stat) = &commit.stat {
+                details.push(format!(
+                    "{}, +{} -{}",
+                    noun(stat.files as usize, "file", "files"),
+                    stat.insertions,
+                    stat.deletions
+                ));
+            }
+            let line = format!(
+                "  {hash} {merge}{} ({})",
+                commit.subject,
+                details.join(", ")
+            );
+            out.push_str(&format!("{}\n", cut(&line, ctx.max_line_len)));
+            shown += 1;
+        }
+    }
+    if commits.len() > shown {
+        out.push_str(&format!("  … +{} more\n", commits.len() - shown));
+    }
+    out
+}
+
+/// Header of an annotated tag in `git show` output.
+#[derive(Debug, Serialize)]
+struct ShowTag<'a> {
+    name: &'a str,
+    tagger: &'a str,
+    date: &'a str,
+    message: Vec<&'a str>,
+}
+
+/// One commit of `git show` output (the default `medium` format).
+#[derive(Debug, Default, Serialize)]
+struct ShowCommit<'a> {
+    hash: &'a str,
+    /// Decoration: `HEAD -> main, origin/main`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refs: Option<&'a str>,
+    /// Listed by git for merge commits only.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parents: Vec<&'a str>,
+    author: &'a str,
+    date: &'a str,
+    subject: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    body: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    trailers: Vec<&'a str>,
+    /// Per-file counts from the patch.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    files: Vec<DiffFile>,
+    /// What follows the message: the patch, or `--stat` lines.
+    #[serde(skip)]
+    changes: Vec<&'a str>,
+}
+
+/// Parsed `git show` of commits and tags.
+#[derive(Debug, Default, Serialize)]
+struct ShowSummary<'a> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<ShowTag<'a>>,
+    commits: Vec<ShowCommit<'a>>,
+}
+
+/// `git show` of commits / tags; `None` for other objects (trees, blobs, …).
+fn parse_show(raw: &str) -> Option<ShowSummary<'_>> {
+    let lines: Vec<&str> = raw.lines().collect();
+    let mut show = ShowSummary::default();
+    let mut i = 0;
+    while i < lines.len() {
+        if let Some(name) = lines[i].strip_prefix("tag ")
+            && let Some(tagger) = lines.get(i + 1).and_then(|l| l.strip_prefix("Tagger:"))
+        {
+            i += 2;
+            let mut tag = ShowTag {
+                name,
+                tagger: tagger.trim(),
+                date: "",
+                message: Vec::new(),
+            };
+            let mut signature = false;
+            while let Some(line) = lines.get(i).filter(|l| !is_commit_line(l)) {
+                if let Some(date) = line.strip_prefix("Date:") {
+                    tag.date = date.trim();
+                } else if line.starts_with("-----BEGIN PGP SIGNATURE") {
+                    signature = true;
+                } else if !signature && !line.trim().is_empty() {
+                    tag.message.push(line.trim_end());
+                } else if line.starts_with("-----END PGP SIGNATURE") {
+                    signature = false;
+                }
+                i += 1;
+            }
+            show.tags.push(tag);
+            continue;
+        }
+        if !is_commit_line(lines[i]) {
+            i += 1;
+            continue;
+        }
+
+        let rest = &lines[i]["commit ".len()..];
+        let (hash, refs) = match rest.split_once(' ') {
+            Some((hash, refs)) => (
+                hash,
+                Some(refs.trim_start_matches('(').trim_end_matches(')')),
+            ),
+            None => (rest, None),
+        };
+        let mut commit = ShowCommit {
+            hash,
+            refs,
+            ..ShowCommit::default()
+        };
+        i += 1;
+        while let Some(line) = lines.get(i).filter(|l| !l.is_empty()) {
+            if let Some(parents) = line.strip_prefix("Merge:") {
+                commit.parents = parents.split_whitespace().collect();
+            } else if let Some(author) = line.strip_prefix("Author:") {
+                commit.author = author.trim();
+            } else if let Some(date) = line.strip_prefix("Date:") {
+                commit.date = date.trim();
+            }
+            i += 1;
+        }
+
+        let mut message = Vec::new();
+        while let Some(line) = lines
+            .get(i)
+            .filter(|l| l.is_empty() || l.starts_with("    "))
+        {
+            message.push(line.get(4..).unwrap_or("").trim_end());
+            i += 1;
+        }
+        let mut message = message.into_iter().skip_while(|l| l.is_empty());
+        commit.subject = message.next().unwrap_or("");
+        let mut body: Vec<&str> = message.collect();
+        while body.last().is_some_and(|l| l.is_empty()) {
+            body.pop();
+        }
+        let last_paragraph = body.iter().rposition(|l| l.is_empty()).map_or(0, |p| p + 1);
+        if body.len() > last_paragraph && body[last_paragraph..].iter().all(|l| is_trailer(l)) {
+            commit.trailers = body.split_off(last_paragraph);
+            while body.last().is_some_and(|l| l.is_empty()) {
+                body.pop();
+            }
+        }
+        commit.body = body.into_iter().skip_while(|l| l.is_empty()).collect();
+
+        while let Some(line) = lines
+            .get(i)
+            .filter(|l| !is_commit_line(l) && !l.starts_with("tag "))
+        {
+            commit.changes.push(line);
+            i += 1;
+        }
+        commit.files = parse_patch(&commit.changes.join("\n"))
+            .into_iter()
+            .map(|f| {
+                let (adds, dels) = f.changes();
+                DiffFile {
+                    path: f.path.to_string(),
+                    adds,
+                    dels,
+                }
+            })
+            .collect();
+        show.commits.push(commit);
+    }
+    (!show.tags.is_empty() || !show.commits.is_empty()).then_some(show)
+}
+
+/// `commit <hash>` starting a commit in `git show` / `git log` output.
+fn is_commit_line(line: &str) -> bool {
+    line.strip_prefix("commit ").is_some_and(|rest| {
+        rest.split(' ')
+            .next()
+            .is_some_and(|h| h.len() >= 4 && h.bytes().all(|b| b.is_ascii_hexdigit()))
+    })
+}
+
+/// A `Token: value` commit trailer (`Signed-off-by: …`, `Fixes: …`).
+fn is_trailer(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(key, value)| {
+        !key.is_empty()
+            && !value.trim().is_empty()
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+/// Compress `git show`: tag and commit headers (merge parents, trailers, a
+/// capped body), then each commit's changes as a compact patch.
+fn compress_show(raw: &str, ctx: &CompressCtx) -> String {
+    let Some(show) = parse_show(raw) else {
+        return truncate(raw, ctx);
+    };
+
+    let mut out = String::new();
+    for tag in &show.tags {
+        out.push_str(&format!("[tag {}] {}", tag.name, tag.tagger));
+        if !tag.date.is_empty() {
+            out.push_str(&format!(", {}", tag.date));
+        }
+        out.push('\n');
+        for line in tag.message.iter().take(ctx.max_block_lines) {
+            out.push_str(&format!("  {line}\n"));
+        }
+    }
+    for commit in &show.commits {
+        match commit.refs {
+            Some(refs) => out.push_str(&format!("[commit {} ({refs})]", commit.hash)),
+            None => out.push_str(&format!("[commit {}]", commit.hash)),
+        }
+        out.push_str(&format!(" {}\n", commit.subject));
+        out.push_str(&format!("author: {}, {}\n", commit.author, commit.date));
+        if !commit.parents.is_empty() {
+            out.push_str(&format!("merge of: {}\n", commit.parents.join(" ")));
+        }
+        for line in commit.body.iter().take(ctx.max_block_lines) {
+            out.push_str(&format!("  {}\n", cut(line, ctx.max_line_len)));
+        }
+        if commit.body.len() > ctx.max_block_lines {
+            out.push_str(&format!(
+                "  … +{} more lines\n",
+                commit.body.len() - ctx.max_block_lines
+            ));
+        }
+        if !commit.trailers.is_empty() {
+            out.push_str(&format!("[trailers: {}]\n", commit.trailers.len()));
+            for trailer in &commit.trailers {
+                out.push_str(&format!("  {trailer}\n"));
+            }
+        }
+
+        let changes = commit.changes.join("\n");
+        if changes.contains("diff --") {
+            out.push_str(&compress_patch(&changes, ctx));
+        } else if !changes.trim().is_empty() {
+            let stat = truncate(changes.trim_matches('\n'), ctx);
+            out.push_str(&format!("{stat}\n"));
+        }
+    }
+    out
+}
+
+/// Compress file content (`git show <rev>:<path>`): its length, then the
+/// first and last lines of a long file.
+fn compress_file_content(raw: &str, ctx: &CompressCtx) -> String {
+    // Trees list their entries; errors are one line.
+    if raw.starts_with("tree ") || raw.starts_with("fatal:") {
+        return truncate(raw, ctx);
+    }
+    let total = raw.lines().count();
+    let noun = if total == 1 { "line" } else { "lines" };
+    let strategy = ctx.truncate.unwrap_or(Strategy::HeadTail);
+    format!(
+        "[file: {total} {noun}]\n{}",
+        truncate_by(raw, ctx.max_lines, ctx.max_line_len, strategy)
+    )
+}
+
 /// Compress push/pull/fetch — extract one-liner.
 fn compress_transfer(sub: &str, raw: &str) -> String {
     let meaningful: Vec<&str> = raw
@@ -205,7 +1096,7 @@ fn compress_write_op(sub: &str, raw: &str) -> String {
 }
 
 /// Compress `git branch` — list branches compactly.
-fn compress_branch(raw: &str) -> String {
+fn compress_branch(raw: &str, ctx: &CompressCtx) -> String {
     let branches: Vec<&str> = raw.lines().filter(|l| !l.trim().is_empty()).collect();
 
     if branches.is_empty() {
@@ -225,17 +1116,17 @@ fn compress_branch(raw: &str) -> String {
         .collect();
 
     let mut out = format!("[branches: {}] current: {current}\n", branches.len());
-    for b in others.iter().take(30) {
+    for b in others.iter().take(ctx.max_items) {
         out.push_str(&format!("  {b}\n"));
     }
-    if others.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", others.len() - 30));
+    if others.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", others.len() - ctx.max_items));
     }
     out
 }
 
 /// Compress `git tag` — list tags compactly.
-fn compress_tag(raw: &str) -> String {
+fn compress_tag(raw: &str, ctx: &CompressCtx) -> String {
     let tags: Vec<&str> = raw.lines().filter(|l| !l.trim().is_empty()).collect();
 
     if tags.is_empty() {
@@ -243,17 +1134,17 @@ fn compress_tag(raw: &str) -> String {
     }
 
     let mut out = format!("[tags: {}]\n", tags.len());
-    for t in tags.iter().take(30) {
+    for t in tags.iter().take(ctx.max_items) {
         out.push_str(&format!("  {t}\n"));
     }
-    if tags.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", tags.len() - 30));
+    if tags.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", tags.len() - ctx.max_items));
     }
     out
 }
 
 /// Compress `git stash` — list/show/pop/apply.
-fn compress_stash(_sub: &str, raw: &str) -> String {
+fn compress_stash(_sub: &str, raw: &str, ctx: &CompressCtx) -> String {
     if raw.trim().is_empty() {
         return "[stash] ok".into();
     }
@@ -263,11 +1154,14 @@ fn compress_stash(_sub: &str, raw: &str) -> String {
     // stash list
     if lines.iter().any(|l| l.starts_with("stash@{")) {
         let mut out = format!("[stash: {} entries]\n", lines.len());
-        for s in lines.iter().take(20) {
+        for s in lines.iter().take(ctx.max_block_lines) {
             out.push_str(&format!("  {s}\n"));
         }
-        if lines.len() > 20 {
-            out.push_str(&format!("  … +{} more\n", lines.len() - 20));
+        if lines.len() > ctx.max_block_lines {
+            out.push_str(&format!(
+                "  … +{} more\n",
+                lines.len() - ctx.max_block_lines
+            ));
         }
         return out;
     }
@@ -319,6 +1213,88 @@ fn compress_merge_like(sub: &str, raw: &str) -> String {
     }
 }
 
+/// First line of the state `GitTool` appends when a merge, rebase,
+/// cherry-pick or revert stops: `# cx.operation rebase 2/5`.
+pub const OPERATION_HEADER: &str = "# cx.operation ";
+/// Conflict markers left in an unmerged file: `# cx.markers 3 src/lib.rs`.
+pub const MARKERS_HEADER: &str = "# cx.markers ";
+
+/// Compress a merge / rebase / cherry-pick / revert that stopped part-way:
+/// the operation and step, each conflicted file with its conflict kind and
+/// marker count, and the commands that resolve, continue or abort it.
+fn compress_stopped(sub: &str, output: &str, state: &str, ctx: &CompressCtx) -> String {
+    let operation = state
+        .lines()
+        .find_map(|l| l.strip_prefix(OPERATION_HEADER))
+        .unwrap_or(sub);
+    let name = operation.split(' ').next().unwrap_or(sub);
+    let markers: Vec<(&str, &str)> = state
+        .lines()
+        .filter_map(|l| l.strip_prefix(MARKERS_HEADER)?.split_once(' '))
+        .map(|(count, path)| (path, count))
+        .collect();
+    let status = parse_porcelain_v2(state);
+
+    let mut out = format!("[git {sub}] ");
+    if status.conflicted.is_empty() {
+        out.push_str("stopped");
+    } else {
+        out.push_str(&format!("CONFLICTS ({})", status.conflicted.len()));
+    }
+    out.push_str(&format!(", {operation} in progress"));
+    if !status.branch.is_empty() && !status.branch.starts_with("(detached") {
+        out.push_str(&format!(" on {}", status.branch));
+    }
+    out.push('\n');
+    // Which commit a rebase / cherry-pick stopped at.
+    if let Some(line) = output
+        .lines()
+        .find(|l| l.to_lowercase().contains("could not apply"))
+    {
+        out.push_str(&format!(
+            "  {}\n",
+            line.trim().trim_start_matches("error: ")
+        ));
+    }
+
+    let conflicted: Vec<String> = status
+        .conflicted
+        .iter()
+        .map(|entry| {
+            let count = markers.iter().find(|(path, _)| {
+                entry
+                    .strip_prefix(path)
+                    .is_some_and(|rest| rest.starts_with(" ("))
+            });
+            match count {
+                Some((_, count)) if *count != "0" => {
+                    let noun = if *count == "1" { "marker" } else { "markers" };
+                    format!("{}, {count} {noun})", entry.trim_end_matches(')'))
+                }
+                _ => entry.clone(),
+            }
+        })
+        .collect();
+    if !conflicted.is_empty() {
+        out.push_str(&status_group("conflicted", &conflicted, ctx.max_items));
+    }
+
+    out.push_str("[next]\n");
+    if !markers.is_empty() {
+        let paths: Vec<&str> = markers.iter().map(|(path, _)| *path).collect();
+        out.push_str(&format!(
+            "  resolve, then: cx git add {}\n",
+            paths.join(" ")
+        ));
+    }
+    out.push_str(&format!("  cx git {name} --continue\n"));
+    if name == "rebase" {
+        out.push_str("  cx git rebase --skip\n");
+    }
+    out.push_str(&format!("  cx git {name} --abort\n"));
+    out
+}
+
 /// Compress checkout/switch output.
 fn compress_checkout(sub: &str, raw: &str) -> String {
     if raw.trim().is_empty() {
@@ -330,14 +1306,14 @@ fn compress_checkout(sub: &str, raw: &str) -> String {
 }
 
 /// Compress `git remote -v` output.
-fn compress_remote(raw: &str) -> String {
+fn compress_remote(raw: &str, ctx: &CompressCtx) -> String {
     let remotes: Vec<&str> = raw.lines().filter(|l| l.contains("(fetch)")).collect();
 
     if remotes.is_empty() {
         if raw.trim().is_empty() {
             return "[remotes] none".into();
         }
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
 
     let mut out = format!("[remotes: {}]\n", remotes.len());
@@ -348,7 +1324,7 @@ fn compress_remote(raw: &str) -> String {
 }
 
 /// Compress `git blame` output — compact, keep line refs.
-fn compress_blame(raw: &str) -> String {
+fn compress_blame(raw: &str, ctx: &CompressCtx) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     let total = lines.len();
 
@@ -356,24 +1332,28 @@ fn compress_blame(raw: &str) -> String {
         return "[blame] empty".into();
     }
 
+    let max_lines = BLAME_LINES.min(ctx.max_lines);
+    let max_len = BLAME_LINE_LEN.min(ctx.max_line_len);
+
     let mut out = format!("[blame: {total} lines]\n");
-    for line in lines.iter().take(80) {
+    for line in lines.iter().take(max_lines) {
         // Shorten long blame lines
-        let display = if line.len() > 120 {
-            format!("{} …", &line[..120])
+        let kept = cut(line, max_len);
+        let display = if kept.len() < line.len() {
+            format!("{kept} …")
         } else {
             line.to_string()
         };
         out.push_str(&format!("{display}\n"));
     }
-    if total > 80 {
-        out.push_str(&format!("  … +{} more lines\n", total - 80));
+    if total > max_lines {
+        out.push_str(&format!("  … +{} more lines\n", total - max_lines));
     }
     out
 }
 
 /// Compress `git clean` output.
-fn compress_clean(raw: &str) -> String {
+fn compress_clean(raw: &str, ctx: &CompressCtx) -> String {
     let removed: Vec<&str> = raw
         .lines()
         .filter(|l| l.starts_with("Removing") || l.starts_with("Would remove"))
@@ -383,15 +1363,15 @@ fn compress_clean(raw: &str) -> String {
         if raw.trim().is_empty() {
             return "[git clean] nothing to clean".into();
         }
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
 
     let mut out = format!("[git clean] {} items\n", removed.len());
-    for r in removed.iter().take(30) {
+    for r in removed.iter().take(ctx.max_items) {
         out.push_str(&format!("  {r}\n"));
     }
-    if removed.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", removed.len() - 30));
+    if removed.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", removed.len() - ctx.max_items));
     }
     out
 }
@@ -401,12 +1381,16 @@ mod tests {
     use super::*;
     use crate::compress::Compressor;
 
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
     // ── compress_status ──
 
     #[test]
     fn test_status_clean() {
         let raw = "On branch main\nnothing to commit, working tree clean\n";
-        let result = compress_status(raw);
+        let result = compress_status(raw, &ctx());
         assert!(result.contains("[branch] main"));
         assert!(result.contains("[clean]"));
     }
@@ -421,7 +1405,7 @@ Changes to be committed:
 \tmodified:   src/main.rs
 
 ";
-        let result = compress_status(raw);
+        let result = compress_status(raw, &ctx());
         assert!(result.contains("[branch] feature/login"));
         assert!(result.contains("[staged 2]"));
         assert!(result.contains("new file:   src/auth.rs"));
@@ -439,7 +1423,7 @@ Changes not staged for commit:
 \tmodified:   Cargo.toml
 
 ";
-        let result = compress_status(raw);
+        let result = compress_status(raw, &ctx());
         assert!(result.contains("[modified 2]"));
         assert!(result.contains("README.md"));
         assert!(result.contains("Cargo.toml"));
@@ -455,7 +1439,7 @@ Untracked files:
 \ttodo.txt
 
 ";
-        let result = compress_status(raw);
+        let result = compress_status(raw, &ctx());
         assert!(result.contains("[untracked 2]"));
         assert!(result.contains("new_file.rs"));
         assert!(result.contains("todo.txt"));
@@ -474,7 +1458,7 @@ Untracked files:
 \ttmp.log
 
 ";
-        let result = compress_status(raw);
+        let result = compress_status(raw, &ctx());
         assert!(result.contains("[branch] dev"));
         assert!(result.contains("ahead"));
         assert!(result.contains("[staged 1]"));
@@ -489,7 +1473,7 @@ On branch main
 Your branch is up to date with 'origin/main'.
 nothing to commit, working tree clean
 ";
-        let result = compress_status(raw);
+        let result = compress_status(raw, &ctx());
         assert!(result.contains("[branch] main"));
         assert!(!result.contains("up to date"));
         assert!(result.contains("[clean]"));
@@ -498,10 +1482,96 @@ nothing to commit, working tree clean
     #[test]
     fn test_status_detached_head() {
         let raw = "HEAD detached at abc1234\nnothing to commit\n";
-        let result = compress_status(raw);
+        let result = compress_status(raw, &ctx());
         assert!(result.contains("[branch] (detached)"));
     }
 
+    #[test]
+    fn test_status_summary_sections() {
+        let raw = "\
+On branch dev
+Your branch is ahead of 'origin/dev' by 3 commits.
+Changes to be committed:
+\tnew file:   src/lib.rs
+Untracked files:
+\ttmp.log
+\tnotes.txt
+
+";
+        let v = GitCompressor.summarize(raw, Some("status"), &ctx());
+        assert_eq!(v["branch"], "dev");
+        assert!(v["tracking"].as_str().unwrap().contains("ahead"));
+        assert_eq!(v["staged"][0], "new file:   src/lib.rs");
+        assert_eq!(v["modified"].as_array().unwrap().len(), 0);
+        assert_eq!(v["untracked"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_status_porcelain_v2() {
+        let raw = "\
+# branch.oid 1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b
+# branch.head feature/login
+# branch.upstream origin/feature/login
+# branch.ab +3 -1
+1 A. N... 000000 100644 100644 0000000 1111111 src/auth.rs
+1 MM N... 100644 100644 100644 1111111 2222222 src/main.rs
+1 .D N... 100644 100644 000000 1111111 1111111 old.txt
+2 R. N... 100644 100644 100644 1111111 1111111 R100 src/new name.rs\tsrc/old.rs
+u UU N... 100644 100644 100644 100644 1111111 2222222 3333333 Cargo.toml
+u DU N... 100644 000000 100644 100644 1111111 0000000 3333333 gone.rs
+1 .M SC.U 160000 160000 160000 1111111 1111111 vendor/lib
+? notes.txt
+";
+        let result = compress_status(raw, &ctx());
+        assert_eq!(
+            result,
+            "\
+[branch] feature/login → origin/feature/login (ahead 3, behind 1)
+[conflicted 2] Cargo.toml (both modified), gone.rs (deleted by us)
+[staged 2] src/auth.rs (new), src/main.rs
+[renamed 1] src/old.rs → src/new name.rs
+[modified 2] src/main.rs, old.txt (deleted)
+[submodules 1] vendor/lib (new commits, untracked content)
+[untracked 1] notes.txt
+"
+        );
+
+        let v = GitCompressor.summarize(raw, Some("status"), &ctx());
+        assert_eq!(v["upstream"], "origin/feature/login");
+        assert_eq!(
+            (v["ahead"].as_u64(), v["behind"].as_u64()),
+            (Some(3), Some(1))
+        );
+        assert_eq!(v["renamed"][0], "src/old.rs → src/new name.rs");
+    }
+
+    #[test]
+    fn test_status_porcelain_clean_and_detached() {
+        let raw = "# branch.oid 1a2b3c4d5e6f\n# branch.head (detached)\n";
+        assert_eq!(
+            compress_status(raw, &ctx()),
+            "[branch] (detached at 1a2b3c4)\n[clean]\n"
+        );
+        let raw = "# branch.oid (initial)\n# branch.head main\n# branch.upstream origin/main\n# branch.ab +0 -0\n";
+        assert_eq!(
+            compress_status(raw, &ctx()),
+            "[branch] main → origin/main\n[clean]\n"
+        );
+    }
+
+    #[test]
+    fn test_status_collapses_untracked_directories() {
+        let mut raw = String::from("# branch.head main\n? README.draft\n");
+        for i in 0..42 {
+            raw.push_str(&format!("? build/out{i}.o\n"));
+        }
+        raw.push_str("? src/a.rs\n");
+        let result = compress_status(&raw, &ctx());
+        assert!(result.contains("[untracked 3] README.draft, build/ (42 files), src/a.rs\n"));
+        let v = GitCompressor.summarize(&raw, Some("status"), &ctx());
+        assert_eq!(v["untracked"].as_array().unwrap().len(), 44);
+    }
+
     // ── compress_diff ──
 
     #[test]
@@ -550,6 +1620,27 @@ diff --git a/b.rs b/b.rs
         assert!(result.contains("b.rs: +1 -1"));
     }
 
+    #[test]
+    fn test_diff_summary_files() {
+        let raw = "\
+diff --git a/a.rs b/a.rs
++line1
++line2
+diff --git a/b.rs b/b.rs
+-old
+";
+        let v = GitCompressor.summarize(raw, Some("diff"), &ctx());
+        assert_eq!(v["files"][0]["path"], "a.rs");
+        assert_eq!(v["files"][0]["adds"], 2);
+        assert_eq!(v["files"][1]["dels"], 1);
+    }
+
+    #[test]
+    fn test_summary_fallback_lines() {
+        let v = GitCompressor.summarize("v1.0\nv2.0\n", Some("tag"), &ctx());
+        assert_eq!(v["lines"][0], "[tags: 2]");
+    }
+
     #[test]
     fn test_diff_empty() {
         let result = compress_diff("");
@@ -557,6 +1648,118 @@ diff --git a/b.rs b/b.rs
         assert!(result.contains("(no changes)"));
     }
 
+    // ── compress_patch ──
+
+    #[test]
+    fn test_patch_keeps_hunks_with_one_line_of_context() {
+        let raw = "\
+diff --git a/src/main.rs b/src/main.rs
+index 1a2b3c4..5d6e7f8 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -10,9 +10,9 @@ fn main() {
+     let a = 1;
+     let b = 2;
+     let c = 3;
+-    println!(\"{a}\");
++    println!(\"{b}\");
+     let d = 4;
+     let e = 5;
+     let f = 6;
+ }
+";
+        let result = compress_patch(raw, &ctx());
+        assert_eq!(
+            result,
+            "\
+[diff] 1 file, +1 -1
+src/main.rs (+1 -1)
+  @@ -10,9 +10,9 @@ fn main() {
+       let c = 3;
+  -    println!(\"{a}\");
+  +    println!(\"{b}\");
+       let d = 4;
+"
+        );
+    }
+
+    #[test]
+    fn test_patch_collapses_whitespace_and_lockfiles() {
+        let raw = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,2 @@ mod a;
+-fn x() {  }
++fn x() {}
+diff --git a/Cargo.lock b/Cargo.lock
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -1,3 +1,3 @@
+-version = \"1.0.0\"
++version = \"1.0.1\"
+@@ -9,3 +9,3 @@
+-checksum = \"aa\"
++checksum = \"bb\"
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+";
+        let result = compress_patch(raw, &ctx());
+        assert!(result.starts_with("[diff] 3 files, +4 -3\n"));
+        assert!(
+            result.contains("src/lib.rs (+1 -1)\n  @@ -1,2 +1,2 @@ mod a; (whitespace only)\n")
+        );
+        assert!(result.contains("Cargo.lock (+2 -2), generated, hunks collapsed\n"));
+        assert!(!result.contains("checksum"));
+        assert!(result.contains("new.txt (+1 -0), new file\n  @@ -0,0 +1 @@\n  +hello\n"));
+    }
+
+    #[test]
+    fn test_patch_per_file_budget() {
+        let mut raw = String::from("diff --git a/big.txt b/big.txt\n@@ -1,0 +1,50 @@\n");
+        for i in 0..50 {
+            raw.push_str(&format!("+line {i}\n"));
+        }
+        let result = compress_patch(&raw, &ctx());
+        assert!(result.contains("  +line 19\n"));
+        assert!(!result.contains("+line 20"));
+        assert!(result.ends_with("  … 30 more changed lines\n"));
+
+        let wide = CompressCtx {
+            max_block_lines: 45,
+            ..ctx()
+        };
+        let result = compress_patch(&raw, &wide);
+        assert!(result.contains("  +line 44\n"));
+        assert!(result.ends_with("  … 5 more changed lines\n"));
+    }
+
+    #[test]
+    fn test_patch_caps_files() {
+        let mut raw = String::new();
+        for i in 0..40 {
+            raw.push_str(&format!(
+                "diff --git a/f{i}.rs b/f{i}.rs\n@@ -1 +1 @@\n-old\n+new {i}\n"
+            ));
+        }
+        let result = compress_patch(&raw, &ctx());
+        assert!(result.starts_with("[diff] 40 files, +40 -40\n"));
+        assert!(result.contains("f29.rs (+1 -1)\n"));
+        assert!(!result.contains("f30.rs"));
+        assert!(result.ends_with("  +new 29\n… +10 files\n"));
+    }
+
+    #[test]
+    fn test_patch_summary_uses_file_counts() {
+        let raw = "diff --git a/a.rs b/a.rs\n@@ -1 +1 @@\n-old\n+new\n";
+        let v = GitCompressor.summarize(raw, Some("patch"), &ctx());
+        assert_eq!(v["files"][0]["adds"], 1);
+    }
+
     // ── compress_transfer ──
 
     #[test]
@@ -633,16 +1836,30 @@ remote: Total 3 (delta 2), reused 3 (delta 2)
     #[test]
     fn test_branch_list() {
         let raw = "  dev\n* main\n  feature/login\n";
-        let result = compress_branch(raw);
+        let result = compress_branch(raw, &ctx());
         assert!(result.contains("[branches: 3]"));
         assert!(result.contains("current: main"));
         assert!(result.contains("dev"));
         assert!(result.contains("feature/login"));
     }
 
+    #[test]
+    fn test_branch_honors_max_items() {
+        let raw = "* main\n  a\n  b\n  c\n  d\n";
+        let ctx = CompressCtx {
+            max_items: 2,
+            ..ctx()
+        };
+        let result = compress_branch(raw, &ctx);
+        assert!(result.contains("[branches: 5]"));
+        assert!(result.contains("  a\n  b\n"));
+        assert!(!result.contains("  c\n"));
+        assert!(result.contains("… +2 more"));
+    }
+
     #[test]
     fn test_branch_empty() {
-        let result = compress_branch("");
+        let result = compress_branch("", &ctx());
         assert_eq!(result, "[branches] none");
     }
 
@@ -651,7 +1868,7 @@ remote: Total 3 (delta 2), reused 3 (delta 2)
     #[test]
     fn test_tag_list() {
         let raw = "v0.1.0\nv0.2.0\nv1.0.0\n";
-        let result = compress_tag(raw);
+        let result = compress_tag(raw, &ctx());
         assert!(result.contains("[tags: 3]"));
         assert!(result.contains("v0.1.0"));
         assert!(result.contains("v1.0.0"));
@@ -659,7 +1876,7 @@ remote: Total 3 (delta 2), reused 3 (delta 2)
 
     #[test]
     fn test_tag_empty() {
-        let result = compress_tag("");
+        let result = compress_tag("", &ctx());
         assert_eq!(result, "[tags] none");
     }
 
@@ -669,7 +1886,7 @@ remote: Total 3 (delta 2), reused 3 (delta 2)
     fn test_stash_list() {
         let raw =
             "stash@{0}: WIP on main: abc1234 Fix thing\nstash@{1}: WIP on dev: def5678 Other\n";
-        let result = compress_stash("stash", raw);
+        let result = compress_stash("stash", raw, &ctx());
         assert!(result.contains("[stash: 2 entries]"));
         assert!(result.contains("stash@{0}"));
     }
@@ -677,13 +1894,13 @@ remote: Total 3 (delta 2), reused 3 (delta 2)
     #[test]
     fn test_stash_push_ok() {
         let raw = "Saved working directory and index state WIP on main: abc1234 msg\n";
-        let result = compress_stash("stash", raw);
+        let result = compress_stash("stash", raw, &ctx());
         assert!(result.contains("[stash] Saved working directory"));
     }
 
     #[test]
     fn test_stash_empty() {
-        let result = compress_stash("stash", "");
+        let result = compress_stash("stash", "", &ctx());
         assert_eq!(result, "[stash] ok");
     }
 
@@ -709,6 +1926,47 @@ Automatic merge failed; fix conflicts and then commit the result.
         assert!(result.contains("CONFLICT (content)"));
     }
 
+    #[test]
+    fn test_rebase_conflict_state() {
+        let raw = "\
+Auto-merging src/lib.rs
+CONFLICT (content): Merge conflict in src/lib.rs
+error: could not apply 0fb441f... Fix parser
+hint: Resolve all conflicts manually, mark them as resolved with
+# cx.operation rebase 2/5 of feature
+# cx.markers 3 src/lib.rs
+# cx.markers 0 old.rs
+# branch.oid b8220aa3c1d2e4f5a6b7c8d9e0f1a2b3c4d5e6f7
+# branch.head (detached)
+u UU N... 100644 100644 100644 100644 1111111 2222222 3333333 src/lib.rs
+u DU N... 100644 000000 100644 100644 1111111 0000000 3333333 old.rs
+";
+        let result = GitCompressor.compress(raw, Some("rebase"), &ctx());
+        assert_eq!(
+            result,
+            "[git rebase] CONFLICTS (2), rebase 2/5 of feature in progress
+  could not apply 0fb441f... Fix parser
+[conflicted 2] src/lib.rs (both modified, 3 markers), old.rs (deleted by us)
+[next]
+  resolve, then: cx git add src/lib.rs old.rs
+  cx git rebase --continue
+  cx git rebase --skip
+  cx git rebase --abort
+"
+        );
+    }
+
+    #[test]
+    fn test_merge_stopped_without_conflicts() {
+        let raw = "Automatic merge went well; stopped before committing as requested\n\
+# cx.operation merge\n# branch.oid abc\n# branch.head main\n";
+        let result = GitCompressor.compress(raw, Some("merge"), &ctx());
+        assert_eq!(
+            result,
+            "[git merge] stopped, merge in progress on main\n[next]\n  cx git merge --continue\n  cx git merge --abort\n"
+        );
+    }
+
     #[test]
     fn test_rebase_success() {
         let raw = "Successfully rebased and updated refs/heads/feature.\n";
@@ -755,7 +2013,7 @@ origin\thttps://github.com/user/repo.git (push)
 upstream\thttps://github.com/other/repo.git (fetch)
 upstream\thttps://github.com/other/repo.git (push)
 ";
-        let result = compress_remote(raw);
+        let result = compress_remote(raw, &ctx());
         assert!(result.contains("[remotes: 2]"));
         assert!(result.contains("origin"));
         assert!(result.contains("upstream"));
@@ -763,7 +2021,7 @@ upstream\thttps://github.com/other/repo.git (push)
 
     #[test]
     fn test_remote_none() {
-        let result = compress_remote("");
+        let result = compress_remote("", &ctx());
         assert_eq!(result, "[remotes] none");
     }
 
@@ -776,15 +2034,26 @@ abc1234 (John 2024-01-01 10:00:00 +0100  1) fn main() {
 def5678 (Jane 2024-01-02 11:00:00 +0100  2)     println!(\"hello\");
 abc1234 (John 2024-01-01 10:00:00 +0100  3) }
 ";
-        let result = compress_blame(raw);
+        let result = compress_blame(raw, &ctx());
         assert!(result.contains("[blame: 3 lines]"));
         assert!(result.contains("John"));
         assert!(result.contains("Jane"));
     }
 
+    #[test]
+    fn test_blame_long_non_ascii_line() {
+        let raw = format!(
+            "abc1234 (Zoé Dupré 2024-01-01 10:00:00 +0100  1) // {}\n",
+            "é😀".repeat(100)
+        );
+        let result = compress_blame(&raw, &ctx());
+        assert!(result.contains("Zoé Dupré"));
+        assert!(result.contains(" …"));
+    }
+
     #[test]
     fn test_blame_empty() {
-        let result = compress_blame("");
+        let result = compress_blame("", &ctx());
         assert_eq!(result, "[blame] empty");
     }
 
@@ -793,7 +2062,7 @@ abc1234 (John 2024-01-01 10:00:00 +0100  3) }
     #[test]
     fn test_clean_dry_run() {
         let raw = "Would remove untracked.txt\nWould remove tmp/\n";
-        let result = compress_clean(raw);
+        let result = compress_clean(raw, &ctx());
         assert!(result.contains("[git clean] 2 items"));
         assert!(result.contains("Would remove untracked.txt"));
     }
@@ -801,13 +2070,13 @@ abc1234 (John 2024-01-01 10:00:00 +0100  3) }
     #[test]
     fn test_clean_actual() {
         let raw = "Removing untracked.txt\nRemoving tmp/\n";
-        let result = compress_clean(raw);
+        let result = compress_clean(raw, &ctx());
         assert!(result.contains("[git clean] 2 items"));
     }
 
     #[test]
     fn test_clean_nothing() {
-        let result = compress_clean("");
+        let result = compress_clean("", &ctx());
         assert_eq!(result, "[git clean] nothing to clean");
     }
 
@@ -817,85 +2086,273 @@ abc1234 (John 2024-01-01 10:00:00 +0100  3) }
     fn test_trait_dispatches_status() {
         let c = GitCompressor;
         let raw = "On branch test\nnothing to commit, working tree clean\n";
-        let result = c.compress(raw, Some("status"));
+        let result = c.compress(raw, Some("status"), &ctx());
         assert!(result.contains("[branch] test"));
     }
 
+    #[test]
+    fn test_short_status_is_not_parsed_as_v2() {
+        let raw = "## main...origin/main\n M src/lib.rs\nA  new.rs\n?? notes.txt\n";
+        let result = GitCompressor.compress(raw, Some("status-short"), &ctx());
+        assert!(!result.contains("[clean]"));
+        assert!(result.contains(" M src/lib.rs"));
+        assert!(result.contains("?? notes.txt"));
+    }
+
     #[test]
     fn test_trait_dispatches_diff() {
         let c = GitCompressor;
-        let result = c.compress("", Some("diff"));
+        let result = c.compress("", Some("diff"), &ctx());
         assert!(result.contains("[diff]"));
     }
 
+    // ── compress_log ──
+
+    const LOG: &str =
+        "@cx\taaaaaaa1111\tbbbbbbb2222 ccccccc3333\tAlice\t2024-05-03\tMerge branch 'fix'
+
+ 2 files changed, 10 insertions(+), 1 deletion(-)
+@cx\taaaaaaa1222\tccccccc3333\tBob\t2024-05-03\tFix parser\twith a tab
+@cx\tccccccc3333\t\tAlice\t2024-05-01\tInitial commit
+
+ 1 file changed, 3 insertions(+)
+";
+
+    #[test]
+    fn test_log_grouped_by_date() {
+        let result = compress_log(LOG, false, &ctx());
+        assert_eq!(
+            result,
+            "[log: 3 commits, 2 authors, 1 merge]
+2024-05-03 (2)
+  aaaaaaa11 [merge] Merge branch 'fix' (Alice, 2 files, +10 -1)
+  aaaaaaa12 Fix parser\twith a tab (Bob)
+2024-05-01 (1)
+  ccccccc33 Initial commit (Alice, 1 file, +3 -0)
+"
+        );
+    }
+
+    #[test]
+    fn test_log_grouped_by_author_and_capped() {
+        let ctx = CompressCtx {
+            max_items: 2,
+            ..CompressCtx::default()
+        };
+        let result = compress_log(LOG, true, &ctx);
+        assert!(result.contains("Alice (2)\n  aaaaaaa11 [merge] Merge branch 'fix' (2024-05-03"));
+        assert!(result.contains("  ccccccc33 Initial commit (2024-05-01, 1 file, +3 -0)\n"));
+        assert!(!result.contains("Bob"));
+        assert!(result.ends_with("  … +1 more\n"));
+    }
+
+    #[test]
+    fn test_log_hash_length_has_a_floor() {
+        let commits = parse_log("@cx\t0123456789\t\ta\td\ts\n").unwrap();
+        assert_eq!(short_hash_len(&commits), MIN_HASH_LEN);
+    }
+
+    #[test]
+    fn test_log_other_formats_and_empty() {
+        assert_eq!(compress_log("", false, &ctx()), "[log] no commits");
+        let oneline = "abc1234 First commit\n";
+        assert_eq!(
+            compress_log(oneline, false, &ctx()),
+            truncate(oneline, &ctx())
+        );
+    }
+
+    #[test]
+    fn test_log_summary_commits() {
+        let v = GitCompressor.summarize(LOG, Some("log"), &ctx());
+        assert_eq!(v["commits"][0]["merge"], true);
+        assert_eq!(v["commits"][0]["parents"].as_array().unwrap().len(), 2);
+        assert_eq!(v["commits"][0]["stat"]["insertions"], 10);
+        assert!(v["commits"][1].get("merge").is_none());
+        assert!(v["commits"][1].get("stat").is_none());
+    }
+
+    // ── compress_show ──
+
+    const SHOW: &str = "commit 90516dd (HEAD -> main)
+Author: Alice <a@x>
+Date:   Fri May 3 10:00:00 2024 +0000
+
+    Fix parser
+
+    The tokenizer dropped the last line.
+
+    Signed-off-by: Alice <a@x>
+    Fixes: #12
+
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@ fn parse()
+ let a = 1;
+-let b = 2;
++let b = 3;
+ let c = 4;
+";
+
+    #[test]
+    fn test_show_commit_header_trailers_and_patch() {
+        let result = compress_show(SHOW, &ctx());
+        assert_eq!(
+            result,
+            "[commit 90516dd (HEAD -> main)] Fix parser
+author: Alice <a@x>, Fri May 3 10:00:00 2024 +0000
+  The tokenizer dropped the last line.
+[trailers: 2]
+  Signed-off-by: Alice <a@x>
+  Fixes: #12
+[diff] 1 file, +1 -1
+src/lib.rs (+1 -1)
+  @@ -1,3 +1,3 @@ fn parse()
+   let a = 1;
+  -let b = 2;
+  +let b = 3;
+   let c = 4;
+"
+        );
+    }
+
+    #[test]
+    fn test_show_annotated_tag_of_merge() {
+        let raw = "tag v1.0
+Tagger: Bob <b@x>
+Date:   Mon May 6 09:00:00 2024 +0000
+
+Release 1.0
+-----BEGIN PGP SIGNATURE-----
+abc
+-----END PGP SIGNATURE-----
+
+commit 9f4e543
+Merge: 5b843c7 3b945a2
+Author: Bob <b@x>
+Date:   Mon May 6 08:00:00 2024 +0000
+
+    Merge branch 'side'
+";
+        let result = compress_show(raw, &ctx());
+        assert!(result.starts_with(
+            "[tag v1.0] Bob <b@x>, Mon May 6 09:00:00 2024 +0000\n  Release 1.0\n[commit 9f4e543]"
+        ));
+        assert!(result.contains("merge of: 5b843c7 3b945a2\n"));
+        assert!(!result.contains("PGP") && !result.contains("abc"));
+        assert!(!result.contains("[diff]"));
+    }
+
+    #[test]
+    fn test_show_stat_and_other_objects() {
+        let raw = "commit 2d14f44\nAuthor: A <a@x>\nDate:   now\n\n    Subject\n\n README.md | 2 +-\n 1 file changed\n";
+        let result = compress_show(raw, &ctx());
+        assert_eq!(
+            result,
+            "[commit 2d14f44] Subject\nauthor: A <a@x>, now\n README.md | 2 +-\n 1 file changed\n"
+        );
+        assert!(parse_show("tree HEAD:src\n\nlib.rs\n").is_none());
+    }
+
+    #[test]
+    fn test_show_summary() {
+        let v = GitCompressor.summarize(SHOW, Some("show"), &ctx());
+        let commit = &v["commits"][0];
+        assert_eq!(commit["subject"], "Fix parser");
+        assert_eq!(commit["refs"], "HEAD -> main");
+        assert_eq!(commit["trailers"][1], "Fixes: #12");
+        assert_eq!(commit["files"][0]["adds"], 1);
+        assert!(commit.get("parents").is_none());
+    }
+
+    #[test]
+    fn test_show_file_content() {
+        let ctx = CompressCtx {
+            max_lines: 4,
+            ..CompressCtx::default()
+        };
+        let raw: String = (1..=10).map(|i| format!("line {i}\n")).collect();
+        let result = GitCompressor.compress(&raw, Some("show-file"), &ctx);
+        assert!(result.starts_with("[file: 10 lines]\nline 1\n"));
+        assert!(result.contains("line 10"));
+        assert!(!result.contains("line 5\n"));
+        let missing = "fatal: path 'x' does not exist in 'HEAD'";
+        assert_eq!(
+            GitCompressor.compress(missing, Some("show-file"), &ctx),
+            missing
+        );
+    }
+
     #[test]
     fn test_trait_dispatches_log() {
         let c = GitCompressor;
         let raw = "abc1234 First commit\ndef5678 Second commit\n";
-        let result = c.compress(raw, Some("log"));
+        let result = c.compress(raw, Some("log"), &ctx());
         assert!(result.contains("abc1234"));
     }
 
     #[test]
     fn test_trait_dispatches_branch() {
         let c = GitCompressor;
-        let result = c.compress("* main\n  dev\n", Some("branch"));
+        let result = c.compress("* main\n  dev\n", Some("branch"), &ctx());
         assert!(result.contains("[branches: 2]"));
     }
 
     #[test]
     fn test_trait_dispatches_stash() {
         let c = GitCompressor;
-        let result = c.compress("", Some("stash"));
+        let result = c.compress("", Some("stash"), &ctx());
         assert!(result.contains("[stash]"));
     }
 
     #[test]
     fn test_trait_dispatches_merge() {
         let c = GitCompressor;
-        let result = c.compress("", Some("merge"));
+        let result = c.compress("", Some("merge"), &ctx());
         assert!(result.contains("[git merge]"));
     }
 
     #[test]
     fn test_trait_dispatches_rebase() {
         let c = GitCompressor;
-        let result = c.compress("", Some("rebase"));
+        let result = c.compress("", Some("rebase"), &ctx());
         assert!(result.contains("[git rebase]"));
     }
 
     #[test]
     fn test_trait_dispatches_checkout() {
         let c = GitCompressor;
-        let result = c.compress("Switched to branch 'x'\n", Some("checkout"));
+        let result = c.compress("Switched to branch 'x'\n", Some("checkout"), &ctx());
         assert!(result.contains("[git checkout]"));
     }
 
     #[test]
     fn test_trait_dispatches_tag() {
         let c = GitCompressor;
-        let result = c.compress("v1.0\n", Some("tag"));
+        let result = c.compress("v1.0\n", Some("tag"), &ctx());
         assert!(result.contains("[tags: 1]"));
     }
 
     #[test]
     fn test_trait_dispatches_remote() {
         let c = GitCompressor;
-        let result = c.compress("", Some("remote"));
+        let result = c.compress("", Some("remote"), &ctx());
         assert!(result.contains("[remotes]"));
     }
 
     #[test]
     fn test_trait_dispatches_clean() {
         let c = GitCompressor;
-        let result = c.compress("", Some("clean"));
+        let result = c.compress("", Some("clean"), &ctx());
         assert!(result.contains("[git clean]"));
     }
 
     #[test]
     fn test_trait_dispatches_blame() {
         let c = GitCompressor;
-        let result = c.compress("", Some("blame"));
+        let result = c.compress("", Some("blame"), &ctx());
         assert!(result.contains("[blame]"));
     }
 
@@ -905,6 +2362,7 @@ abc1234 (John 2024-01-01 10:00:00 +0100  3) }
         let result = c.compress(
             "Unstaged changes after reset:\nM\tsrc/main.rs\n",
             Some("reset"),
+            &ctx(),
         );
         assert!(result.contains("[git reset]"));
     }
@@ -912,7 +2370,7 @@ abc1234 (John 2024-01-01 10:00:00 +0100  3) }
     #[test]
     fn test_trait_none_sub() {
         let c = GitCompressor;
-        let result = c.compress("hello", None);
+        let result = c.compress("hello", None, &ctx());
         assert!(result.contains("hello"));
     }
 }
@@ -0,0 +1,751 @@
+use super::summary::{self, Diagnostic, TestFailure, TestReport, parse_location};
+use super::truncate::truncate;
+use super::{CompressCtx, Compressor};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Pure compressor for the Go toolchain (`go test`, `build`, `vet`, `mod`).
+pub struct GoCompressor;
+
+impl Compressor for GoCompressor {
+    fn compress(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> String {
+        match sub.unwrap_or("") {
+            "test" => match events_to_text(raw) {
+                Some(text) => compress_test(&text, ctx),
+                None => compress_test(raw, ctx),
+            },
+            "build" | "install" => compress_build("go build", "error", raw, ctx),
+            "vet" => compress_build("go vet", "warning", raw, ctx),
+            "mod" | "get" => compress_mod(raw, ctx),
+            _ => truncate(raw, ctx),
+        }
+    }
+
+    fn summarize(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> Value {
+        match sub.unwrap_or("") {
+            "test" => json!(parse_test_report(raw)),
+            "build" | "install" => json!(parse_build(raw, "error")),
+            "vet" => json!(parse_build(raw, "warning")),
+            _ => summary::lines(&self.compress(raw, sub, ctx)),
+        }
+    }
+}
+
+// ── go test -json ──
+
+/// One line of `go test -json` (the `test2json` event stream).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TestEvent {
+    action: String,
+    #[serde(default)]
+    package: String,
+    test: Option<String>,
+    output: Option<String>,
+}
+
+fn parse_event(line: &str) -> Option<TestEvent> {
+    line.starts_with('{')
+        .then(|| serde_json::from_str(line).ok())
+        .flatten()
+}
+
+/// Rebuild plain `go test` output from a `-json` stream, keeping each failed
+/// test's output together even when tests ran in parallel. Output of passed
+/// and skipped tests is dropped. `None` when `raw` holds no events.
+fn events_to_text(raw: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut pending: HashMap<(String, String), String> = HashMap::new();
+    let mut order: Vec<(String, String)> = Vec::new();
+    let mut any = false;
+
+    for line in raw.lines() {
+        let Some(event) = parse_event(line) else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+        any = true;
+        let Some(test) = event.test else {
+            if let Some(output) = &event.output {
+                out.push_str(output);
+            }
+            continue;
+        };
+        let key = (event.package, test);
+        match event.action.as_str() {
+            "output" => {
+                if !pending.contains_key(&key) {
+                    order.push(key.clone());
+                }
+                let buf = pending.entry(key).or_default();
+                buf.push_str(event.output.as_deref().unwrap_or(""));
+            }
+            "fail" => out.push_str(&pending.remove(&key).unwrap_or_default()),
+            "pass" | "skip" => {
+                pending.remove(&key);
+            }
+            _ => {}
+        }
+    }
+
+    // Tests that never finished: the binary crashed (panic, timeout) mid-test.
+    for key in order {
+        if let Some(buf) = pending.remove(&key) {
+            out.push_str(&buf);
+        }
+    }
+    any.then_some(out)
+}
+
+// ── go test ──
+
+/// Compress `go test`: `--- FAIL` blocks, panics, build errors and the
+/// per-package `ok` / `FAIL` lines.
+fn compress_test(raw: &str, ctx: &CompressCtx) -> String {
+    let lines: Vec<&str> = raw.lines().collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut ok = 0usize;
+    // Lines before this index already belong to an emitted block.
+    let mut consumed = 0;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+
+        if line.starts_with("--- FAIL:") {
+            // With -v / -json the test's own output comes before `--- FAIL`.
+            let before = lines[consumed..i - 1]
+                .iter()
+                .rev()
+                .take_while(|l| is_indented(l))
+                .count();
+            let after = lines[i..].iter().take_while(|l| is_indented(l)).count();
+            out.push(line.to_string());
+            out.extend(
+                lines[i - 1 - before..i - 1]
+                    .iter()
+                    .chain(&lines[i..i + after])
+                    .take(ctx.max_block_lines)
+                    .map(|l| l.to_string()),
+            );
+            i += after;
+            consumed = i;
+        } else if line.starts_with("panic:") {
+            let block = lines[i..]
+                .iter()
+                .take_while(|l| !is_package_result(l) && !l.starts_with("--- "))
+                .count();
+            out.push(line.to_string());
+            out.extend(
+                project_frames(&lines[i..i + block])
+                    .into_iter()
+                    .take(ctx.max_block_lines)
+                    .map(|l| l.to_string()),
+            );
+            i += block;
+            consumed = i;
+        } else if line.starts_with("ok ") || line.starts_with("ok\t") {
+            ok += 1;
+            if ok <= ctx.max_items {
+                out.push(line.to_string());
+            }
+        } else if is_package_result(line)
+            || line.starts_with("# ")
+            || line
+                .strip_prefix("go: ")
+                .is_some_and(|msg| !is_module_progress(msg))
+            || is_go_diagnostic(line)
+        {
+            out.push(line.to_string());
+        }
+    }
+
+    if ok > ctx.max_items {
+        out.push(format!("  … +{} more ok", ok - ctx.max_items));
+    }
+    if out.is_empty() {
+        return truncate(raw, ctx);
+    }
+    format!("[go test]\n{}", out.join("\n"))
+}
+
+/// Test output (`t.Log`, `t.Error`, subtests) is indented under its test.
+fn is_indented(line: &str) -> bool {
+    line.starts_with([' ', '\t'])
+}
+
+/// `FAIL\tpkg\t0.1s` / `FAIL\tpkg [build failed]` (not the bare `FAIL`).
+fn is_package_result(line: &str) -> bool {
+    line.starts_with("FAIL\t")
+        || line.starts_with("FAIL ")
+        || line.starts_with("ok ")
+        || line.starts_with("ok\t")
+        || line.starts_with("exit status")
+}
+
+/// `go: downloading …` & co, printed while modules are fetched (not errors).
+fn is_module_progress(msg: &str) -> bool {
+    ["downloading ", "finding module", "extracting "]
+        .iter()
+        .any(|p| msg.starts_with(p))
+}
+
+/// `file.go:12:5: message` as printed by the compiler and vet (never
+/// indented, unlike `t.Log` output).
+fn is_go_diagnostic(line: &str) -> bool {
+    !is_indented(line) && parse_location(line).is_some_and(|(loc, _)| loc.file.ends_with(".go"))
+}
+
+/// A panic's goroutine dump without the runtime / testing frames: each frame
+/// is a function line followed by a tab-indented `path:line +0x…` line.
+fn project_frames<'a>(block: &[&'a str]) -> Vec<&'a str> {
+    let mut out: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < block.len() {
+        let line = block[i];
+        let location = block.get(i + 1).filter(|l| l.starts_with('\t'));
+        if let Some(location) = location
+            && !line.starts_with('\t')
+            && !line.starts_with("panic:")
+        {
+            if !["/src/runtime/", "/src/testing/"]
+                .iter()
+                .any(|p| location.contains(p))
+            {
+                out.push(line);
+                out.push(location);
+            }
+            i += 2;
+            continue;
+        }
+        if !line.trim().is_empty() {
+            out.push(line);
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Counts + failures, from `-json` events when present, else from the
+/// `--- PASS` / `--- FAIL` / `--- SKIP` lines of `-v` output.
+fn parse_test_report(raw: &str) -> TestReport {
+    let mut report = TestReport::default();
+    let mut outputs: HashMap<(String, String), Vec<String>> = HashMap::new();
+    let mut failed_packages: Vec<String> = Vec::new();
+    let mut json = false;
+
+    for event in raw.lines().filter_map(parse_event) {
+        json = true;
+        let Some(test) = event.test else {
+            // A package that fails without a failing test did not build or crashed.
+            if event.action == "fail" && !failed_packages.contains(&event.package) {
+                report.errors += 1;
+            }
+            continue;
+        };
+        let key = (event.package, test);
+        match event.action.as_str() {
+            "output" => outputs
+                .entry(key)
+                .or_default()
+                .push(event.output.unwrap_or_default()),
+            "pass" => report.passed += 1,
+            "skip" => report.skipped += 1,
+            "fail" => {
+                report.failed += 1;
+                let lines = outputs.remove(&key).unwrap_or_default();
+                report.failures.push(TestFailure {
+                    message: failure_message(lines.iter().map(String::as_str)),
+                    name: key.1,
+                    ..TestFailure::default()
+                });
+                failed_packages.push(key.0);
+            }
+            _ => {}
+        }
+    }
+    if json {
+        return report;
+    }
+
+    let lines: Vec<&str> = raw.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("--- FAIL: ") {
+            // The message follows `--- FAIL`, or precedes it with -v.
+            let after = lines[i + 1..]
+                .iter()
+                .take_while(|l| is_indented(l))
+                .copied();
+            let before = lines[..i]
+                .iter()
+                .rev()
+                .take_while(|l| is_indented(l))
+                .copied();
+            report.failed += 1;
+            report.failures.push(TestFailure {
+                name: test_name(rest),
+                message: failure_message(after).or_else(|| failure_message(before)),
+                ..TestFailure::default()
+            });
+        } else if trimmed.starts_with("--- PASS: ") {
+            report.passed += 1;
+        } else if trimmed.starts_with("--- SKIP: ") {
+            report.skipped += 1;
+        } else if line.starts_with("FAIL") && line.ends_with(" failed]") {
+            report.errors += 1;
+        }
+    }
+    report
+}
+
+/// `TestFoo/sub (0.00s)` → `TestFoo/sub`.
+fn test_name(rest: &str) -> String {
+    rest.rsplit_once(" (").map_or(rest, |(n, _)| n).to_string()
+}
+
+/// First line a failing test printed itself (not `=== RUN` / `--- FAIL`).
+fn failure_message<'a>(lines: impl Iterator<Item = &'a str>) -> Option<String> {
+    lines
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with("=== ") && !l.starts_with("--- "))
+        .map(str::to_string)
+}
+
+// ── go build / vet ──
+
+/// Diagnostics grouped by the `# package` header that precedes them.
+#[derive(Debug, Default, Serialize)]
+struct BuildSummary {
+    packages: Vec<PackageDiagnostics>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct PackageDiagnostics {
+    package: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// `# example.com/foo [example.com/foo.test]` / `# [example.com/foo]` → `example.com/foo`.
+fn package_header(line: &str) -> Option<&str> {
+    let name = line.strip_prefix("# ")?.split_whitespace().next()?;
+    Some(name.trim_start_matches('[').trim_end_matches(']'))
+}
+
+fn parse_build(raw: &str, level: &str) -> BuildSummary {
+    let mut summary = BuildSummary::default();
+    for line in raw.lines() {
+        if let Some(package) = package_header(line) {
+            if summary.packages.last().is_none_or(|p| p.package != package) {
+                summary.packages.push(PackageDiagnostics {
+                    package: package.to_string(),
+                    ..PackageDiagnostics::default()
+                });
+            }
+            continue;
+        }
+        let line = line.strip_prefix("vet: ").unwrap_or(line);
+        let Some((loc, message)) = parse_location(line).filter(|(l, _)| l.file.ends_with(".go"))
+        else {
+            continue;
+        };
+        if summary.packages.is_empty() {
+            summary.packages.push(PackageDiagnostics::default());
+        }
+        let package = summary.packages.last_mut().expect("pushed above");
+        package.diagnostics.push(
+            Diagnostic {
+                level: level.to_string(),
+                message: message.to_string(),
+                ..Diagnostic::default()
+            }
+            .at(loc),
+        );
+    }
+    summary
+}
+
+/// Compress `go build` / `go vet`: diagnostics grouped by package, each group
+/// capped to `max_hits`. Tab-indented continuation lines (`have` / `want`)
+/// stay with their diagnostic.
+fn compress_build(label: &str, level: &str, raw: &str, ctx: &CompressCtx) -> String {
+    if raw.trim().is_empty() {
+        return match level {
+            "error" => format!("[{label}] ok"),
+            _ => format!("[{label}] clean"),
+        };
+    }
+
+    // (package, entries); each entry is a diagnostic + its continuation lines.
+    let mut groups: Vec<(&str, Vec<Vec<&str>>)> = Vec::new();
+    let mut other: Vec<&str> = Vec::new();
+    for line in raw.lines() {
+        if let Some(package) = package_header(line) {
+            if groups.last().is_none_or(|(p, _)| *p != package) {
+                groups.push((package, Vec::new()));
+            }
+        } else if is_go_diagnostic(line.strip_prefix("vet: ").unwrap_or(line)) {
+            if groups.is_empty() {
+                groups.push(("", Vec::new()));
+            }
+            groups.last_mut().expect("pushed above").1.push(vec![line]);
+        } else if line.starts_with('\t') || line.starts_with("    ") {
+            if let Some(entry) = groups.last_mut().and_then(|(_, e)| e.last_mut()) {
+                entry.push(line.trim());
+            }
+        } else if !line.trim().is_empty() {
+            other.push(line);
+        }
+    }
+
+    let total: usize = groups.iter().map(|(_, e)| e.len()).sum();
+    if total == 0 {
+        return truncate(raw, ctx);
+    }
+
+    let noun = if level == "error" { "errors" } else { "issues" };
+    let mut out = format!(
+        "[{label}] {total} {noun} in {} packages\n",
+        groups.iter().filter(|(_, e)| !e.is_empty()).count()
+    );
+    for line in &other {
+        out.push_str(&format!("{line}\n"));
+    }
+    for (package, entries) in groups.iter().filter(|(_, e)| !e.is_empty()) {
+        let name = if package.is_empty() { "." } else { package };
+        out.push_str(&format!("[{name}: {}]\n", entries.len()));
+        for entry in entries.iter().take(ctx.max_hits) {
+            out.push_str(&format!("  {}\n", entry[0]));
+            for cont in &entry[1..] {
+                out.push_str(&format!("      {cont}\n"));
+            }
+        }
+        if entries.len() > ctx.max_hits {
+            out.push_str(&format!("  … +{} more\n", entries.len() - ctx.max_hits));
+        }
+    }
+    out
+}
+
+// ── go mod tidy / download, go get ──
+
+/// Compress `go mod tidy` / `download` / `go get`: downloads collapse to a
+/// count, requirement changes are listed, anything else is kept as an error.
+fn compress_mod(raw: &str, ctx: &CompressCtx) -> String {
+    let mut downloads = 0usize;
+    let mut changes: Vec<&str> = Vec::new();
+    let mut errors: Vec<&str> = Vec::new();
+
+    for line in raw.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let msg = line.strip_prefix("go: ").unwrap_or(line);
+        if msg.starts_with("downloading ") {
+            downloads += 1;
+        } else if is_module_progress(msg) {
+            continue;
+        } else if ["found ", "added ", "upgraded ", "downgraded ", "removed "]
+            .iter()
+            .any(|p| msg.starts_with(p))
+        {
+            changes.push(msg);
+        } else {
+            errors.push(line);
+        }
+    }
+
+    let mut out = String::new();
+    if downloads > 0 {
+        out.push_str(&format!("[go mod] downloaded {downloads} modules\n"));
+    }
+    if !changes.is_empty() {
+        out.push_str(&format!("[changes: {}]\n", changes.len()));
+        for c in changes.iter().take(ctx.max_items) {
+            out.push_str(&format!("  {c}\n"));
+        }
+        if changes.len() > ctx.max_items {
+            out.push_str(&format!("  … +{} more\n", changes.len() - ctx.max_items));
+        }
+    }
+    if !errors.is_empty() {
+        out.push_str(&format!("[errors: {}]\n", errors.len()));
+        for e in errors.iter().take(ctx.max_hits) {
+            out.push_str(&format!("  {e}\n"));
+        }
+        if errors.len() > ctx.max_hits {
+            out.push_str(&format!("  … +{} more\n", errors.len() - ctx.max_hits));
+        }
+    }
+    if out.is_empty() {
+        return "[go mod] ok".into();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress::Compressor;
+
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
+    const TEST_OUTPUT: &str = "\
+=== RUN   TestAdd
+--- PASS: TestAdd (0.00s)
+=== RUN   TestSub
+    calc_test.go:14: got 3, want 4
+--- FAIL: TestSub (0.00s)
+=== RUN   TestTable
+=== RUN   TestTable/neg
+    calc_test.go:30: neg: got 1
+--- FAIL: TestTable (0.00s)
+    --- FAIL: TestTable/neg (0.00s)
+--- SKIP: TestSlow (0.00s)
+FAIL
+exit status 1
+FAIL\texample.com/calc\t0.004s
+ok  \texample.com/util\t0.002s
+?   \texample.com/cmd\t[no test files]
+";
+
+    // ── go test ──
+
+    #[test]
+    fn test_keeps_failures_and_package_lines() {
+        let result = compress_test(TEST_OUTPUT, &ctx());
+        assert!(result.starts_with("[go test]\n"));
+        assert!(result.contains("--- FAIL: TestSub (0.00s)\n    calc_test.go:14: got 3, want 4\n"));
+        assert!(result.contains(
+            "--- FAIL: TestTable (0.00s)\n    calc_test.go:30: neg: got 1\n    --- FAIL: TestTable/neg"
+        ));
+        assert!(result.contains("FAIL\texample.com/calc\t0.004s"));
+        assert!(result.contains("ok  \texample.com/util"));
+        assert!(!result.contains("=== RUN"));
+        assert!(!result.contains("--- PASS"));
+        assert!(!result.contains("no test files"));
+        assert!(!result.contains("\nFAIL\n"));
+    }
+
+    #[test]
+    fn test_text_report() {
+        let report = parse_test_report(TEST_OUTPUT);
+        assert_eq!((report.passed, report.failed, report.skipped), (1, 3, 1));
+        assert_eq!(
+            report.failures[0].message.as_deref(),
+            Some("calc_test.go:14: got 3, want 4")
+        );
+        assert_eq!(report.failures[1].name, "TestTable");
+        assert_eq!(
+            report.failures[1].message.as_deref(),
+            Some("--- FAIL: TestTable/neg (0.00s)")
+                .filter(|_| false)
+                .or(Some("calc_test.go:30: neg: got 1"))
+        );
+    }
+
+    #[test]
+    fn test_panic_keeps_project_frames() {
+        let raw = "\
+--- FAIL: TestIndex (0.00s)
+panic: runtime error: index out of range [5] with length 3 [recovered]
+\tpanic: runtime error: index out of range [5] with length 3
+
+goroutine 7 [running]:
+testing.tRunner.func1.2({0x5f1a20, 0xc000018150})
+\t/usr/local/go/src/testing/testing.go:1545 +0x238
+panic({0x5f1a20?, 0xc000018150?})
+\t/usr/local/go/src/runtime/panic.go:914 +0x21f
+example.com/calc.At(...)
+\t/home/me/calc/calc.go:9
+example.com/calc.TestIndex(0x0?)
+\t/home/me/calc/calc_test.go:40 +0x1d
+created by testing.(*T).Run in goroutine 1
+\t/usr/local/go/src/testing/testing.go:1648 +0x3ad
+exit status 2
+FAIL\texample.com/calc\t0.005s
+";
+        let result = compress_test(raw, &ctx());
+        assert!(result.contains("panic: runtime error: index out of range [5]"));
+        assert!(result.contains("example.com/calc.At(...)\n\t/home/me/calc/calc.go:9"));
+        assert!(result.contains("/home/me/calc/calc_test.go:40"));
+        assert!(!result.contains("testing.go"));
+        assert!(!result.contains("runtime/panic.go"));
+        assert!(result.ends_with("FAIL\texample.com/calc\t0.005s"));
+    }
+
+    #[test]
+    fn test_build_failure_in_test() {
+        let raw = "\
+# example.com/calc [example.com/calc.test]
+./calc_test.go:9:2: undefined: Mul
+FAIL\texample.com/calc [build failed]
+FAIL
+";
+        let result = compress_test(raw, &ctx());
+        assert!(result.contains("# example.com/calc [example.com/calc.test]"));
+        assert!(result.contains("./calc_test.go:9:2: undefined: Mul"));
+        assert_eq!(parse_test_report(raw).errors, 1);
+    }
+
+    #[test]
+    fn test_module_downloads_dropped_errors_kept() {
+        let raw = "\
+go: downloading github.com/stretchr/testify v1.9.0
+go: downloading github.com/davecgh/go-spew v1.1.1
+go: finding module for package github.com/nope/x
+go: example.com/calc imports github.com/nope/x: module github.com/nope/x: not found
+FAIL\texample.com/calc [setup failed]
+";
+        let result = compress_test(raw, &ctx());
+        assert_eq!(
+            result,
+            "[go test]\ngo: example.com/calc imports github.com/nope/x: module github.com/nope/x: not found\nFAIL\texample.com/calc [setup failed]"
+        );
+    }
+
+    #[test]
+    fn test_many_ok_packages_capped() {
+        let raw: String = (0..40)
+            .map(|i| format!("ok  \texample.com/p{i}\t0.01s\n"))
+            .collect();
+        let result = compress_test(&raw, &ctx());
+        assert!(result.contains("example.com/p29\t"));
+        assert!(!result.contains("example.com/p30\t"));
+        assert!(result.ends_with("  … +10 more ok"));
+    }
+
+    // ── go test -json ──
+
+    const JSON_OUTPUT: &str = r#"{"Action":"start","Package":"example.com/calc"}
+{"Action":"run","Package":"example.com/calc","Test":"TestAdd"}
+{"Action":"output","Package":"example.com/calc","Test":"TestAdd","Output":"=== RUN   TestAdd\n"}
+{"Action":"run","Package":"example.com/calc","Test":"TestSub"}
+{"Action":"output","Package":"example.com/calc","Test":"TestSub","Output":"=== RUN   TestSub\n"}
+{"Action":"output","Package":"example.com/calc","Test":"TestAdd","Output":"--- PASS: TestAdd (0.00s)\n"}
+{"Action":"pass","Package":"example.com/calc","Test":"TestAdd","Elapsed":0}
+{"Action":"output","Package":"example.com/calc","Test":"TestSub","Output":"    calc_test.go:14: got 3, want 4\n"}
+{"Action":"output","Package":"example.com/calc","Test":"TestSub","Output":"--- FAIL: TestSub (0.00s)\n"}
+{"Action":"fail","Package":"example.com/calc","Test":"TestSub","Elapsed":0}
+{"Action":"output","Package":"example.com/calc","Output":"FAIL\n"}
+{"Action":"output","Package":"example.com/calc","Output":"FAIL\texample.com/calc\t0.004s\n"}
+{"Action":"fail","Package":"example.com/calc","Elapsed":0.004}
+{"Action":"output","Package":"example.com/util","Output":"ok  \texample.com/util\t0.002s\n"}
+{"Action":"pass","Package":"example.com/util","Elapsed":0.002}
+"#;
+
+    #[test]
+    fn test_json_stream() {
+        let result = GoCompressor.compress(JSON_OUTPUT, Some("test"), &ctx());
+        assert_eq!(
+            result,
+            "[go test]\n--- FAIL: TestSub (0.00s)\n    calc_test.go:14: got 3, want 4\nFAIL\texample.com/calc\t0.004s\nok  \texample.com/util\t0.002s"
+        );
+        assert!(!result.contains("TestAdd"));
+    }
+
+    #[test]
+    fn test_json_report() {
+        let report = parse_test_report(JSON_OUTPUT);
+        assert_eq!((report.passed, report.failed, report.errors), (1, 1, 0));
+        assert_eq!(report.failures[0].name, "TestSub");
+        assert_eq!(
+            report.failures[0].message.as_deref(),
+            Some("calc_test.go:14: got 3, want 4")
+        );
+    }
+
+    #[test]
+    fn test_json_keeps_output_of_unfinished_test() {
+        let raw = r#"{"Action":"output","Package":"p","Test":"TestHang","Output":"panic: test timed out after 1s\n"}
+{"Action":"output","Package":"p","Output":"FAIL\tp\t1.01s\n"}
+"#;
+        let text = events_to_text(raw).unwrap();
+        assert_eq!(text, "FAIL\tp\t1.01s\npanic: test timed out after 1s\n");
+        assert_eq!(parse_test_report(raw).failed, 0);
+    }
+
+    // ── go build / vet ──
+
+    #[test]
+    fn test_build_grouped_by_package() {
+        let raw = "\
+# example.com/calc
+./calc.go:10:2: undefined: bar
+./calc.go:12:9: cannot use s (variable of type string) as int value in return statement
+\thave (string)
+\twant (int)
+# example.com/util
+util/util.go:3:8: \"os\" imported and not used
+";
+        let result = compress_build("go build", "error", raw, &ctx());
+        assert!(result.starts_with("[go build] 3 errors in 2 packages\n"));
+        assert!(result.contains("[example.com/calc: 2]\n  ./calc.go:10:2: undefined: bar\n"));
+        assert!(result.contains("in return statement\n      have (string)\n      want (int)\n"));
+        assert!(result.contains("[example.com/util: 1]\n  util/util.go:3:8"));
+
+        let v = GoCompressor.summarize(raw, Some("build"), &ctx());
+        assert_eq!(v["packages"][0]["package"], "example.com/calc");
+        assert_eq!(v["packages"][0]["diagnostics"][1]["line"], 12);
+        assert_eq!(v["packages"][1]["diagnostics"][0]["file"], "util/util.go");
+    }
+
+    #[test]
+    fn test_vet_merges_bracketed_header() {
+        let raw = "\
+# example.com/calc
+# [example.com/calc]
+./calc.go:7:2: fmt.Printf format %d has arg s of wrong type string
+";
+        let result = GoCompressor.compress(raw, Some("vet"), &ctx());
+        assert!(result.starts_with("[go vet] 1 issues in 1 packages\n[example.com/calc: 1]\n"));
+        let v = GoCompressor.summarize(raw, Some("vet"), &ctx());
+        assert_eq!(v["packages"][0]["diagnostics"][0]["level"], "warning");
+    }
+
+    #[test]
+    fn test_build_clean() {
+        assert_eq!(
+            GoCompressor.compress("", Some("build"), &ctx()),
+            "[go build] ok"
+        );
+        assert_eq!(
+            GoCompressor.compress("\n", Some("vet"), &ctx()),
+            "[go vet] clean"
+        );
+    }
+
+    #[test]
+    fn test_build_without_diagnostics_falls_back() {
+        let raw = "go: cannot find main module, but found .git/config";
+        let result = GoCompressor.compress(raw, Some("build"), &ctx());
+        assert!(result.contains("cannot find main module"));
+    }
+
+    // ── go mod ──
+
+    #[test]
+    fn test_mod_tidy_counts_downloads() {
+        let raw = "\
+go: finding module for package github.com/pkg/errors
+go: downloading github.com/pkg/errors v0.9.1
+go: downloading golang.org/x/sys v0.15.0
+go: found github.com/pkg/errors in github.com/pkg/errors v0.9.1
+";
+        let result = compress_mod(raw, &ctx());
+        assert_eq!(
+            result,
+            "[go mod] downloaded 2 modules\n[changes: 1]\n  found github.com/pkg/errors in github.com/pkg/errors v0.9.1\n"
+        );
+    }
+
+    #[test]
+    fn test_mod_errors_and_clean() {
+        let raw = "go: example.com/calc imports\n\tgithub.com/nope/x: cannot find module providing package github.com/nope/x\n";
+        let result = compress_mod(raw, &ctx());
+        assert!(result.starts_with("[errors: 2]"));
+        assert_eq!(compress_mod("", &ctx()), "[go mod] ok");
+    }
+}
@@ -1,48 +1,83 @@
-use super::Compressor;
+use super::truncate::cut;
+use super::{CompressCtx, Compressor};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// Characters kept per match line (still capped by `max_line_len`).
+const MATCH_LINE_LEN: usize = 200;
 
 /// Pure compressor for grep / ripgrep output.
 pub struct GrepCompressor;
 
 impl Compressor for GrepCompressor {
-    fn compress(&self, raw: &str, _sub: Option<&str>) -> String {
-        compress_grep(raw)
+    fn compress(&self, raw: &str, _sub: Option<&str>, ctx: &CompressCtx) -> String {
+        compress_grep(raw, ctx)
     }
-}
 
-/// Group grep results by file and truncate per-file hits.
-fn compress_grep(raw: &str) -> String {
-    if raw.trim().is_empty() {
-        return "[grep] no matches".into();
+    fn summarize(&self, raw: &str, _sub: Option<&str>, _ctx: &CompressCtx) -> Value {
+        json!(group_matches(raw))
     }
+}
+
+/// Matches grouped by file, in output order.
+#[derive(Debug, Default, Serialize)]
+struct GrepSummary<'a> {
+    matches: usize,
+    files: Vec<FileHits<'a>>,
+}
 
-    let mut files: Vec<(&str, Vec<&str>)> = Vec::new();
-    let mut total_matches = 0usize;
+#[derive(Debug, Serialize)]
+struct FileHits<'a> {
+    file: &'a str,
+    hits: Vec<&'a str>,
+}
 
-    for line in raw.lines() {
-        total_matches += 1;
-        if let Some(colon) = line.find(':') {
-            let file = &line[..colon];
-            let rest = &line[colon + 1..];
+fn group_matches(raw: &str) -> GrepSummary<'_> {
+    let mut summary = GrepSummary::default();
 
-            if let Some(last) = files.last_mut()
-                && last.0 == file
+    for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+        summary.matches += 1;
+        if let Some((file, rest)) = line.split_once(':') {
+            if let Some(last) = summary.files.last_mut()
+                && last.file == file
             {
-                last.1.push(rest);
+                last.hits.push(rest);
                 continue;
             }
-            files.push((file, vec![rest]));
+            summary.files.push(FileHits {
+                file,
+                hits: vec![rest],
+            });
         }
     }
+    summary
+}
+
+/// Group grep results by file and truncate per-file hits.
+fn compress_grep(raw: &str, ctx: &CompressCtx) -> String {
+    if raw.trim().is_empty() {
+        return "[grep] no matches".into();
+    }
+
+    let GrepSummary {
+        matches: total_matches,
+        files,
+    } = group_matches(raw);
+
+    let max_len = MATCH_LINE_LEN.min(ctx.max_line_len);
 
     let mut out = format!("[grep] {total_matches} matches in {} files\n", files.len());
-    for (file, matches) in &files {
+    for FileHits {
+        file,
+        hits: matches,
+    } in &files
+    {
         out.push_str(&format!("\n── {} ({} hits)\n", file, matches.len()));
-        for m in matches.iter().take(10) {
-            let display = if m.len() > 200 { &m[..200] } else { m };
-            out.push_str(&format!("  {display}\n"));
+        for m in matches.iter().take(ctx.max_hits) {
+            out.push_str(&format!("  {}\n", cut(m, max_len)));
         }
-        if matches.len() > 10 {
-            out.push_str(&format!("  … +{} more\n", matches.len() - 10));
+        if matches.len() > ctx.max_hits {
+            out.push_str(&format!("  … +{} more\n", matches.len() - ctx.max_hits));
         }
     }
     out
@@ -53,20 +88,24 @@ mod tests {
     use super::*;
     use crate::compress::Compressor;
 
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
     #[test]
     fn test_empty_input() {
-        assert_eq!(compress_grep(""), "[grep] no matches");
+        assert_eq!(compress_grep("", &ctx()), "[grep] no matches");
     }
 
     #[test]
     fn test_whitespace_only() {
-        assert_eq!(compress_grep("   \n  \n"), "[grep] no matches");
+        assert_eq!(compress_grep("   \n  \n", &ctx()), "[grep] no matches");
     }
 
     #[test]
     fn test_single_match() {
         let raw = "src/main.rs:10:fn main() {";
-        let result = compress_grep(raw);
+        let result = compress_grep(raw, &ctx());
         assert!(result.contains("1 matches in 1 files"));
         assert!(result.contains("── src/main.rs (1 hits)"));
         assert!(result.contains("10:fn main() {"));
@@ -79,7 +118,7 @@ src/a.rs:1:fn foo()
 src/a.rs:5:fn bar()
 src/b.rs:2:fn baz()
 ";
-        let result = compress_grep(raw);
+        let result = compress_grep(raw, &ctx());
         assert!(result.contains("3 matches in 2 files"));
         assert!(result.contains("── src/a.rs (2 hits)"));
         assert!(result.contains("── src/b.rs (1 hits)"));
@@ -91,24 +130,45 @@ src/b.rs:2:fn baz()
         for i in 0..15 {
             raw.push_str(&format!("big_file.rs:{i}:match line {i}\n"));
         }
-        let result = compress_grep(&raw);
+        let result = compress_grep(&raw, &ctx());
         assert!(result.contains("15 matches in 1 files"));
         assert!(result.contains("── big_file.rs (15 hits)"));
         assert!(result.contains("… +5 more"));
     }
 
+    #[test]
+    fn test_max_hits_from_ctx() {
+        let raw = "f.rs:1:a\nf.rs:2:b\nf.rs:3:c\n";
+        let ctx = CompressCtx {
+            max_hits: 1,
+            ..ctx()
+        };
+        let result = compress_grep(raw, &ctx);
+        assert!(result.contains("1:a"));
+        assert!(!result.contains("2:b"));
+        assert!(result.contains("… +2 more"));
+    }
+
     #[test]
     fn test_long_match_line_truncated() {
         let long_content = "x".repeat(300);
         let raw = format!("file.rs:1:{long_content}");
-        let result = compress_grep(&raw);
+        let result = compress_grep(&raw, &ctx());
         assert!(result.len() < raw.len());
     }
 
+    #[test]
+    fn test_long_non_ascii_match_cut_on_char_boundary() {
+        let raw = format!("notes.md:1:{}\n", "日本語".repeat(100));
+        let result = compress_grep(&raw, &ctx());
+        assert!(result.contains(&"日本語".repeat(60)));
+        assert!(!result.contains(&"日本語".repeat(70)));
+    }
+
     #[test]
     fn test_non_colon_line_counted() {
         let raw = "no colon here\nsrc/a.rs:1:match\n";
-        let result = compress_grep(raw);
+        let result = compress_grep(raw, &ctx());
         assert!(result.contains("2 matches in 1 files"));
     }
 
@@ -119,25 +179,35 @@ z.rs:1:last
 a.rs:1:first
 m.rs:1:middle
 ";
-        let result = compress_grep(raw);
+        let result = compress_grep(raw, &ctx());
         assert!(result.contains("3 matches in 3 files"));
         assert!(result.contains("── z.rs"));
         assert!(result.contains("── a.rs"));
         assert!(result.contains("── m.rs"));
     }
 
+    #[test]
+    fn test_summary_groups_by_file() {
+        let raw = "src/a.rs:1:fn foo()\nsrc/a.rs:5:fn bar()\nsrc/b.rs:2:fn baz()\n";
+        let v = GrepCompressor.summarize(raw, None, &ctx());
+        assert_eq!(v["matches"], 3);
+        assert_eq!(v["files"][0]["file"], "src/a.rs");
+        assert_eq!(v["files"][0]["hits"][1], "5:fn bar()");
+        assert_eq!(v["files"][1]["hits"].as_array().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_trait_compress() {
         let c = GrepCompressor;
         let raw = "f.rs:1:hello\n";
-        let result = c.compress(raw, None);
+        let result = c.compress(raw, None, &ctx());
         assert!(result.contains("[grep]"));
     }
 
     #[test]
     fn test_trait_ignores_sub() {
         let c = GrepCompressor;
-        let result = c.compress("", Some("anything"));
+        let result = c.compress("", Some("anything"), &ctx());
         assert_eq!(result, "[grep] no matches");
     }
 }
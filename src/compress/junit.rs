@@ -0,0 +1,404 @@
+//! JUnit XML reports (`cargo nextest`, `pytest --junitxml`, …) as a [`TestReport`].
+//!
+//! Only the elements test runners actually emit are understood: this is a
+//! tag scanner, not a general XML parser.
+
+use super::summary::{
+    Location, SuiteReport, TestFailure, TestReport, TestTiming, parse_location, parse_panic,
+};
+
+/// Line `run_compressed` puts between the command output and the report it
+/// appends (see `RunOpts::report`), so XML the command printed itself is
+/// never taken for the report.
+pub const REPORT_HEADER: &str = "# cx.report\n";
+
+/// Split command output followed by a report into the output and the report
+/// document.
+pub fn split(text: &str) -> (&str, Option<&str>) {
+    match text.find(REPORT_HEADER) {
+        Some(start) => (&text[..start], Some(&text[start + REPORT_HEADER.len()..])),
+        None => (text, None),
+    }
+}
+
+/// Parse the first `<testsuites>` / `<testsuite>` document found in `text`.
+pub fn parse(text: &str) -> Option<TestReport> {
+    let xml = &text[text.find("<testsuite")?..];
+    let mut report = TestReport::default();
+    let mut case: Option<Case> = None;
+    let mut pos = 0;
+
+    while let Some(tag) = next_tag(xml, pos) {
+        pos = tag.end;
+        match (tag.name, tag.closing) {
+            ("testsuite", false) => report.suites.push(SuiteReport {
+                name: attr(tag.attrs, "name").unwrap_or_default(),
+                ..SuiteReport::default()
+            }),
+            ("testcase", false) => {
+                let suite = report.suites.last().map(|s| s.name.as_str()).unwrap_or("");
+                let name = attr(tag.attrs, "name").unwrap_or_default();
+                let name = match attr(tag.attrs, "classname") {
+                    Some(class) if !class.is_empty() && class != suite => {
+                        format!("{class}::{name}")
+                    }
+                    _ => name,
+                };
+                let next = Case {
+                    name,
+                    secs: attr(tag.attrs, "time").and_then(|t| t.parse().ok()),
+                    ..Case::default()
+                };
+                if tag.self_closing {
+                    finish(&mut report, next);
+                } else {
+                    case = Some(next);
+                }
+            }
+            ("failure" | "error", false) => {
+                let Some(case) = case.as_mut() else { continue };
+                let body = if tag.self_closing {
+                    String::new()
+                } else {
+                    let close = format!("</{}", tag.name);
+                    let end = xml[pos..].find(&close).map_or(xml.len(), |i| pos + i);
+                    let body = text_content(&xml[pos..end]);
+                    pos = end;
+                    body
+                };
+                case.outcome = Some(if tag.name == "error" {
+                    Outcome::Error
+                } else {
+                    Outcome::Failure
+                });
+                case.message = attr(tag.attrs, "message");
+                case.body = body;
+            }
+            ("skipped", false) => {
+                if let Some(case) = case.as_mut() {
+                    // pytest reports expected failures as skips of this type.
+                    let xfail = attr(tag.attrs, "type").is_some_and(|t| t == "pytest.xfail");
+                    case.outcome = Some(if xfail {
+                        Outcome::XFailed
+                    } else {
+                        Outcome::Skipped
+                    });
+                }
+            }
+            ("testcase", true) => {
+                if let Some(case) = case.take() {
+                    finish(&mut report, case);
+                }
+            }
+            ("testsuites", true) => break,
+            _ => {}
+        }
+    }
+    Some(report)
+}
+
+#[derive(Debug, Default)]
+struct Case {
+    name: String,
+    secs: Option<f64>,
+    outcome: Option<Outcome>,
+    message: Option<String>,
+    body: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Failure,
+    Error,
+    Skipped,
+    XFailed,
+}
+
+/// Count a finished test case towards the report and its suite.
+fn finish(report: &mut TestReport, case: Case) {
+    if report.suites.is_empty() {
+        report.suites.push(SuiteReport::default());
+    }
+    let suite = report.suites.last_mut().expect("pushed above");
+    match case.outcome {
+        None => {
+            report.passed += 1;
+            suite.passed += 1;
+        }
+        Some(Outcome::Skipped) => {
+            report.skipped += 1;
+            suite.skipped += 1;
+        }
+        Some(Outcome::XFailed) => {
+            report.xfailed += 1;
+            suite.skipped += 1;
+        }
+        Some(outcome) => {
+            if outcome == Outcome::Error {
+                report.errors += 1;
+            } else {
+                report.failed += 1;
+            }
+            suite.failed += 1;
+            let lines: Vec<&str> = case.body.lines().collect();
+            let (message, loc) = match parse_panic(&lines) {
+                Some((message, loc)) => (Some(message), loc),
+                None => (
+                    case.message
+                        .filter(|m| !m.is_empty())
+                        .or_else(|| first_line(&case.body)),
+                    last_location(&lines),
+                ),
+            };
+            report.failures.push(TestFailure {
+                name: case.name.clone(),
+                message,
+                file: loc.as_ref().map(|l| l.file.clone()),
+                line: loc.and_then(|l| l.line),
+            });
+        }
+    }
+    if let Some(secs) = case.secs {
+        report.slowest.push(TestTiming {
+            name: case.name,
+            secs,
+        });
+    }
+}
+
+fn first_line(body: &str) -> Option<String> {
+    body.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+/// The last `file:line:` in a traceback: where the assertion fired.
+fn last_location(lines: &[&str]) -> Option<Location> {
+    lines
+        .iter()
+        .rev()
+        .filter_map(|l| parse_location(l.trim()))
+        .map(|(loc, _)| loc)
+        .find(|loc| loc.file.contains('.'))
+}
+
+// ── tag scanning ──
+
+struct Tag<'a> {
+    name: &'a str,
+    attrs: &'a str,
+    closing: bool,
+    self_closing: bool,
+    /// Byte offset just past the tag's `>`.
+    end: usize,
+}
+
+/// The next element tag at or after `pos`, skipping comments, CDATA and
+/// processing instructions.
+fn next_tag(xml: &str, mut pos: usize) -> Option<Tag<'_>> {
+    loop {
+        let start = pos + xml[pos..].find('<')?;
+        let rest = &xml[start..];
+        let skip_to = |end: &str| rest.find(end).map(|i| start + i + end.len());
+        if rest.starts_with("<!--") {
+            pos = skip_to("-->")?;
+            continue;
+        }
+        if rest.starts_with("<![CDATA[") {
+            pos = skip_to("]]>")?;
+            continue;
+        }
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            pos = skip_to(">")?;
+            continue;
+        }
+
+        let end = start + rest.find('>')? + 1;
+        let inner = &xml[start + 1..end - 1];
+        let closing = inner.starts_with('/');
+        let self_closing = inner.ends_with('/');
+        let inner = inner.trim_start_matches('/').trim_end_matches('/');
+        let (name, attrs) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+        return Some(Tag {
+            name,
+            attrs,
+            closing,
+            self_closing,
+            end,
+        });
+    }
+}
+
+/// Value of `key="…"` (or `key='…'`) in a tag's attribute list, unescaped.
+fn attr(attrs: &str, key: &str) -> Option<String> {
+    let mut rest = attrs;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim();
+        let after = rest[eq + 1..].trim_start();
+        let quote = after.chars().next()?;
+        let value_end = after[1..].find(quote)? + 1;
+        if name == key {
+            return Some(unescape(&after[1..value_end]));
+        }
+        rest = &after[value_end + 1..];
+    }
+    None
+}
+
+/// Element text with CDATA sections unwrapped and entities decoded.
+fn text_content(raw: &str) -> String {
+    let mut out = String::new();
+    let mut rest = raw;
+    while let Some(start) = rest.find("<![CDATA[") {
+        out.push_str(&unescape(&rest[..start]));
+        let body = &rest[start + 9..];
+        let end = body.find("]]>").unwrap_or(body.len());
+        out.push_str(&body[..end]);
+        rest = body.get(end + 3..).unwrap_or("");
+    }
+    out.push_str(&unescape(rest));
+    out
+}
+
+fn unescape(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';').filter(|&i| i <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let decoded = match &rest[1..semi] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            e => e
+                .strip_prefix("#x")
+                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                .or_else(|| e.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEXTEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="nextest-run" tests="4" failures="1" errors="0" uuid="x" timestamp="2024-01-01T00:00:00Z" time="0.120">
+    <testsuite name="demo" tests="3" disabled="1" errors="0" failures="1">
+        <testcase name="tests::a" classname="demo" timestamp="t" time="0.004">
+        </testcase>
+        <testcase name="tests::b" classname="demo" timestamp="t" time="0.105">
+            <failure type="test failure">thread &apos;tests::b&apos; panicked at src/lib.rs:9:5:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace</failure>
+            <system-out><![CDATA[running 1 test <ok>]]></system-out>
+        </testcase>
+        <testcase name="tests::c" classname="demo" time="0.000">
+            <skipped/>
+        </testcase>
+    </testsuite>
+    <testsuite name="demo::api" tests="1" disabled="0" errors="0" failures="0">
+        <testcase name="get" classname="demo::api" time="0.011"/>
+    </testsuite>
+</testsuites>
+"#;
+
+    #[test]
+    fn test_nextest_junit() {
+        let report = parse(NEXTEST).unwrap();
+        assert_eq!(
+            (report.passed, report.failed, report.skipped, report.errors),
+            (2, 1, 1, 0)
+        );
+        assert_eq!(report.suites.len(), 2);
+        assert_eq!(report.suites[0].name, "demo");
+        assert_eq!(report.suites[0].failed, 1);
+        assert_eq!(report.suites[1].passed, 1);
+
+        let f = &report.failures[0];
+        assert_eq!(f.name, "tests::b");
+        assert_eq!(
+            f.message.as_deref(),
+            Some("assertion `left == right` failed\n  left: 1\n right: 2")
+        );
+        assert_eq!((f.file.as_deref(), f.line), (Some("src/lib.rs"), Some(9)));
+        assert_eq!(report.slowest.len(), 4);
+    }
+
+    #[test]
+    fn test_pytest_junit() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?><testsuites><testsuite name="pytest" errors="1" failures="1" skipped="0" tests="3" time="0.05"><testcase classname="tests.test_a" name="test_ok" time="0.001" /><testcase classname="tests.test_a" name="test_eq" time="0.002"><failure message="assert 1 == 2">def test_eq():
+&gt;       assert 1 == 2
+E       assert 1 == 2
+
+tests/test_a.py:5: AssertionError</failure></testcase><testcase classname="tests.test_a" name="test_todo" time="0.001"><skipped type="pytest.xfail" message="not yet" /></testcase><testcase classname="tests.test_a" name="test_db" time="0.001"><error message="failed on setup with &quot;fixture 'db' not found&quot;">file tests/test_a.py, line 7</error></testcase></testsuite></testsuites>"#;
+        let report = parse(xml).unwrap();
+        assert_eq!((report.passed, report.failed, report.errors), (1, 1, 1));
+        assert_eq!((report.skipped, report.xfailed), (0, 1));
+        let f = &report.failures[0];
+        assert_eq!(f.name, "tests.test_a::test_eq");
+        assert_eq!(f.message.as_deref(), Some("assert 1 == 2"));
+        assert_eq!(
+            (f.file.as_deref(), f.line),
+            (Some("tests/test_a.py"), Some(5))
+        );
+        assert_eq!(
+            report.failures[1].message.as_deref(),
+            Some("failed on setup with \"fixture 'db' not found\"")
+        );
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(
+            unescape("a &lt;b&gt; &amp; &#39;c&#x27; &bogus"),
+            "a <b> & 'c' &bogus"
+        );
+    }
+
+    #[test]
+    fn test_not_junit() {
+        assert!(parse("running 3 tests").is_none());
+        assert_eq!(split("running 3 tests"), ("running 3 tests", None));
+    }
+
+    #[test]
+    fn test_split_output_and_report() {
+        let (output, xml) =
+            split("1 passed in 0.01s\n# cx.report\n<?xml version=\"1.0\"?><testsuites/>");
+        assert_eq!(output, "1 passed in 0.01s\n");
+        assert_eq!(xml, Some("<?xml version=\"1.0\"?><testsuites/>"));
+    }
+
+    #[test]
+    fn test_split_ignores_xml_in_output() {
+        let text = "<?xml version=\"1.0\"?><config/>\n<testsuite name=\"fixture\">";
+        assert_eq!(split(text), (text, None));
+    }
+}
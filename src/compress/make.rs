@@ -0,0 +1,553 @@
+//! Task runners (`make`, `just`, `task`): strip the runner's own noise, then
+//! hand the recipe output to the compressor of the tool it ran.
+
+use super::truncate::truncate;
+use super::{CompressCtx, Compressor, cargo, go, node, python, summary};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// First line of the section `MakeTool` appends after the output: the
+/// command lines a dry run (`make -n`, `just --dry-run`) printed, one per
+/// line. Output lines matching one exactly are the runner's echo.
+pub const RECIPES_HEADER: &str = "# cx.recipes\n";
+
+/// Pure compressor for task-runner output.
+pub struct MakeCompressor;
+
+impl Compressor for MakeCompressor {
+    fn compress(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> String {
+        if sub == Some("targets") {
+            return format_targets(&parse_listing(raw), ctx);
+        }
+        let run = strip_noise(raw);
+        let mut out = match route(&run.recipes, &run.output) {
+            Some((compressor, sub)) => compressor.compress(&run.output, Some(sub), ctx),
+            None => truncate(&run.output, ctx),
+        };
+        // The runner's own failure line names the target that broke.
+        for line in &run.errors {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(line);
+        }
+        out
+    }
+
+    fn summarize(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> Value {
+        if sub == Some("targets") {
+            return json!({ "targets": parse_listing(raw) });
+        }
+        let run = strip_noise(raw);
+        match route(&run.recipes, &run.output) {
+            Some((compressor, sub)) => compressor.summarize(&run.output, Some(sub), ctx),
+            None => summary::lines(&self.compress(raw, sub, ctx)),
+        }
+    }
+}
+
+/// A runnable target with its description, if the file documents it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Target {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+}
+
+// ── noise ──
+
+/// Runner output split into its parts.
+struct RunOutput<'a> {
+    /// What the recipes printed.
+    output: String,
+    /// Recipe command lines the runner echoed (`make` / `just` verbatim, as
+    /// listed after [`RECIPES_HEADER`]; `task` as `task: [name] command`).
+    recipes: Vec<&'a str>,
+    /// The runner's own failure lines (`make: *** [test] Error 1`).
+    errors: Vec<&'a str>,
+}
+
+fn strip_noise(raw: &str) -> RunOutput<'_> {
+    let (raw, echoed): (&str, Vec<&str>) = match raw.find(RECIPES_HEADER) {
+        Some(start) => (
+            // Without the blank line that separates the section.
+            raw[..start].trim_end_matches('\n'),
+            raw[start + RECIPES_HEADER.len()..]
+                .lines()
+                .map(str::trim_end)
+                .filter(|l| !l.is_empty())
+                .collect(),
+        ),
+        None => (raw, Vec::new()),
+    };
+    let mut run = RunOutput {
+        output: String::new(),
+        recipes: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for line in raw.lines() {
+        if is_directory_notice(line) {
+            continue;
+        }
+        if is_runner_error(line) {
+            run.errors.push(line);
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("task: [")
+            && let Some((_, command)) = rest.split_once("] ")
+        {
+            run.recipes.push(command);
+            continue;
+        }
+        if echoed.contains(&line.trim_end()) {
+            run.recipes.push(line);
+            continue;
+        }
+        run.output.push_str(line);
+        run.output.push('\n');
+    }
+    run
+}
+
+/// `make: *** [Makefile:3: test] Error 1`, `error: Recipe `test` failed …`
+/// (just), `task: Failed to run task "test": …`.
+fn is_runner_error(line: &str) -> bool {
+    (line.starts_with("make") && line.contains(": *** "))
+        || (line.starts_with("error: Recipe `") && line.contains("failed"))
+        || line.starts_with("task: Failed to run task")
+}
+
+/// `make[1]: Entering directory '/src/sub'` and its `Leaving` twin.
+fn is_directory_notice(line: &str) -> bool {
+    line.starts_with("make")
+        && (line.contains(": Entering directory") || line.contains(": Leaving directory"))
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(k, _)| {
+        !k.is_empty() && k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// `/usr/bin/python3` → `python3`, `./node_modules/.bin/jest` → `jest`.
+fn program_name(word: &str) -> &str {
+    word.rsplit('/').next().unwrap_or(word)
+}
+
+// ── routing ──
+
+type Route = (&'static dyn Compressor, &'static str);
+
+/// Pick a compressor from the last recipe that ran a known tool, else from
+/// markers in the output itself (recipes prefixed with `@` are not echoed).
+fn route(recipes: &[&str], output: &str) -> Option<Route> {
+    recipes
+        .iter()
+        .rev()
+        .find_map(|r| route_command(r))
+        .or_else(|| sniff(output))
+}
+
+/// Route one shell line by the last `&&` / `;` / `|` segment that runs a known tool.
+fn route_command(line: &str) -> Option<Route> {
+    line.rsplit(['&', ';', '|']).find_map(|segment| {
+        let words: Vec<&str> = segment
+            .split_whitespace()
+            .skip_while(|w| is_assignment(w))
+            .collect();
+        route_words(&words)
+    })
+}
+
+fn route_words(words: &[&str]) -> Option<Route> {
+    let (first, rest) = words.split_first()?;
+    let next = rest.first().copied().unwrap_or("");
+    match program_name(first) {
+        // Wrappers: route whatever they run.
+        "uv" | "poetry" | "pipenv" | "pdm" | "hatch" if next == "run" => route_words(&rest[1..]),
+        "python" | "python3" if next == "-m" => route_words(&rest[1..]),
+        "env" | "npx" | "time" => route_words(rest),
+        "cargo" => {
+            let sub = match next {
+                "test" | "nextest" => "test",
+                "build" | "check" | "b" | "c" => "build",
+                "clippy" => "clippy",
+                "fmt" => "fmt",
+                _ => return None,
+            };
+            Some((&cargo::CargoCompressor, sub))
+        }
+        "pytest" | "py.test" => Some((&python::PythonCompressor, "pytest")),
+        "ruff" => Some((&python::PythonCompressor, "ruff")),
+        "mypy" => Some((&python::PythonCompressor, "mypy")),
+        "go" => {
+            let sub = match next {
+                "test" => "test",
+                "build" | "install" => "build",
+                "vet" => "vet",
+                _ => return None,
+            };
+            Some((&go::GoCompressor, sub))
+        }
+        "jest" | "vitest" | "mocha" => Some((&node::NodeCompressor, "test")),
+        "npm" | "pnpm" | "yarn" | "bun" => {
+            let script = if next == "run" {
+                rest.get(1).copied().unwrap_or("")
+            } else {
+                next
+            };
+            match script {
+                "test" | "t" => Some((&node::NodeCompressor, "test")),
+                "install" | "i" | "ci" | "add" => Some((&node::NodeCompressor, "install")),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Recognise a tool by the summary lines it prints.
+fn sniff(output: &str) -> Option<Route> {
+    let lines = || output.lines();
+    if lines().any(|l| l.starts_with("test result:")) {
+        Some((&cargo::CargoCompressor, "test"))
+    } else if lines()
+        .any(|l| l.contains("test session starts") || l.contains("short test summary info"))
+    {
+        Some((&python::PythonCompressor, "pytest"))
+    } else if lines()
+        .any(|l| l.starts_with("--- FAIL:") || l.starts_with("ok  \t") || l.starts_with("FAIL\t"))
+    {
+        Some((&go::GoCompressor, "test"))
+    } else if lines().any(|l| {
+        let l = l.trim_start();
+        (l.starts_with("Tests:") && l.contains(" total")) || l.starts_with("Test Files ")
+    }) {
+        Some((&node::NodeCompressor, "test"))
+    } else if lines().any(|l| l.starts_with("error[E") || l.trim_start().starts_with("Finished `"))
+    {
+        Some((&cargo::CargoCompressor, "build"))
+    } else {
+        None
+    }
+}
+
+// ── targets ──
+
+/// Targets defined in a Makefile: `name: deps ## description`. Special
+/// (`.PHONY`), pattern (`%.o`) and variable-named targets are skipped; a
+/// `## description` comment on the line above also documents the target.
+pub fn makefile_targets(text: &str) -> Vec<Target> {
+    let mut targets: Vec<Target> = Vec::new();
+    let mut comment: Option<&str> = None;
+
+    for line in text.lines() {
+        if let Some(doc) = line.strip_prefix("##") {
+            comment = Some(doc.trim());
+            continue;
+        }
+        let doc = comment.take();
+        if line.starts_with(['\t', ' ', '#']) {
+            continue;
+        }
+        let Some((names, after)) = line.split_once(':') else {
+            continue;
+        };
+        // `VAR := x`, `VAR ::= x` are assignments, not rules.
+        if after.starts_with('=') || after.starts_with(":=") || names.contains('=') {
+            continue;
+        }
+        let doc = after
+            .split_once("##")
+            .map(|(_, d)| d.trim())
+            .or(doc)
+            .filter(|d| !d.is_empty());
+        for name in names.split_whitespace() {
+            if name.starts_with('.') || name.contains(['%', '$', '(']) {
+                continue;
+            }
+            if !targets.iter().any(|t| t.name == name) {
+                targets.push(Target {
+                    name: name.to_string(),
+                    doc: doc.map(str::to_string),
+                });
+            }
+        }
+    }
+    targets
+}
+
+/// Targets from `just --list` or `task --list-all` output.
+fn parse_listing(raw: &str) -> Vec<Target> {
+    let mut targets = Vec::new();
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        // task: `* build:   Build the project`
+        if let Some(entry) = trimmed.strip_prefix("* ") {
+            let (name, doc) = entry.split_once(':').unwrap_or((entry, ""));
+            targets.push(Target {
+                name: name.trim().to_string(),
+                doc: Some(doc.trim())
+                    .filter(|d| !d.is_empty())
+                    .map(str::to_string),
+            });
+            continue;
+        }
+        // just: `    build *args  # Build the project`, under `Available recipes:`
+        if !line.starts_with([' ', '\t']) || trimmed.is_empty() || trimmed.starts_with('[') {
+            continue;
+        }
+        let (signature, doc) = match trimmed.split_once(" # ") {
+            Some((s, d)) => (s, Some(d.trim().to_string())),
+            None => (trimmed, None),
+        };
+        if let Some(name) = signature.split_whitespace().next() {
+            targets.push(Target {
+                name: name.to_string(),
+                doc,
+            });
+        }
+    }
+    targets
+}
+
+/// `[targets: N]` with one `name — description` line per target.
+pub fn format_targets(targets: &[Target], ctx: &CompressCtx) -> String {
+    if targets.is_empty() {
+        return "[targets] none found".into();
+    }
+    let mut out = format!("[targets: {}]\n", targets.len());
+    for t in targets.iter().take(ctx.max_items) {
+        match &t.doc {
+            Some(doc) => out.push_str(&format!("  {} — {doc}\n", t.name)),
+            None => out.push_str(&format!("  {}\n", t.name)),
+        }
+    }
+    if targets.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", targets.len() - ctx.max_items));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
+    // ── noise ──
+
+    #[test]
+    fn test_strips_directory_notices_and_echoed_recipes() {
+        let raw = "\
+make[1]: Entering directory '/src/sub'
+mkdir -p build
+GOOS=linux go build -o build/app ./cmd/app
+built build/app
+make[1]: Leaving directory '/src/sub'
+# cx.recipes
+mkdir -p build
+GOOS=linux go build -o build/app ./cmd/app
+";
+        let run = strip_noise(raw);
+        assert_eq!(run.output, "built build/app\n");
+        assert_eq!(
+            run.recipes,
+            vec![
+                "mkdir -p build",
+                "GOOS=linux go build -o build/app ./cmd/app"
+            ]
+        );
+        assert!(run.errors.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_output_is_truncated_and_keeps_make_error() {
+        let raw = "echo deploying\ndeploying\nmake: *** [Makefile:4: deploy] Error 1\n# cx.recipes\necho deploying\n";
+        let result = MakeCompressor.compress(raw, Some("run"), &ctx());
+        assert_eq!(result, "deploying\nmake: *** [Makefile:4: deploy] Error 1");
+    }
+
+    #[test]
+    fn test_keeps_output_that_only_looks_like_a_command() {
+        let raw = "\
+npm run build
+npm error code ELIFECYCLE
+npm error Missing script: build
+true positive check
+all good
+make: *** [Makefile:2: build] Error 1
+# cx.recipes
+npm run build
+";
+        let result = MakeCompressor.compress(raw, Some("run"), &ctx());
+        assert_eq!(
+            result,
+            "npm error code ELIFECYCLE\nnpm error Missing script: build\ntrue positive check\nall good\nmake: *** [Makefile:2: build] Error 1"
+        );
+    }
+
+    // ── routing ──
+
+    #[test]
+    fn test_routes_cargo_test_recipe() {
+        let raw = "\
+cargo test --workspace
+   Compiling demo v0.1.0
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.50s
+     Running unittests src/lib.rs
+
+running 2 tests
+test tests::a ... ok
+test tests::b ... FAILED
+
+failures:
+
+---- tests::b stdout ----
+thread 'tests::b' panicked at src/lib.rs:9:5:
+boom
+
+failures:
+    tests::b
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+
+make: *** [Makefile:2: test] Error 101
+# cx.recipes
+cargo test --workspace
+";
+        let result = MakeCompressor.compress(raw, Some("run"), &ctx());
+        assert!(result.starts_with("[cargo test]\n"));
+        assert!(result.contains("---- tests::b stdout ----"));
+        assert!(!result.contains("tests::a ... ok"));
+        assert!(result.ends_with("make: *** [Makefile:2: test] Error 101"));
+
+        let v = MakeCompressor.summarize(raw, Some("run"), &ctx());
+        assert_eq!(v["passed"], 1);
+        assert_eq!(v["failures"][0]["name"], "tests::b");
+    }
+
+    #[test]
+    fn test_routes_wrapped_and_chained_commands() {
+        let route_sub = |line: &str| route_command(line).map(|(_, sub)| sub);
+        assert_eq!(route_sub("uv run pytest -x tests/"), Some("pytest"));
+        assert_eq!(route_sub("python3 -m pytest"), Some("pytest"));
+        assert_eq!(
+            route_sub("cd api && GOFLAGS=-mod=mod go test ./..."),
+            Some("test")
+        );
+        assert_eq!(
+            route_sub("cargo clippy -- -D warnings | tee lint.log"),
+            Some("clippy")
+        );
+        assert_eq!(route_sub("npm run test -- --ci"), Some("test"));
+        assert_eq!(route_sub("./node_modules/.bin/jest"), Some("test"));
+        assert_eq!(route_sub("rm -rf dist"), None);
+        assert_eq!(route_sub("cargo run --release"), None);
+    }
+
+    #[test]
+    fn test_task_prefix_is_a_recipe() {
+        let raw = "task: [test] go test ./...\nok  \texample.com/calc\t0.01s\n";
+        let run = strip_noise(raw);
+        assert_eq!(run.recipes, vec!["go test ./..."]);
+        let result = MakeCompressor.compress(raw, Some("run"), &ctx());
+        assert_eq!(result, "[go test]\nok  \texample.com/calc\t0.01s");
+    }
+
+    #[test]
+    fn test_sniffs_silent_recipe_output() {
+        let raw = "\
+============================= test session starts ==============================
+collected 3 items
+
+tests/test_a.py ..F                                                      [100%]
+
+=========================== short test summary info ============================
+FAILED tests/test_a.py::test_c - assert 1 == 2
+========================= 1 failed, 2 passed in 0.03s ==========================
+";
+        let result = MakeCompressor.compress(raw, Some("run"), &ctx());
+        assert!(result.starts_with("[pytest] 1 failed, 2 passed in 0.03s\n"));
+        assert!(result.contains("[failures: 1]\n  tests/test_a.py::test_c\n"));
+    }
+
+    // ── targets ──
+
+    #[test]
+    fn test_makefile_targets() {
+        let text = "\
+VERSION := 1.0
+CC ?= gcc
+.PHONY: build test lint
+
+## Build the binary
+build: deps
+\tgo build ./...
+
+test: build ## Run the tests
+\tgo test ./...
+
+lint fmt:
+\tgolangci-lint run
+
+%.o: %.c
+\t$(CC) -c $<
+
+$(BIN): build
+";
+        let targets = makefile_targets(text);
+        let names: Vec<&str> = targets.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["build", "test", "lint", "fmt"]);
+        assert_eq!(targets[0].doc.as_deref(), Some("Build the binary"));
+        assert_eq!(targets[1].doc.as_deref(), Some("Run the tests"));
+        assert_eq!(targets[2].doc, None);
+    }
+
+    #[test]
+    fn test_just_listing() {
+        let raw = "\
+Available recipes:
+    build            # Build the project
+    test *args       # Run the tests
+    [lint]
+    clippy
+";
+        let result = MakeCompressor.compress(raw, Some("targets"), &ctx());
+        assert_eq!(
+            result,
+            "[targets: 3]\n  build — Build the project\n  test — Run the tests\n  clippy\n"
+        );
+    }
+
+    #[test]
+    fn test_task_listing() {
+        let raw = "\
+task: Available tasks for this project:
+* build:       Build the project
+* test:        Run the tests
+* clean:
+";
+        let v = MakeCompressor.summarize(raw, Some("targets"), &ctx());
+        assert_eq!(v["targets"][0]["name"], "build");
+        assert_eq!(v["targets"][1]["doc"], "Run the tests");
+        assert!(v["targets"][2].get("doc").is_none());
+    }
+
+    #[test]
+    fn test_targets_capped_and_empty() {
+        let targets: Vec<Target> = (0..35)
+            .map(|i| Target {
+                name: format!("t{i}"),
+                doc: None,
+            })
+            .collect();
+        let result = format_targets(&targets, &ctx());
+        assert!(result.starts_with("[targets: 35]"));
+        assert!(result.ends_with("  … +5 more\n"));
+        assert_eq!(format_targets(&[], &ctx()), "[targets] none found");
+    }
+}
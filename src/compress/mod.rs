@@ -1,15 +1,116 @@
+pub mod budget;
 pub mod cargo;
 pub mod docker;
 pub mod generic;
 pub mod git;
+pub mod go;
 pub mod grep;
+pub mod junit;
+pub mod make;
+pub mod node;
+pub mod normalize;
 pub mod python;
+pub mod rules;
+pub mod summary;
 pub mod truncate;
 
+use serde_json::Value;
+
+/// Limits applied by compressors, resolved from `Config` for a given tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressCtx {
+    /// Maximum lines before truncation.
+    pub max_lines: usize,
+    /// Maximum characters per line.
+    pub max_line_len: usize,
+    /// Maximum entries per listing (branches, tags, lints, containers, …).
+    pub max_items: usize,
+    /// Maximum entries in package listings (`pip list`, `uv pip freeze`, …).
+    pub max_packages: usize,
+    /// Maximum hits kept per group (grep matches per file, build warnings, …).
+    pub max_hits: usize,
+    /// Maximum lines kept per detail block (test failures, stash list, …).
+    pub max_block_lines: usize,
+    /// Target size of the whole output in estimated tokens (see `budget`).
+    pub token_budget: Option<usize>,
+    /// Which lines plain truncation keeps; `None` uses the compressor's default.
+    pub truncate: Option<truncate::Strategy>,
+    /// Exit code of the command whose output is compressed (0 until it ran).
+    pub exit_code: i32,
+}
+
+impl Default for CompressCtx {
+    fn default() -> Self {
+        Self {
+            max_lines: 150,
+            max_line_len: 300,
+            max_items: 30,
+            max_packages: 50,
+            max_hits: 10,
+            max_block_lines: 20,
+            token_budget: None,
+            truncate: None,
+            exit_code: 0,
+        }
+    }
+}
+
 /// Pure compression trait.
 /// Implementations transform raw command output into a compact form.
 /// No I/O — only string-in, string-out.
 pub trait Compressor {
     /// Compress raw output, optionally using the sub-command name for context.
-    fn compress(&self, raw: &str, sub: Option<&str>) -> String;
+    fn compress(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> String;
+
+    /// Typed summary for `--format json`.
+    /// Defaults to the compressed text split into lines.
+    fn summarize(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> Value {
+        summary::lines(&self.compress(raw, sub, ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Property: no compressor panics on non-ASCII output, whatever the limits.
+    #[test]
+    fn test_compressors_never_panic_on_non_ascii() {
+        let line = "src/naïve.rs:12:5: error: déjà vu — 日本語 🚀👩\u{200d}💻 e\u{301}";
+        let raw = format!("{line}\n").repeat(40);
+        let compressors: [(&dyn Compressor, &[&str]); 9] = [
+            (
+                &git::GitCompressor,
+                &["status", "diff", "log", "blame", "branch", "stash"],
+            ),
+            (&cargo::CargoCompressor, &["build", "test", "clippy", "fmt"]),
+            (
+                &python::PythonCompressor,
+                &["pytest", "ruff", "mypy", "list"],
+            ),
+            (&docker::DockerCompressor, &["ps", "images", "logs"]),
+            (&grep::GrepCompressor, &["grep"]),
+            (&go::GoCompressor, &["test", "build", "vet", "mod"]),
+            (&make::MakeCompressor, &["run", "targets"]),
+            (
+                &node::NodeCompressor,
+                &["install", "test", "run", "ls", "outdated"],
+            ),
+            (&generic::GenericCompressor, &["run"]),
+        ];
+        for max_line_len in [0, 1, 3, 7, 13, 40, 300] {
+            let ctx = CompressCtx {
+                max_line_len,
+                max_lines: 5,
+                ..CompressCtx::default()
+            };
+            for (compressor, subs) in &compressors {
+                for sub in *subs {
+                    compressor.compress(&raw, Some(sub), &ctx);
+                    compressor.summarize(&raw, Some(sub), &ctx);
+                }
+            }
+            budget::fit(&raw, max_line_len);
+        }
+    }
 }
@@ -0,0 +1,746 @@
+use super::summary::{self, TestFailure, TestReport};
+use super::truncate::{Strategy, truncate, truncate_by};
+use super::{CompressCtx, Compressor};
+use serde_json::{Value, json};
+
+/// Pure compressor for Node package managers (npm, pnpm, yarn, bun) and
+/// JavaScript test runners (jest, vitest, mocha).
+pub struct NodeCompressor;
+
+impl Compressor for NodeCompressor {
+    fn compress(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> String {
+        match sub.unwrap_or("") {
+            "install" => compress_install(raw, ctx),
+            "test" => compress_test(raw, ctx),
+            "run" => compress_run(raw, ctx),
+            "ls" => compress_ls(raw, ctx),
+            "outdated" => compress_outdated(raw, ctx),
+            _ => truncate(raw, ctx),
+        }
+    }
+
+    fn summarize(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> Value {
+        match sub.unwrap_or("") {
+            "test" => json!(parse_test_report(raw)),
+            _ => summary::lines(&self.compress(raw, sub, ctx)),
+        }
+    }
+}
+
+// ── install ──
+
+fn is_error_line(line: &str) -> bool {
+    line.starts_with("npm ERR!")
+        || line.starts_with("npm error")
+        || line.starts_with("error ")
+        || line.contains("ERR_PNPM")
+        || line.trim_start().starts_with("ERROR")
+}
+
+/// `pkg@1.2.3` from `npm WARN deprecated pkg@1.2.3: message` (npm, pnpm, yarn).
+fn deprecated_package(line: &str) -> Option<&str> {
+    let spec = match line.split_once("deprecated ") {
+        // npm / pnpm: `npm WARN deprecated pkg@1.0.0: reason`
+        Some((_, rest)) if !rest.starts_with(' ') && rest.split(':').next()?.contains('@') => {
+            rest.split(':').next()?
+        }
+        // yarn: `warning parent > pkg@1.0.0: pkg has been deprecated`
+        _ => {
+            let (spec, reason) = line.strip_prefix("warning ")?.split_once(": ")?;
+            if !reason.contains("deprecated") {
+                return None;
+            }
+            spec.rsplit(" > ").next()?
+        }
+    };
+    let spec = spec.trim();
+    (!spec.is_empty() && !spec.contains(' ')).then_some(spec)
+}
+
+/// Compress `npm install` / `pnpm install` / `yarn` / `bun install`:
+/// counts, vulnerabilities, deduplicated deprecations and errors.
+fn compress_install(raw: &str, ctx: &CompressCtx) -> String {
+    let mut totals: Vec<&str> = Vec::new();
+    let mut audit: Option<&str> = None;
+    let mut audit_fix: Option<&str> = None;
+    let mut deprecated: Vec<&str> = Vec::new();
+    let mut errors: Vec<&str> = Vec::new();
+
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if let Some(pkg) = deprecated_package(trimmed) {
+            if !deprecated.contains(&pkg) {
+                deprecated.push(pkg);
+            }
+        } else if is_error_line(trimmed) {
+            if !errors.contains(&trimmed) {
+                errors.push(trimmed);
+            }
+        } else if trimmed.contains("vulnerabilit") {
+            audit = Some(trimmed);
+        } else if trimmed.starts_with("npm audit fix") {
+            audit_fix = Some(trimmed);
+        } else if trimmed.starts_with("added ")
+            || trimmed.starts_with("removed ")
+            || trimmed.starts_with("changed ")
+            || trimmed.starts_with("up to date")
+            || trimmed.starts_with("Packages:")
+            || trimmed.starts_with("Done in")
+            || trimmed.starts_with("success ")
+            || (trimmed.contains("packages installed") && trimmed.contains('['))
+        {
+            totals.push(trimmed);
+        }
+    }
+
+    let mut out = String::new();
+    for t in &totals {
+        out.push_str(&format!("[install] {t}\n"));
+    }
+    if let Some(a) = audit {
+        match audit_fix {
+            Some(fix) => out.push_str(&format!("[audit] {a} — {fix}\n")),
+            None => out.push_str(&format!("[audit] {a}\n")),
+        }
+    }
+    if !deprecated.is_empty() {
+        out.push_str(&format!("[deprecated: {}]\n", deprecated.len()));
+        for d in deprecated.iter().take(ctx.max_items) {
+            out.push_str(&format!("  {d}\n"));
+        }
+        if deprecated.len() > ctx.max_items {
+            out.push_str(&format!("  … +{} more\n", deprecated.len() - ctx.max_items));
+        }
+    }
+    if !errors.is_empty() {
+        out.push_str(&format!("[errors: {}]\n", errors.len()));
+        for e in errors.iter().take(ctx.max_block_lines) {
+            out.push_str(&format!("  {e}\n"));
+        }
+        if errors.len() > ctx.max_block_lines {
+            out.push_str(&format!(
+                "  … +{} more\n",
+                errors.len() - ctx.max_block_lines
+            ));
+        }
+    }
+
+    if out.is_empty() {
+        return truncate(raw, ctx);
+    }
+    out
+}
+
+// ── run ──
+
+/// Compress `npm run <script>`: drop the `> pkg@1.0.0 script` banners and
+/// keep head + tail of the script's own output.
+fn compress_run(raw: &str, ctx: &CompressCtx) -> String {
+    let mut lines = raw.lines().peekable();
+    let mut kept: Vec<&str> = Vec::new();
+    while let Some(line) = lines.next() {
+        // npm echoes `> name@version script` then `> command`; pnpm and yarn `$ command`.
+        if line.starts_with("> ") || line.starts_with("$ ") {
+            while lines.next_if(|l| l.starts_with("> ")).is_some() {}
+            continue;
+        }
+        if !(kept.is_empty() && line.trim().is_empty()) {
+            kept.push(line);
+        }
+    }
+    let strategy = ctx.truncate.unwrap_or(Strategy::HeadTail);
+    truncate_by(&kept.join("\n"), ctx.max_lines, ctx.max_line_len, strategy)
+}
+
+// ── test (jest / vitest / mocha) ──
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TestLine {
+    /// Final counts: `Tests: 1 failed, 3 passed`, `3 passing (12ms)`, …
+    Summary,
+    /// Start of a failure block: jest `●`, vitest `FAIL file > test`, mocha `1) …`.
+    FailureStart,
+    /// Failing file header (jest `FAIL  src/a.test.js`).
+    FailedFile,
+    /// Passing tests, code frames, separators.
+    Noise,
+    Other,
+}
+
+fn classify_test_line(line: &str, after_failing: bool) -> TestLine {
+    let t = line.trim();
+    let summary_prefixes = [
+        "Test Suites:",
+        "Tests:",
+        "Snapshots:",
+        "Time:",
+        "Test Files ",
+        "Tests ",
+        "Duration ",
+    ];
+    if summary_prefixes.iter().any(|p| t.starts_with(p))
+        || ["passing", "failing", "pending"]
+            .iter()
+            .any(|w| summary::count_of(t, w) > 0 && t.split_whitespace().count() <= 3)
+    {
+        return TestLine::Summary;
+    }
+    if t.starts_with("● ") {
+        return TestLine::FailureStart;
+    }
+    if let Some(rest) = t.strip_prefix("FAIL ") {
+        return if rest.contains(" > ") {
+            TestLine::FailureStart
+        } else {
+            TestLine::FailedFile
+        };
+    }
+    // Mocha numbers its failures once the `N failing` line was printed.
+    if after_failing
+        && t.split_once(") ")
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    {
+        return TestLine::FailureStart;
+    }
+    let is_code_frame = t.starts_with('>') && t.contains('|')
+        || t.split_once('|')
+            .is_some_and(|(n, _)| n.trim().chars().all(|c| c.is_ascii_digit()));
+    if t.is_empty()
+        || t.starts_with("PASS ")
+        || t.starts_with('✓')
+        || t.starts_with('√')
+        || t.starts_with('×')
+        || t.starts_with('❯') && !t.contains(':')
+        || t.starts_with('⎯')
+        || t.starts_with("Start at")
+        || is_code_frame
+    {
+        return TestLine::Noise;
+    }
+    TestLine::Other
+}
+
+/// Compress jest / vitest / mocha output: failing files, failure blocks
+/// (capped) and the final counts.
+fn compress_test(raw: &str, ctx: &CompressCtx) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut summary_lines: Vec<&str> = Vec::new();
+    let mut in_failure = false;
+    let mut after_failing = false;
+    let mut block_lines = 0usize;
+
+    for line in raw.lines() {
+        let kind = classify_test_line(line, after_failing);
+        match kind {
+            TestLine::Summary => {
+                after_failing |= line.contains("failing");
+                in_failure = false;
+                summary_lines.push(line.trim());
+            }
+            TestLine::FailedFile => {
+                in_failure = false;
+                out.push(line.trim().to_string());
+            }
+            TestLine::FailureStart => {
+                in_failure = true;
+                block_lines = 0;
+                out.push(format!("  {}", line.trim()));
+            }
+            TestLine::Other if in_failure => {
+                block_lines += 1;
+                if block_lines <= ctx.max_block_lines {
+                    out.push(format!("    {}", line.trim()));
+                }
+            }
+            TestLine::Noise | TestLine::Other => {}
+        }
+    }
+
+    if out.is_empty() && summary_lines.is_empty() {
+        return truncate(raw, ctx);
+    }
+    out.extend(summary_lines.iter().map(|s| s.to_string()));
+    format!("[test]\n{}\n", out.join("\n"))
+}
+
+/// Counts from the runner's summary + one entry per failure block.
+fn parse_test_report(raw: &str) -> TestReport {
+    let mut report = TestReport::default();
+    let mut after_failing = false;
+    let mut current: Option<TestFailure> = None;
+
+    for line in raw.lines() {
+        let t = line.trim();
+        match classify_test_line(line, after_failing) {
+            TestLine::Summary => {
+                after_failing |= t.contains("failing");
+                // jest `Tests:` / vitest `Tests` carry test counts; skip suite/file lines.
+                if t.starts_with("Tests") || !t.starts_with("Test") {
+                    report.passed +=
+                        summary::count_of(t, "passed") + summary::count_of(t, "passing");
+                    report.failed +=
+                        summary::count_of(t, "failed") + summary::count_of(t, "failing");
+                    report.skipped += summary::count_of(t, "skipped")
+                        + summary::count_of(t, "pending")
+                        + summary::count_of(t, "todo");
+                }
+                report.failures.extend(current.take());
+            }
+            TestLine::FailureStart => {
+                report.failures.extend(current.take());
+                let name = t
+                    .trim_start_matches("● ")
+                    .trim_start_matches("FAIL ")
+                    .trim();
+                let name = match name.split_once(") ") {
+                    Some((n, rest)) if n.chars().all(|c| c.is_ascii_digit()) => rest,
+                    _ => name,
+                };
+                current = Some(TestFailure {
+                    name: name.trim_end_matches(':').to_string(),
+                    ..TestFailure::default()
+                });
+            }
+            TestLine::Other => {
+                // Prefer the assertion line over mocha's wrapped test title.
+                if let Some(f) = current.as_mut()
+                    && (f.message.is_none()
+                        || t.contains("Error")
+                            && !f.message.as_ref().is_some_and(|m| m.contains("Error")))
+                {
+                    f.message = Some(t.to_string());
+                }
+            }
+            TestLine::FailedFile | TestLine::Noise => {}
+        }
+    }
+    report.failures.extend(current);
+    report
+}
+
+// ── ls / outdated ──
+
+/// Compress `npm ls` / `pnpm ls` / `yarn list`: package count, top-level
+/// packages, and problems (missing, invalid, extraneous) in full.
+fn compress_ls(raw: &str, ctx: &CompressCtx) -> String {
+    let mut root: Option<&str> = None;
+    let mut packages: Vec<&str> = Vec::new();
+    let mut problems: Vec<&str> = Vec::new();
+
+    for line in raw.lines() {
+        let entry = line
+            .trim_start_matches(|c: char| "│├└─┬ `|+-\\".contains(c))
+            .trim();
+        if entry.is_empty()
+            || entry == "(empty)"
+            || entry.starts_with("Legend:")
+            || entry.ends_with("dependencies:")
+            || entry.starts_with("yarn list")
+            || entry.starts_with("Done in")
+        {
+            continue;
+        }
+        if root.is_none() && line == entry {
+            root = Some(entry);
+            continue;
+        }
+        let lower = entry.to_lowercase();
+        if ["unmet", "invalid", "extraneous", "missing", "err!"]
+            .iter()
+            .any(|k| lower.contains(k))
+        {
+            problems.push(entry);
+        } else {
+            packages.push(entry);
+        }
+    }
+
+    let mut out = match root {
+        Some(r) => format!("[ls] {r} — {} packages\n", packages.len()),
+        None => format!("[ls] {} packages\n", packages.len()),
+    };
+    for p in packages.iter().take(ctx.max_packages) {
+        out.push_str(&format!("  {p}\n"));
+    }
+    if packages.len() > ctx.max_packages {
+        out.push_str(&format!(
+            "  … +{} more\n",
+            packages.len() - ctx.max_packages
+        ));
+    }
+    if !problems.is_empty() {
+        out.push_str(&format!("[problems: {}]\n", problems.len()));
+        for p in &problems {
+            out.push_str(&format!("  {p}\n"));
+        }
+    }
+    out
+}
+
+/// Compress `npm outdated` & co: `name current → latest` per package.
+fn compress_outdated(raw: &str, ctx: &CompressCtx) -> String {
+    let mut rows: Vec<String> = Vec::new();
+    for line in raw.lines() {
+        // pnpm draws a box table; npm and yarn align columns with spaces.
+        let cells: Vec<&str> = if line.contains('│') {
+            line.split('│')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        let is_version = |c: &str| c.starts_with(|ch: char| ch.is_ascii_digit()) || c == "MISSING";
+        if cells.len() < 3 || !is_version(cells[1]) {
+            continue;
+        }
+        // npm / yarn: Package Current Wanted Latest …; pnpm: Package Current Latest.
+        let latest = if cells.len() >= 4 { cells[3] } else { cells[2] };
+        rows.push(format!("{} {} → {latest}", cells[0], cells[1]));
+    }
+
+    if rows.is_empty() {
+        // npm exits 1 when something is outdated, so only a clean exit with
+        // no rows means "up to date"; otherwise show why it failed.
+        if ctx.exit_code == 0 {
+            return "[outdated] all up to date".into();
+        }
+        return truncate(raw.trim(), ctx);
+    }
+    let mut out = format!("[outdated: {}]\n", rows.len());
+    for r in rows.iter().take(ctx.max_items) {
+        out.push_str(&format!("  {r}\n"));
+    }
+    if rows.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", rows.len() - ctx.max_items));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress::Compressor;
+
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
+    // ── install ──
+
+    #[test]
+    fn test_npm_install_summary() {
+        let raw = "\
+npm WARN deprecated inflight@1.0.6: This module is not supported, and leaks memory.
+npm WARN deprecated glob@7.2.3: Glob versions prior to v9 are no longer supported
+npm WARN deprecated inflight@1.0.6: This module is not supported, and leaks memory.
+
+added 245 packages, removed 3 packages, and audited 246 packages in 5s
+
+34 packages are looking for funding
+  run `npm fund` for details
+
+3 vulnerabilities (1 moderate, 2 high)
+
+To address all issues, run:
+  npm audit fix
+";
+        let result = compress_install(raw, &ctx());
+        assert!(result.contains("[install] added 245 packages, removed 3 packages"));
+        assert!(result.contains("[audit] 3 vulnerabilities (1 moderate, 2 high) — npm audit fix"));
+        assert!(result.contains("[deprecated: 2]"));
+        assert!(result.contains("  inflight@1.0.6\n"));
+        assert!(result.contains("  glob@7.2.3\n"));
+        assert!(!result.contains("funding"));
+    }
+
+    #[test]
+    fn test_pnpm_install() {
+        let raw = "\
+ WARN  deprecated request@2.88.2: request has been deprecated
+Packages: +120
+++++++++++++++++++++++++++++++++
+Progress: resolved 120, reused 120, downloaded 0, added 120, done
+
+dependencies:
++ react 18.2.0
+
+Done in 2.1s
+";
+        let result = compress_install(raw, &ctx());
+        assert!(result.contains("[install] Packages: +120"));
+        assert!(result.contains("[install] Done in 2.1s"));
+        assert!(result.contains("request@2.88.2"));
+        assert!(!result.contains("Progress"));
+    }
+
+    #[test]
+    fn test_yarn_deprecated_keeps_leaf_package() {
+        assert_eq!(
+            deprecated_package(
+                "warning jest > jsdom > request@2.88.2: request has been deprecated"
+            ),
+            Some("request@2.88.2")
+        );
+        assert_eq!(deprecated_package("nothing here"), None);
+    }
+
+    #[test]
+    fn test_install_errors_deduplicated() {
+        let raw = "\
+npm ERR! code ERESOLVE
+npm ERR! ERESOLVE unable to resolve dependency tree
+npm ERR! code ERESOLVE
+";
+        let result = compress_install(raw, &ctx());
+        assert!(result.contains("[errors: 2]"));
+    }
+
+    #[test]
+    fn test_install_up_to_date() {
+        let raw = "\nup to date, audited 50 packages in 400ms\n\nfound 0 vulnerabilities\n";
+        let result = compress_install(raw, &ctx());
+        assert!(result.contains("[install] up to date, audited 50 packages"));
+        assert!(result.contains("[audit] found 0 vulnerabilities"));
+    }
+
+    // ── run ──
+
+    #[test]
+    fn test_run_drops_npm_banner() {
+        let raw = "\n> my-app@1.0.0 build\n> tsc -p .\n\nsrc/index.ts(3,7): error TS2322: Type 'string' is not assignable\n";
+        let result = compress_run(raw, &ctx());
+        assert_eq!(
+            result,
+            "src/index.ts(3,7): error TS2322: Type 'string' is not assignable"
+        );
+    }
+
+    // ── test ──
+
+    #[test]
+    fn test_jest_failures_and_summary() {
+        let raw = "\
+ PASS  src/other.test.js
+ FAIL  src/sum.test.js
+  ● sum › adds numbers
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 4
+    Received: 5
+
+      3 | test('adds numbers', () => {
+    > 4 |   expect(sum(2, 2)).toBe(4);
+        |                     ^
+      5 | });
+
+      at Object.<anonymous> (src/sum.test.js:4:21)
+
+Test Suites: 1 failed, 1 passed, 2 total
+Tests:       1 failed, 3 passed, 4 total
+Snapshots:   0 total
+Time:        1.2 s
+";
+        let result = compress_test(raw, &ctx());
+        assert!(result.contains("FAIL  src/sum.test.js"));
+        assert!(result.contains("  ● sum › adds numbers"));
+        assert!(result.contains("    Expected: 4"));
+        assert!(result.contains("    Received: 5"));
+        assert!(result.contains("at Object.<anonymous> (src/sum.test.js:4:21)"));
+        assert!(!result.contains("PASS"));
+        assert!(!result.contains("| });"));
+        assert!(result.contains("Tests:       1 failed, 3 passed, 4 total"));
+
+        let report = parse_test_report(raw);
+        assert_eq!(report.passed, 3);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.failures[0].name, "sum › adds numbers");
+        assert_eq!(
+            report.failures[0].message.as_deref(),
+            Some("expect(received).toBe(expected) // Object.is equality")
+        );
+    }
+
+    #[test]
+    fn test_vitest_failures_and_summary() {
+        let raw = "\
+ ✓ src/a.test.ts (3 tests) 5ms
+ ❯ src/sum.test.ts (2 tests | 1 failed) 7ms
+   × sum > adds numbers 5ms
+     → expected 5 to be 4
+
+⎯⎯⎯⎯⎯⎯⎯ Failed Tests 1 ⎯⎯⎯⎯⎯⎯⎯
+
+ FAIL  src/sum.test.ts > sum > adds numbers
+AssertionError: expected 5 to be 4 // Object.is equality
+
+- Expected
++ Received
+
+- 4
++ 5
+
+ ❯ src/sum.test.ts:4:21
+      2| test('adds numbers', () => {
+      4|   expect(sum(2, 2)).toBe(4)
+
+ Test Files  1 failed | 1 passed (2)
+      Tests  1 failed | 4 passed (5)
+   Start at  10:00:00
+   Duration  300ms
+";
+        let result = compress_test(raw, &ctx());
+        assert!(result.contains("  FAIL  src/sum.test.ts > sum > adds numbers"));
+        assert!(result.contains("    AssertionError: expected 5 to be 4"));
+        assert!(result.contains("    + 5"));
+        assert!(result.contains("    ❯ src/sum.test.ts:4:21"));
+        assert!(!result.contains("✓"));
+        assert!(!result.contains("Start at"));
+        assert!(result.contains("Tests  1 failed | 4 passed (5)"));
+
+        let report = parse_test_report(raw);
+        assert_eq!((report.passed, report.failed), (4, 1));
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(
+            report.failures[0].name,
+            "src/sum.test.ts > sum > adds numbers"
+        );
+    }
+
+    #[test]
+    fn test_mocha_failures_and_summary() {
+        let raw = "\
+  Array
+    #indexOf()
+      ✓ should find the value
+      1) should return -1
+
+  3 passing (12ms)
+  1 pending
+  1 failing
+
+  1) Array
+       #indexOf()
+         should return -1:
+     AssertionError [ERR_ASSERTION]: 1 == -1
+      at Context.<anonymous> (test/test.js:5:14)
+";
+        let result = compress_test(raw, &ctx());
+        assert!(result.contains("  1) Array"));
+        assert!(result.contains("AssertionError [ERR_ASSERTION]: 1 == -1"));
+        assert!(result.contains("3 passing (12ms)"));
+
+        let report = parse_test_report(raw);
+        assert_eq!((report.passed, report.failed, report.skipped), (3, 1, 1));
+        assert_eq!(report.failures[0].name, "Array");
+        assert_eq!(
+            report.failures[0].message.as_deref(),
+            Some("AssertionError [ERR_ASSERTION]: 1 == -1")
+        );
+    }
+
+    #[test]
+    fn test_failure_block_capped() {
+        let mut raw = String::from("  ● big failure\n");
+        for i in 0..40 {
+            raw.push_str(&format!("    detail {i}\n"));
+        }
+        let ctx = CompressCtx {
+            max_block_lines: 3,
+            ..ctx()
+        };
+        let result = compress_test(&raw, &ctx);
+        assert!(result.contains("detail 2"));
+        assert!(!result.contains("detail 3"));
+    }
+
+    // ── ls / outdated ──
+
+    #[test]
+    fn test_npm_ls() {
+        let raw = "\
+my-app@1.0.0 /home/me/my-app
+├── lodash@4.17.21
+├── react@18.2.0
+├── UNMET DEPENDENCY typescript@^5.0.0
+└── vite@5.0.0 extraneous
+";
+        let result = compress_ls(raw, &ctx());
+        assert!(result.contains("[ls] my-app@1.0.0 /home/me/my-app — 2 packages"));
+        assert!(result.contains("  react@18.2.0"));
+        assert!(result.contains("[problems: 2]"));
+        assert!(result.contains("UNMET DEPENDENCY typescript@^5.0.0"));
+    }
+
+    #[test]
+    fn test_npm_ls_no_dependencies() {
+        let raw = "t@1.0.0 /tmp/t\n└── (empty)\n";
+        assert_eq!(
+            compress_ls(raw, &ctx()),
+            "[ls] t@1.0.0 /tmp/t — 0 packages\n"
+        );
+    }
+
+    #[test]
+    fn test_npm_outdated() {
+        let raw = "\
+Package     Current  Wanted  Latest  Location                 Depended by
+react        17.0.2  17.0.2  18.2.0  node_modules/react       my-app
+typescript    5.0.4   5.3.3   5.3.3  node_modules/typescript  my-app
+";
+        let result = compress_outdated(raw, &ctx());
+        assert!(result.contains("[outdated: 2]"));
+        assert!(result.contains("  react 17.0.2 → 18.2.0"));
+        assert!(result.contains("  typescript 5.0.4 → 5.3.3"));
+    }
+
+    #[test]
+    fn test_pnpm_outdated_box_table() {
+        let raw = "\
+┌──────────────────┬─────────┬────────┐
+│ Package          │ Current │ Latest │
+├──────────────────┼─────────┼────────┤
+│ typescript (dev) │ 5.0.4   │ 5.3.3  │
+└──────────────────┴─────────┴────────┘
+";
+        let result = compress_outdated(raw, &ctx());
+        assert!(result.contains("  typescript (dev) 5.0.4 → 5.3.3"));
+    }
+
+    #[test]
+    fn test_outdated_empty() {
+        assert_eq!(compress_outdated("", &ctx()), "[outdated] all up to date");
+    }
+
+    #[test]
+    fn test_failed_outdated_is_not_up_to_date() {
+        let raw = "\
+npm error code ENOTFOUND
+npm error syscall getaddrinfo
+npm error errno ENOTFOUND
+npm error network request to https://registry.npmjs.org/react failed, reason: getaddrinfo ENOTFOUND registry.npmjs.org
+npm error A complete log of this run can be found in: /home/dev/.npm/_logs/2026-10-16T09_12_44_103Z-debug-0.log
+";
+        let failed = CompressCtx {
+            exit_code: 1,
+            ..ctx()
+        };
+        let result = compress_outdated(raw, &failed);
+        assert!(!result.contains("all up to date"));
+        assert!(result.contains("npm error code ENOTFOUND"));
+
+        // npm also exits 1 when it did find outdated packages.
+        let raw = "Package  Current  Wanted  Latest  Location\nreact    18.2.0   18.3.1  19.0.0  node_modules/react\n";
+        assert!(compress_outdated(raw, &failed).contains("  react 18.2.0 → 19.0.0"));
+    }
+
+    #[test]
+    fn test_trait_dispatch() {
+        let c = NodeCompressor;
+        let result = c.compress("Tests: 2 passed, 2 total\n", Some("test"), &ctx());
+        assert!(result.starts_with("[test]"));
+        let v = c.summarize("Tests: 2 passed, 2 total\n", Some("test"), &ctx());
+        assert_eq!(v["passed"], 2);
+    }
+}
@@ -0,0 +1,153 @@
+//! Terminal noise removal, applied to raw output before any compressor runs.
+//!
+//! Tools that believe they talk to a terminal (or run with
+//! `CARGO_TERM_COLOR=always`, `FORCE_COLOR`, …) emit color codes and progress
+//! bars redrawn with `\r`. Both cost tokens and break prefix checks such as
+//! `line.starts_with("error")`, so they are removed here once for everyone.
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+/// Single-byte CSI, the 8-bit form of `ESC [`.
+const CSI: char = '\u{9b}';
+
+/// Strip ANSI escape sequences and control characters, and collapse
+/// `\r`-redrawn lines to their final state.
+pub fn normalize(raw: &str) -> String {
+    if !raw.contains(|c: char| c.is_control() && c != '\n' && c != '\t') {
+        return raw.to_string();
+    }
+    let stripped = strip_ansi(raw);
+    let lines: Vec<&str> = stripped.split('\n').map(collapse_cr).collect();
+    lines.join("\n")
+}
+
+/// Remove CSI (`ESC [ … final`), OSC (`ESC ] … BEL|ST`) and other escape
+/// sequences, plus stray control characters. `\n`, `\t` and `\r` are kept;
+/// backspace erases the previous character.
+pub fn strip_ansi(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ESC => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                Some(']') => skip_osc(&mut chars),
+                // `ESC ( B` and friends: intermediates, then one final byte.
+                Some(c) if ('\x20'..='\x2f').contains(&c) => {
+                    while chars.next_if(|c| ('\x20'..='\x2f').contains(c)).is_some() {}
+                    chars.next();
+                }
+                // Two-character sequences (`ESC 7`, `ESC M`, …) or a lone ESC.
+                _ => {}
+            },
+            CSI => skip_csi(&mut chars),
+            '\x08' => {
+                out.pop();
+            }
+            '\n' | '\t' | '\r' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parameters (`0x30..=0x3f`), intermediates (`0x20..=0x2f`), then a final byte.
+fn skip_csi(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while chars.next_if(|c| ('\x20'..='\x3f').contains(c)).is_some() {}
+    chars.next();
+}
+
+/// Everything up to BEL or the string terminator `ESC \`.
+fn skip_osc(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            BEL => return,
+            ESC => {
+                chars.next_if_eq(&'\\');
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The text a terminal would be left showing after `\r` redraws: the last
+/// non-empty segment. A trailing `\r` (from `\r\n` endings) is ignored.
+fn collapse_cr(line: &str) -> &str {
+    if !line.contains('\r') {
+        return line;
+    }
+    line.split('\r')
+        .rfind(|seg| !seg.trim().is_empty())
+        .unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text_unchanged() {
+        let raw = "error[E0308]: mismatched types\n  --> src/main.rs:4:5\n\tindented";
+        assert_eq!(normalize(raw), raw);
+    }
+
+    #[test]
+    fn test_strips_sgr_colors() {
+        let raw = "\x1b[1m\x1b[31merror\x1b[0m\x1b[1m: mismatched types\x1b[0m";
+        assert_eq!(normalize(raw), "error: mismatched types");
+    }
+
+    #[test]
+    fn test_strips_cursor_and_erase_sequences() {
+        assert_eq!(normalize("\x1b[2K\x1b[1Gdone\x1b[?25h"), "done");
+    }
+
+    #[test]
+    fn test_strips_osc_hyperlinks() {
+        let raw = "see \x1b]8;;https://example.com\x07docs\x1b]8;;\x1b\\ here";
+        assert_eq!(normalize(raw), "see docs here");
+    }
+
+    #[test]
+    fn test_strips_charset_and_8bit_csi() {
+        assert_eq!(normalize("\x1b(Bplain\u{9b}32mgreen"), "plaingreen");
+    }
+
+    #[test]
+    fn test_collapses_carriage_return_progress() {
+        let raw = "Downloading  10%\rDownloading  50%\rDownloading 100%\nDone";
+        assert_eq!(normalize(raw), "Downloading 100%\nDone");
+    }
+
+    #[test]
+    fn test_cargo_progress_bar_erased_before_next_line() {
+        let raw = "    Building [=====>   ] 3/10: foo\r\x1b[K   Compiling bar v0.1.0\n";
+        assert_eq!(normalize(raw), "   Compiling bar v0.1.0\n");
+    }
+
+    #[test]
+    fn test_crlf_line_endings_kept() {
+        assert_eq!(normalize("one\r\ntwo\r\n"), "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_backspace_and_bell() {
+        assert_eq!(normalize("abx\x08c\x07"), "abc");
+    }
+
+    #[test]
+    fn test_unterminated_sequences_do_not_panic() {
+        assert_eq!(normalize("tail\x1b"), "tail");
+        assert_eq!(normalize("tail\x1b["), "tail");
+        assert_eq!(normalize("tail\x1b]8;;unterminated"), "tail");
+    }
+
+    #[test]
+    fn test_non_ascii_preserved() {
+        let raw = "\x1b[32m✔ réussi — 日本語\x1b[0m";
+        assert_eq!(normalize(raw), "✔ réussi — 日本語");
+    }
+}
@@ -1,86 +1,313 @@
-use super::Compressor;
+use super::junit;
+use super::summary::{
+    self, Diagnostic, Location, SLOWEST_TESTS, TestFailure, TestReport, parse_location,
+};
 use super::truncate::{dedup_lines, truncate};
+use super::{CompressCtx, Compressor};
+use serde::Serialize;
+use serde_json::{Value, json};
 
 /// Pure compressor for Python ecosystem output (pytest, ruff, pip, mypy, uv).
 pub struct PythonCompressor;
 
 impl Compressor for PythonCompressor {
-    fn compress(&self, raw: &str, sub: Option<&str>) -> String {
+    fn compress(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> String {
         match sub.unwrap_or("") {
-            "pytest" | "test" => compress_pytest(raw),
-            "ruff" => compress_ruff(raw),
-            "mypy" => compress_mypy(raw),
-            "pip" | "install" | "uninstall" => compress_pip_install(raw),
-            "list" | "freeze" => compress_pip_list(raw),
-            "outdated" => compress_pip_outdated(raw),
-            "sync" => compress_uv_sync(raw),
-            "run" => truncate(raw),
-            "lock" => compress_uv_lock(raw),
-            "add" | "remove" => compress_uv_dep(sub.unwrap_or(""), raw),
-            _ => truncate(raw),
+            "pytest" | "test" => compress_pytest(raw, ctx),
+            "ruff" => compress_ruff(raw, ctx),
+            "mypy" => compress_mypy(raw, ctx),
+            "pip" | "install" | "uninstall" => compress_pip_install(raw, ctx),
+            "list" | "freeze" => compress_pip_list(raw, ctx),
+            "outdated" => compress_pip_outdated(raw, ctx),
+            "sync" => compress_uv_sync(raw, ctx),
+            "run" => truncate(raw, ctx),
+            "lock" => compress_uv_lock(raw, ctx),
+            "add" | "remove" => compress_uv_dep(sub.unwrap_or(""), raw, ctx),
+            _ => truncate(raw, ctx),
+        }
+    }
+
+    fn summarize(&self, raw: &str, sub: Option<&str>, ctx: &CompressCtx) -> Value {
+        match sub.unwrap_or("") {
+            "pytest" | "test" => json!(parse_pytest_report(raw)),
+            "ruff" => json!(parse_ruff(raw)),
+            "mypy" => json!(parse_mypy(raw)),
+            _ => summary::lines(&self.compress(raw, sub, ctx)),
         }
     }
 }
 
+/// Lint / type-check diagnostics + the tool's own summary line.
+#[derive(Debug, Default, Serialize)]
+struct LintSummary<'a> {
+    diagnostics: Vec<Diagnostic>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+}
+
 // ── pytest ──
 
-/// Compress pytest output: keep only failures + summary line.
-fn compress_pytest(raw: &str) -> String {
-    let lines: Vec<&str> = raw.lines().collect();
-    let mut out = Vec::new();
-    let mut in_failure = false;
-    let mut failure_lines = 0usize;
-
-    for line in &lines {
-        // Summary lines (always keep)
-        if line.starts_with("=")
-            && (line.contains("passed") || line.contains("failed") || line.contains("error"))
-        {
-            out.push(*line);
-            continue;
+/// Compress pytest output: the summary counts, then each failure reduced to
+/// its test id, innermost project frame and `E` lines.
+fn compress_pytest(raw: &str, ctx: &CompressCtx) -> String {
+    let (text, xml) = junit::split(raw);
+    let junit = xml.and_then(junit::parse);
+    let summary = text.lines().rev().find_map(summary_line);
+    let failures = parse_failures(text);
+
+    let counts = match (&junit, summary) {
+        (Some(report), _) => counts_line(report),
+        (None, Some(summary)) => summary.to_string(),
+        (None, None) if failures.is_empty() => return truncate(text, ctx),
+        (None, None) => String::new(),
+    };
+
+    let mut out = format!("[pytest] {counts}").trim_end().to_string();
+    out.push('\n');
+    if !failures.is_empty() {
+        out.push_str(&format!("[failures: {}]\n", failures.len()));
+        for f in failures.iter().take(ctx.max_items) {
+            match &f.frame {
+                Some(Location {
+                    file,
+                    line: Some(line),
+                    ..
+                }) => out.push_str(&format!("  {} ({file}:{line})\n", f.id)),
+                _ => out.push_str(&format!("  {}\n", f.id)),
+            }
+            let detail: Vec<String> = if f.errors.is_empty() {
+                f.message.iter().map(|m| format!("    {m}")).collect()
+            } else {
+                f.errors.iter().map(|e| format!("    E {e}")).collect()
+            };
+            for line in detail.iter().take(ctx.max_block_lines) {
+                out.push_str(line);
+                out.push('\n');
+            }
+            if detail.len() > ctx.max_block_lines {
+                out.push_str(&format!(
+                    "    … +{} more lines\n",
+                    detail.len() - ctx.max_block_lines
+                ));
+            }
         }
-        // FAILURES header
-        if line.contains("FAILURES") && line.starts_with("=") {
-            in_failure = true;
-            out.push(*line);
-            continue;
+        if failures.len() > ctx.max_items {
+            out.push_str(&format!("  … +{} more\n", failures.len() - ctx.max_items));
         }
-        // Individual failure header
-        if line.starts_with("___") && line.ends_with("___") {
-            in_failure = true;
-            failure_lines = 0;
-            out.push(*line);
-            continue;
+    }
+    out
+}
+
+/// pytest's final `1 failed, 2 passed in 0.05s` line, without the `=` rule
+/// it has outside `-q` mode.
+fn summary_line(line: &str) -> Option<&str> {
+    let trimmed = line.trim_matches(|c| c == '=' || c == ' ');
+    let counted = ["passed", "failed", "error", "skipped", "no tests ran"]
+        .iter()
+        .any(|w| trimmed.contains(w));
+    (counted && trimmed.contains(" in ") && !line.starts_with(' ')).then_some(trimmed)
+}
+
+/// `1 failed, 2 passed, 1 xfailed` from a JUnit report's exact counts.
+fn counts_line(report: &TestReport) -> String {
+    let counts = [
+        (report.failed, "failed"),
+        (report.passed, "passed"),
+        (report.skipped, "skipped"),
+        (report.xfailed, "xfailed"),
+        (
+            report.errors,
+            if report.errors == 1 {
+                "error"
+            } else {
+                "errors"
+            },
+        ),
+    ];
+    let parts: Vec<String> = counts
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, word)| format!("{n} {word}"))
+        .collect();
+    if parts.is_empty() {
+        "no tests ran".into()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// A failing test from pytest's `FAILURES` / `ERRORS` sections or, without
+/// tracebacks (`--tb=no`), from the short test summary.
+#[derive(Debug, PartialEq)]
+struct PytestFailure<'a> {
+    /// Node id, e.g. `tests/test_math.py::TestDiv::test_div[0-1]`.
+    id: String,
+    message: Option<String>,
+    /// The `E` lines of the traceback, without the gutter.
+    errors: Vec<&'a str>,
+    /// Innermost traceback frame inside the project.
+    frame: Option<Location>,
+}
+
+impl From<PytestFailure<'_>> for TestFailure {
+    fn from(f: PytestFailure<'_>) -> Self {
+        TestFailure {
+            name: f.id,
+            message: f.message,
+            file: f.frame.as_ref().map(|l| l.file.clone()),
+            line: f.frame.and_then(|l| l.line),
         }
-        // Capture failure details (cap per failure)
-        if in_failure {
-            failure_lines += 1;
-            if failure_lines <= 20 {
-                out.push(*line);
-            }
-            // End of failure block
-            if line.starts_with("=") || (line.is_empty() && failure_lines > 2) {
-                in_failure = false;
-            }
+    }
+}
+
+/// `FAILED id - message` / `ERROR id` short-summary entries.
+fn short_summary(text: &str) -> Vec<(&str, Option<&str>)> {
+    text.lines()
+        .filter_map(|l| {
+            l.strip_prefix("FAILED ")
+                .or_else(|| l.strip_prefix("ERROR "))
+        })
+        .map(|entry| match entry.split_once(" - ") {
+            Some((id, message)) => (id.trim(), Some(message.trim())),
+            None => (entry.trim(), None),
+        })
+        .collect()
+}
+
+/// Parse each `____ test_name ____` traceback block, naming it by the node id
+/// from the short summary when pytest printed one.
+fn parse_failures(text: &str) -> Vec<PytestFailure<'_>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let short = short_summary(text);
+    let mut failures: Vec<PytestFailure> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let Some(header) = block_header(lines[i]) else {
+            i += 1;
             continue;
+        };
+        let name = [
+            "ERROR collecting ",
+            "ERROR at setup of ",
+            "ERROR at teardown of ",
+        ]
+        .iter()
+        .find_map(|p| header.strip_prefix(p))
+        .unwrap_or(header);
+        let len = lines[i + 1..]
+            .iter()
+            .take_while(|l| block_header(l).is_none() && !l.starts_with('='))
+            .count();
+        let block = &lines[i + 1..i + 1 + len];
+        i += 1 + len;
+
+        let errors: Vec<&str> = block.iter().filter_map(|l| error_line(l)).collect();
+        let frame = block
+            .iter()
+            .rev()
+            .filter_map(|l| parse_location(l).map(|(loc, _)| loc))
+            .find(|loc| loc.file.ends_with(".py") && is_project_file(&loc.file));
+        let entry = short.iter().find(|(id, _)| node_id_matches(id, name));
+        let message = entry
+            .and_then(|(_, m)| m.map(str::to_string))
+            .or_else(|| errors.first().map(|e| e.trim().to_string()));
+        failures.push(PytestFailure {
+            id: entry.map_or(name, |(id, _)| id).to_string(),
+            message,
+            errors,
+            frame,
+        });
+    }
+
+    // `--tb=no` / `--tb=line`: the short summary is all there is.
+    for (id, message) in short {
+        if !failures.iter().any(|f| f.id == id) {
+            failures.push(PytestFailure {
+                id: id.to_string(),
+                message: message.map(str::to_string),
+                errors: Vec::new(),
+                frame: None,
+            });
         }
-        // Short test summary
-        if line.starts_with("FAILED ") || line.starts_with("ERROR ") {
-            out.push(*line);
-        }
     }
+    failures
+}
+
+/// Name in a `____ TestDiv.test_div[0-1] ____` block header.
+fn block_header(line: &str) -> Option<&str> {
+    if !(line.starts_with("___") && line.ends_with("___")) {
+        return None;
+    }
+    let name = line.trim_matches(|c| c == '_' || c == ' ');
+    (!name.is_empty()).then_some(name)
+}
 
-    if out.is_empty() {
-        return truncate(raw);
+/// `E       assert 1 == 2` → `assert 1 == 2`, keeping deeper indentation
+/// (diff lines of an assertion rewrite).
+fn error_line(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix('E')?;
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
     }
+    Some(rest.strip_prefix("       ").unwrap_or(rest.trim_start()))
+}
+
+/// Whether a traceback frame is project code rather than an installed
+/// package, the standard library or pytest's own machinery.
+fn is_project_file(file: &str) -> bool {
+    const EXTERNAL: &[&str] = &[
+        "site-packages",
+        "dist-packages",
+        "/lib/python",
+        "/_pytest/",
+        "/pluggy/",
+    ];
+    !file.starts_with('<') && !EXTERNAL.iter().any(|e| file.contains(e))
+}
+
+/// Whether node id `tests/test_x.py::TestDiv::test_div[0]` is the test a
+/// block header names (`TestDiv.test_div[0]`, or the file for collection errors).
+fn node_id_matches(id: &str, name: &str) -> bool {
+    id == name || id.replace("::", ".").ends_with(&format!(".{name}"))
+}
 
-    format!("[pytest]\n{}", out.join("\n"))
+/// Exact counts from the `--junitxml` report when there is one, else from
+/// pytest's summary line; failures from the tracebacks, falling back to the
+/// report's.
+fn parse_pytest_report(raw: &str) -> TestReport {
+    let (text, xml) = junit::split(raw);
+    let mut report = match xml.and_then(junit::parse) {
+        Some(mut report) => {
+            // pytest writes a single `pytest` suite; it adds nothing.
+            report.suites.clear();
+            report.keep_slowest(SLOWEST_TESTS);
+            report
+        }
+        None => {
+            let mut report = TestReport::default();
+            if let Some(line) = text.lines().rev().find_map(summary_line) {
+                report.passed = summary::count_of(line, "passed");
+                report.failed = summary::count_of(line, "failed");
+                report.skipped = summary::count_of(line, "skipped");
+                report.xfailed = summary::count_of(line, "xfailed");
+                report.errors =
+                    summary::count_of(line, "error") + summary::count_of(line, "errors");
+            }
+            report
+        }
+    };
+    let failures = parse_failures(text);
+    if !failures.is_empty() {
+        report.failures = failures.into_iter().map(TestFailure::from).collect();
+    }
+    report
 }
 
 // ── ruff ──
 
 /// Compress ruff check / ruff format output: group by rule.
-fn compress_ruff(raw: &str) -> String {
+fn compress_ruff(raw: &str, ctx: &CompressCtx) -> String {
     if raw.trim().is_empty() {
         return "[ruff] clean".into();
     }
@@ -108,20 +335,49 @@ fn compress_ruff(raw: &str) -> String {
         out.push_str(&format!("[ruff] {} issues\n", diagnostics.len()));
     }
 
-    for d in diagnostics.iter().take(30) {
+    for d in diagnostics.iter().take(ctx.max_items) {
         out.push_str(&format!("  {d}\n"));
     }
-    if diagnostics.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", diagnostics.len() - 30));
+    if diagnostics.len() > ctx.max_items {
+        out.push_str(&format!(
+            "  … +{} more\n",
+            diagnostics.len() - ctx.max_items
+        ));
     }
 
     out
 }
 
+/// Parse `file:line:col: CODE message` ruff diagnostics.
+fn parse_ruff(raw: &str) -> LintSummary<'_> {
+    let mut out = LintSummary::default();
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("Found ") || trimmed.starts_with("All checks") {
+            out.summary = Some(trimmed);
+            continue;
+        }
+        let Some((loc, rest)) = parse_location(trimmed) else {
+            continue;
+        };
+        let (code, message) = rest.split_once(' ').unwrap_or((rest, ""));
+        out.diagnostics.push(
+            Diagnostic {
+                level: "error".into(),
+                code: Some(code.to_string()),
+                message: message.trim().to_string(),
+                ..Diagnostic::default()
+            }
+            .at(loc),
+        );
+    }
+    out
+}
+
 // ── mypy ──
 
 /// Compress mypy output: group errors, keep summary.
-fn compress_mypy(raw: &str) -> String {
+fn compress_mypy(raw: &str, ctx: &CompressCtx) -> String {
     let mut errors: Vec<&str> = Vec::new();
     let mut summary: Option<&str> = None;
 
@@ -138,15 +394,15 @@ fn compress_mypy(raw: &str) -> String {
         if let Some(s) = summary {
             return format!("[mypy] {s}");
         }
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
 
     let mut out = format!("[mypy] {} errors\n", errors.len());
-    for e in errors.iter().take(30) {
+    for e in errors.iter().take(ctx.max_items) {
         out.push_str(&format!("  {e}\n"));
     }
-    if errors.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", errors.len() - 30));
+    if errors.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", errors.len() - ctx.max_items));
     }
     if let Some(s) = summary {
         out.push_str(&format!("{s}\n"));
@@ -154,10 +410,45 @@ fn compress_mypy(raw: &str) -> String {
     out
 }
 
+/// Parse `file:line: error: message  [code]` mypy diagnostics.
+fn parse_mypy(raw: &str) -> LintSummary<'_> {
+    let mut out = LintSummary::default();
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("Found ") || trimmed.starts_with("Success") {
+            out.summary = Some(trimmed);
+            continue;
+        }
+        let Some((loc, rest)) = parse_location(trimmed) else {
+            continue;
+        };
+        let Some((level, message)) = rest.split_once(": ") else {
+            continue;
+        };
+        let (message, code) = match message.trim_end().strip_suffix(']') {
+            Some(m) => match m.rsplit_once("  [") {
+                Some((msg, code)) => (msg, Some(code.to_string())),
+                None => (message, None),
+            },
+            None => (message, None),
+        };
+        out.diagnostics.push(
+            Diagnostic {
+                level: level.to_string(),
+                code,
+                message: message.trim().to_string(),
+                ..Diagnostic::default()
+            }
+            .at(loc),
+        );
+    }
+    out
+}
+
 // ── pip / uv pip ──
 
 /// Compress pip install / uv pip install output.
-fn compress_pip_install(raw: &str) -> String {
+fn compress_pip_install(raw: &str, ctx: &CompressCtx) -> String {
     let mut installed = Vec::new();
     let mut already = 0usize;
     let mut summary_line: Option<&str> = None;
@@ -178,7 +469,7 @@ fn compress_pip_install(raw: &str) -> String {
         out.push_str(&format!("[pip] {s}\n"));
     } else if !installed.is_empty() {
         out.push_str(&format!("[pip] installed {}\n", installed.len()));
-        for pkg in installed.iter().take(20) {
+        for pkg in installed.iter().take(ctx.max_block_lines) {
             out.push_str(&format!("  {pkg}\n"));
         }
     }
@@ -187,13 +478,13 @@ fn compress_pip_install(raw: &str) -> String {
     }
 
     if out.is_empty() {
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
     out
 }
 
 /// Compress pip list / uv pip list / pip freeze.
-fn compress_pip_list(raw: &str) -> String {
+fn compress_pip_list(raw: &str, ctx: &CompressCtx) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     if lines.is_empty() {
         return "[pip list] empty".into();
@@ -207,17 +498,20 @@ fn compress_pip_list(raw: &str) -> String {
         .collect();
 
     let mut out = format!("[packages: {}]\n", packages.len());
-    for p in packages.iter().take(50) {
+    for p in packages.iter().take(ctx.max_packages) {
         out.push_str(&format!("  {p}\n"));
     }
-    if packages.len() > 50 {
-        out.push_str(&format!("  … +{} more\n", packages.len() - 50));
+    if packages.len() > ctx.max_packages {
+        out.push_str(&format!(
+            "  … +{} more\n",
+            packages.len() - ctx.max_packages
+        ));
     }
     out
 }
 
 /// Compress pip list --outdated output.
-fn compress_pip_outdated(raw: &str) -> String {
+fn compress_pip_outdated(raw: &str, ctx: &CompressCtx) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     let packages: Vec<&str> = lines
         .iter()
@@ -230,11 +524,11 @@ fn compress_pip_outdated(raw: &str) -> String {
     }
 
     let mut out = format!("[outdated: {}]\n", packages.len());
-    for p in packages.iter().take(30) {
+    for p in packages.iter().take(ctx.max_items) {
         out.push_str(&format!("  {p}\n"));
     }
-    if packages.len() > 30 {
-        out.push_str(&format!("  … +{} more\n", packages.len() - 30));
+    if packages.len() > ctx.max_items {
+        out.push_str(&format!("  … +{} more\n", packages.len() - ctx.max_items));
     }
     out
 }
@@ -242,7 +536,7 @@ fn compress_pip_outdated(raw: &str) -> String {
 // ── uv specific ──
 
 /// Compress `uv sync` output.
-fn compress_uv_sync(raw: &str) -> String {
+fn compress_uv_sync(raw: &str, ctx: &CompressCtx) -> String {
     let mut installed = 0usize;
     let mut uninstalled = 0usize;
     let mut resolved: Option<&str> = None;
@@ -265,24 +559,24 @@ fn compress_uv_sync(raw: &str) -> String {
     out.push_str(&format!("+{installed} -{uninstalled}\n"));
 
     if raw.lines().count() > 10 {
-        out.push_str(&dedup_lines(raw));
+        out.push_str(&dedup_lines(raw, ctx));
     }
     out
 }
 
 /// Compress `uv lock` output.
-fn compress_uv_lock(raw: &str) -> String {
+fn compress_uv_lock(raw: &str, ctx: &CompressCtx) -> String {
     let resolved = raw.lines().find(|l| l.trim().starts_with("Resolved"));
 
     if let Some(r) = resolved {
         format!("[uv lock] {}", r.trim())
     } else {
-        truncate(raw)
+        truncate(raw, ctx)
     }
 }
 
 /// Compress `uv add` / `uv remove` output.
-fn compress_uv_dep(sub: &str, raw: &str) -> String {
+fn compress_uv_dep(sub: &str, raw: &str, ctx: &CompressCtx) -> String {
     let mut changes = Vec::new();
     let mut resolved: Option<&str> = None;
 
@@ -304,15 +598,18 @@ fn compress_uv_dep(sub: &str, raw: &str) -> String {
     } else {
         out.push('\n');
     }
-    for c in changes.iter().take(20) {
+    for c in changes.iter().take(ctx.max_block_lines) {
         out.push_str(&format!("  {c}\n"));
     }
-    if changes.len() > 20 {
-        out.push_str(&format!("  … +{} more\n", changes.len() - 20));
+    if changes.len() > ctx.max_block_lines {
+        out.push_str(&format!(
+            "  … +{} more\n",
+            changes.len() - ctx.max_block_lines
+        ));
     }
 
     if out.trim().is_empty() {
-        return truncate(raw);
+        return truncate(raw, ctx);
     }
     out
 }
@@ -322,6 +619,10 @@ mod tests {
     use super::*;
     use crate::compress::Compressor;
 
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
     // ── pytest ──
 
     #[test]
@@ -334,7 +635,7 @@ test_math.py ..........
 
 ============================== 10 passed in 0.03s ==============================
 ";
-        let result = compress_pytest(raw);
+        let result = compress_pytest(raw, &ctx());
         assert!(result.contains("[pytest]"));
         assert!(result.contains("10 passed"));
     }
@@ -359,18 +660,180 @@ test_math.py:10: ZeroDivisionError
 FAILED test_math.py::test_div - ZeroDivisionError: division by zero
 ========================= 1 failed, 2 passed in 0.05s =========================
 ";
-        let result = compress_pytest(raw);
+        let result = compress_pytest(raw, &ctx());
         assert!(result.contains("[pytest]"));
-        assert!(result.contains("FAILURES"));
-        assert!(result.contains("test_div"));
+        assert!(result.contains("[failures: 1]"));
+        assert!(result.contains("  test_math.py::test_div (test_math.py:10)"));
         assert!(result.contains("ZeroDivisionError"));
         assert!(result.contains("1 failed, 2 passed"));
     }
 
+    #[test]
+    fn test_pytest_report() {
+        let raw = "\
+FAILED test_math.py::test_div - ZeroDivisionError: division by zero
+ERROR test_db.py::test_conn
+=============== 1 failed, 2 passed, 3 skipped, 1 error in 0.05s ===============
+";
+        let report = parse_pytest_report(raw);
+        assert_eq!(report.passed, 2);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.skipped, 3);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.failures.len(), 2);
+        assert_eq!(report.failures[0].name, "test_math.py::test_div");
+        assert_eq!(
+            report.failures[0].message.as_deref(),
+            Some("ZeroDivisionError: division by zero")
+        );
+        assert_eq!(report.failures[1].message, None);
+    }
+
+    #[test]
+    fn test_pytest_traceback_reduced() {
+        let raw = "\
+=================================== FAILURES ===================================
+_________________________ TestParse.test_load[empty-0] _________________________
+
+self = <tests.test_parse.TestParse object at 0x7f>, src = '', n = 0
+
+    @pytest.mark.parametrize(\"src,n\", [(\"\", 0)])
+    def test_load(self, src, n):
+>       assert load(src) == n
+
+tests/test_parse.py:12:
+_ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _
+
+src = ''
+
+    def load(src):
+>       return json.loads(src)
+
+app/parse.py:4:
+_ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _
+
+s = ''
+
+>       return _default_decoder.decode(s)
+E       json.decoder.JSONDecodeError: Expecting value: line 1 column 1 (char 0)
+
+/usr/lib/python3.12/json/__init__.py:346: JSONDecodeError
+=========================== short test summary info ============================
+FAILED tests/test_parse.py::TestParse::test_load[empty-0] - json.decoder.JSONDecodeError: Expecting value
+1 failed, 4 passed in 0.12s
+";
+        let result = compress_pytest(raw, &ctx());
+        assert_eq!(
+            result,
+            "[pytest] 1 failed, 4 passed in 0.12s
+[failures: 1]
+  tests/test_parse.py::TestParse::test_load[empty-0] (app/parse.py:4)
+    E json.decoder.JSONDecodeError: Expecting value: line 1 column 1 (char 0)
+"
+        );
+
+        let report = parse_pytest_report(raw);
+        assert_eq!((report.failed, report.passed), (1, 4));
+        let f = &report.failures[0];
+        assert_eq!(
+            f.message.as_deref(),
+            Some("json.decoder.JSONDecodeError: Expecting value")
+        );
+        assert_eq!((f.file.as_deref(), f.line), (Some("app/parse.py"), Some(4)));
+    }
+
+    #[test]
+    fn test_pytest_assertion_diff_and_collection_error() {
+        let raw = "\
+==================================== ERRORS ====================================
+____________________ ERROR collecting tests/test_broken.py _____________________
+tests/test_broken.py:1: in <module>
+    import missing
+E   ModuleNotFoundError: No module named 'missing'
+=================================== FAILURES ===================================
+___________________________________ test_eq ____________________________________
+
+    def test_eq():
+>       assert [1, 2] == [1, 3]
+E       assert [1, 2] == [1, 3]
+E         At index 1 diff: 2 != 3
+
+tests/test_eq.py:2: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_eq.py::test_eq - assert [1, 2] == [1, 3]
+ERROR tests/test_broken.py
+========================= 1 failed, 1 error in 0.05s ==========================
+";
+        let failures = parse_failures(raw);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].id, "tests/test_broken.py");
+        assert_eq!(
+            failures[0].errors,
+            vec!["ModuleNotFoundError: No module named 'missing'"]
+        );
+        assert_eq!(failures[1].id, "tests/test_eq.py::test_eq");
+        assert_eq!(
+            failures[1].errors,
+            vec!["assert [1, 2] == [1, 3]", "  At index 1 diff: 2 != 3"]
+        );
+    }
+
+    #[test]
+    fn test_pytest_short_summary_only() {
+        let raw = "\
+FAILED tests/test_a.py::test_x - assert 0
+1 failed in 0.01s
+";
+        let result = compress_pytest(raw, &ctx());
+        assert!(result.contains("[failures: 1]\n  tests/test_a.py::test_x\n    assert 0\n"));
+    }
+
+    #[test]
+    fn test_pytest_junit_counts() {
+        let raw = r#"1 failed, 1 passed in 0.02s
+# cx.report
+<?xml version="1.0" encoding="utf-8"?><testsuites><testsuite name="pytest" tests="4"><testcase classname="tests.test_a" name="test_ok" time="0.001" /><testcase classname="tests.test_a" name="test_skip" time="0.001"><skipped type="pytest.skip" message="later" /></testcase><testcase classname="tests.test_a" name="test_todo" time="0.001"><skipped type="pytest.xfail" message="bug" /></testcase><testcase classname="tests.test_a" name="test_bad" time="0.002"><failure message="assert 0">tests/test_a.py:9: AssertionError</failure></testcase></testsuite></testsuites>"#;
+        let result = compress_pytest(raw, &ctx());
+        assert!(result.starts_with("[pytest] 1 failed, 1 passed, 1 skipped, 1 xfailed\n"));
+
+        let report = parse_pytest_report(raw);
+        assert_eq!((report.skipped, report.xfailed), (1, 1));
+        assert!(report.suites.is_empty());
+        assert_eq!(report.failures[0].name, "tests.test_a::test_bad");
+        assert_eq!(report.failures[0].line, Some(9));
+    }
+
+    #[test]
+    fn test_pytest_xml_in_output_is_not_a_report() {
+        let output = r#"_________________________________ test_render _________________________________
+----------------------------- Captured stdout call -----------------------------
+<?xml version="1.0"?><testsuite name="fixture" tests="9"/>
+=========================== short test summary info ============================
+FAILED tests/test_xml.py::test_render - assert 0
+1 failed, 2 passed in 0.03s
+"#;
+        let result = compress_pytest(output, &ctx());
+        assert!(
+            result.starts_with("[pytest] 1 failed, 2 passed in 0.03s\n"),
+            "{result}"
+        );
+        assert!(result.contains("  tests/test_xml.py::test_render\n"));
+        let report = parse_pytest_report(output);
+        assert_eq!((report.passed, report.failed), (2, 1));
+
+        let raw = format!(
+            "{output}# cx.report\n{}",
+            r#"<?xml version="1.0" encoding="utf-8"?><testsuites><testsuite name="pytest" tests="3"><testcase classname="tests.test_xml" name="test_a" time="0.001" /><testcase classname="tests.test_xml" name="test_b" time="0.001" /><testcase classname="tests.test_xml" name="test_render" time="0.002"><failure message="assert 0">tests/test_xml.py:4: AssertionError</failure></testcase></testsuite></testsuites>"#
+        );
+        let report = parse_pytest_report(&raw);
+        assert_eq!((report.passed, report.failed), (2, 1));
+        assert!(compress_pytest(&raw, &ctx()).starts_with("[pytest] 1 failed, 2 passed\n"));
+    }
+
     #[test]
     fn test_pytest_no_tests() {
         let raw = "no tests ran in 0.01s\n";
-        let result = compress_pytest(raw);
+        let result = compress_pytest(raw, &ctx());
         assert!(result.contains("no tests ran"));
     }
 
@@ -378,7 +841,7 @@ FAILED test_math.py::test_div - ZeroDivisionError: division by zero
 
     #[test]
     fn test_ruff_clean() {
-        let result = compress_ruff("");
+        let result = compress_ruff("", &ctx());
         assert_eq!(result, "[ruff] clean");
     }
 
@@ -390,13 +853,23 @@ src/main.py:15:1: F401 `os` imported but unused
 src/utils.py:3:1: E302 Expected 2 blank lines
 Found 3 fixable errors.
 ";
-        let result = compress_ruff(raw);
+        let result = compress_ruff(raw, &ctx());
         assert!(result.contains("[ruff] Found 3 fixable errors."));
         assert!(result.contains("E501"));
         assert!(result.contains("F401"));
         assert!(result.contains("E302"));
     }
 
+    #[test]
+    fn test_ruff_summary() {
+        let raw = "src/main.py:10:5: E501 Line too long (120 > 88)\nFound 1 error.\n";
+        let v = PythonCompressor.summarize(raw, Some("ruff"), &ctx());
+        assert_eq!(v["diagnostics"][0]["code"], "E501");
+        assert_eq!(v["diagnostics"][0]["file"], "src/main.py");
+        assert_eq!(v["diagnostics"][0]["column"], 5);
+        assert_eq!(v["summary"], "Found 1 error.");
+    }
+
     #[test]
     fn test_ruff_many_issues_truncated() {
         let mut raw = String::new();
@@ -404,7 +877,7 @@ Found 3 fixable errors.
             raw.push_str(&format!("src/file{i}.py:1:1: E501 Line too long\n"));
         }
         raw.push_str("Found 35 fixable errors.\n");
-        let result = compress_ruff(&raw);
+        let result = compress_ruff(&raw, &ctx());
         assert!(result.contains("[ruff] Found 35 fixable errors."));
         assert!(result.contains("… +5 more"));
     }
@@ -412,7 +885,7 @@ Found 3 fixable errors.
     #[test]
     fn test_ruff_format() {
         let raw = "Would reformat: 3 files\n";
-        let result = compress_ruff(raw);
+        let result = compress_ruff(raw, &ctx());
         assert!(result.contains("[ruff] Would reformat: 3 files"));
     }
 
@@ -421,7 +894,7 @@ Found 3 fixable errors.
     #[test]
     fn test_mypy_clean() {
         let raw = "Success: no issues found in 5 source files\n";
-        let result = compress_mypy(raw);
+        let result = compress_mypy(raw, &ctx());
         assert_eq!(result, "[mypy] Success: no issues found in 5 source files");
     }
 
@@ -433,13 +906,32 @@ src/main.py:15: error: Missing return statement
 src/utils.py:3: note: See class definition
 Found 2 errors in 2 files (checked 5 source files)
 ";
-        let result = compress_mypy(raw);
+        let result = compress_mypy(raw, &ctx());
         assert!(result.contains("[mypy] 3 errors"));
         assert!(result.contains("Incompatible types"));
         assert!(result.contains("Missing return"));
         assert!(result.contains("Found 2 errors"));
     }
 
+    #[test]
+    fn test_mypy_summary() {
+        let raw = "\
+src/main.py:10: error: Incompatible types in assignment  [assignment]
+src/utils.py:3: note: See class definition
+Found 1 error in 1 file (checked 5 source files)
+";
+        let summary = parse_mypy(raw);
+        assert_eq!(summary.diagnostics.len(), 2);
+        assert_eq!(summary.diagnostics[0].level, "error");
+        assert_eq!(summary.diagnostics[0].code.as_deref(), Some("assignment"));
+        assert_eq!(
+            summary.diagnostics[0].message,
+            "Incompatible types in assignment"
+        );
+        assert_eq!(summary.diagnostics[1].level, "note");
+        assert!(summary.summary.unwrap().starts_with("Found 1 error"));
+    }
+
     // ── pip ──
 
     #[test]
@@ -449,7 +941,7 @@ Collecting requests
   Downloading requests-2.31.0.tar.gz
 Successfully installed requests-2.31.0 urllib3-2.0.4
 ";
-        let result = compress_pip_install(raw);
+        let result = compress_pip_install(raw, &ctx());
         assert!(result.contains("[pip] Successfully installed"));
     }
 
@@ -459,7 +951,7 @@ Successfully installed requests-2.31.0 urllib3-2.0.4
 Requirement already satisfied: requests in ./venv/lib/python3.11/site-packages (2.31.0)
 Requirement already satisfied: urllib3 in ./venv/lib/python3.11/site-packages (2.0.4)
 ";
-        let result = compress_pip_install(raw);
+        let result = compress_pip_install(raw, &ctx());
         assert!(result.contains("2 already satisfied"));
     }
 
@@ -472,7 +964,7 @@ requests   2.31.0
 flask      3.0.0
 numpy      1.25.0
 ";
-        let result = compress_pip_list(raw);
+        let result = compress_pip_list(raw, &ctx());
         assert!(result.contains("[packages: 3]"));
         assert!(result.contains("requests"));
         assert!(result.contains("flask"));
@@ -480,7 +972,7 @@ numpy      1.25.0
 
     #[test]
     fn test_pip_list_empty() {
-        let result = compress_pip_list("");
+        let result = compress_pip_list("", &ctx());
         assert_eq!(result, "[pip list] empty");
     }
 
@@ -492,7 +984,7 @@ Package    Version Latest
 requests   2.28.0  2.31.0
 flask      2.3.0   3.0.0
 ";
-        let result = compress_pip_outdated(raw);
+        let result = compress_pip_outdated(raw, &ctx());
         assert!(result.contains("[outdated: 2]"));
         assert!(result.contains("requests"));
     }
@@ -503,7 +995,7 @@ flask      2.3.0   3.0.0
 Package    Version Latest
 ---------- ------- ------
 ";
-        let result = compress_pip_outdated(raw);
+        let result = compress_pip_outdated(raw, &ctx());
         assert_eq!(result, "[pip] all up to date");
     }
 
@@ -517,7 +1009,7 @@ Resolved 42 packages in 1.2s
 + requests==2.31.0
 - old-package==1.0.0
 ";
-        let result = compress_uv_sync(raw);
+        let result = compress_uv_sync(raw, &ctx());
         assert!(result.contains("[uv sync]"));
         assert!(result.contains("Resolved 42 packages"));
         assert!(result.contains("+2 -1"));
@@ -526,7 +1018,7 @@ Resolved 42 packages in 1.2s
     #[test]
     fn test_uv_lock() {
         let raw = "Resolved 42 packages in 0.5s\n";
-        let result = compress_uv_lock(raw);
+        let result = compress_uv_lock(raw, &ctx());
         assert!(result.contains("[uv lock] Resolved 42 packages"));
     }
 
@@ -537,7 +1029,7 @@ Resolved 15 packages in 0.3s
 + requests==2.31.0
 + urllib3==2.0.4
 ";
-        let result = compress_uv_dep("add", raw);
+        let result = compress_uv_dep("add", raw, &ctx());
         assert!(result.contains("[uv add]"));
         assert!(result.contains("Resolved 15 packages"));
         assert!(result.contains("+ requests"));
@@ -550,7 +1042,7 @@ Resolved 13 packages in 0.2s
 - requests==2.31.0
 - urllib3==2.0.4
 ";
-        let result = compress_uv_dep("remove", raw);
+        let result = compress_uv_dep("remove", raw, &ctx());
         assert!(result.contains("[uv remove]"));
         assert!(result.contains("- requests"));
     }
@@ -562,14 +1054,14 @@ Resolved 13 packages in 0.2s
         let c = PythonCompressor;
         let raw =
             "============================== 1 passed in 0.01s ==============================\n";
-        let result = c.compress(raw, Some("pytest"));
+        let result = c.compress(raw, Some("pytest"), &ctx());
         assert!(result.contains("[pytest]"));
     }
 
     #[test]
     fn test_trait_dispatches_ruff() {
         let c = PythonCompressor;
-        let result = c.compress("", Some("ruff"));
+        let result = c.compress("", Some("ruff"), &ctx());
         assert!(result.contains("[ruff] clean"));
     }
 
@@ -577,7 +1069,7 @@ Resolved 13 packages in 0.2s
     fn test_trait_dispatches_mypy() {
         let c = PythonCompressor;
         let raw = "Success: no issues found in 1 source file\n";
-        let result = c.compress(raw, Some("mypy"));
+        let result = c.compress(raw, Some("mypy"), &ctx());
         assert!(result.contains("[mypy] Success"));
     }
 
@@ -585,14 +1077,14 @@ Resolved 13 packages in 0.2s
     fn test_trait_dispatches_pip() {
         let c = PythonCompressor;
         let raw = "Successfully installed pkg-1.0\n";
-        let result = c.compress(raw, Some("pip"));
+        let result = c.compress(raw, Some("pip"), &ctx());
         assert!(result.contains("[pip]"));
     }
 
     #[test]
     fn test_trait_dispatches_list() {
         let c = PythonCompressor;
-        let result = c.compress("", Some("list"));
+        let result = c.compress("", Some("list"), &ctx());
         assert!(result.contains("[pip list]"));
     }
 
@@ -600,14 +1092,14 @@ Resolved 13 packages in 0.2s
     fn test_trait_dispatches_sync() {
         let c = PythonCompressor;
         let raw = "Resolved 5 packages in 0.1s\n";
-        let result = c.compress(raw, Some("sync"));
+        let result = c.compress(raw, Some("sync"), &ctx());
         assert!(result.contains("[uv sync]"));
     }
 
     #[test]
     fn test_trait_fallback() {
         let c = PythonCompressor;
-        let result = c.compress("some output", None);
+        let result = c.compress("some output", None, &ctx());
         assert!(result.contains("some output"));
     }
 }
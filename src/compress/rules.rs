@@ -0,0 +1,351 @@
+//! User-defined compressors: `[[compressor]]` tables in `.cx.toml`.
+//!
+//! A rule matches a program (and optionally its first argument) and describes
+//! the compression declaratively, so internal tools can be taught to `cx`
+//! without writing Rust:
+//!
+//! ```toml
+//! [[compressor]]
+//! name = "terraform"
+//! program = "terraform"
+//! subcommand = "plan"
+//! args = ["-no-color"]
+//! drop = ['^\s*$', 'Refreshing state']
+//! summary = [{ pattern = 'Plan: (\d+) to add, (\d+) to change, (\d+) to destroy', template = "plan: +$1 ~$2 -$3" }]
+//! section = [{ name = "destroy", match = 'will be destroyed', max = 5 }]
+//! ```
+
+use super::truncate::{Strategy, truncate_by};
+use super::{CompressCtx, Compressor};
+use regex::{Regex, RegexSet};
+use serde::Deserialize;
+
+/// A `[[compressor]]` table as written in TOML.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RuleSpec {
+    /// Label for the output and footer (defaults to `program`).
+    pub name: Option<String>,
+    /// Program the rule applies to, matched against argv[0]'s file name.
+    pub program: String,
+    /// Only apply when the first argument is this subcommand.
+    pub subcommand: Option<String>,
+    /// Arguments injected after the subcommand unless already given.
+    pub args: Vec<String>,
+    /// Regexes: when non-empty, only matching lines are kept.
+    pub keep: Vec<String>,
+    /// Regexes: matching lines are dropped (applied before `keep`).
+    pub drop: Vec<String>,
+    /// Lines printed first, built from regex captures.
+    pub summary: Vec<SummarySpec>,
+    /// Named groups of matching lines, each capped to `max` entries.
+    pub section: Vec<SectionSpec>,
+    /// Collapse consecutive identical lines into `line  (×N)`.
+    pub dedup: bool,
+    /// Line cap for the remaining output (defaults to `max_lines`).
+    pub max_lines: Option<usize>,
+    /// Truncation strategy for the remaining output.
+    pub truncate: Option<Strategy>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SummarySpec {
+    pub pattern: String,
+    /// Expansion with `$1` / `${name}` capture references; the whole match if unset.
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SectionSpec {
+    pub name: String,
+    #[serde(rename = "match")]
+    pub pattern: String,
+    /// Entries shown (defaults to `max_hits`).
+    pub max: Option<usize>,
+}
+
+impl RuleSpec {
+    /// Whether this rule applies to `program args…`.
+    pub fn matches(&self, program: &str, args: &[String]) -> bool {
+        let name = std::path::Path::new(program)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(program);
+        name == self.program
+            && self
+                .subcommand
+                .as_ref()
+                .is_none_or(|sub| args.first() == Some(sub))
+    }
+
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.program)
+    }
+
+    /// `args` with the rule's default arguments injected after the subcommand.
+    pub fn apply_args(&self, args: &[String]) -> Vec<String> {
+        let at = usize::from(self.subcommand.is_some()).min(args.len());
+        let missing = self.args.iter().filter(|a| !args.contains(a)).cloned();
+        args[..at]
+            .iter()
+            .cloned()
+            .chain(missing)
+            .chain(args[at..].iter().cloned())
+            .collect()
+    }
+}
+
+/// A compiled rule, ready to compress.
+pub struct RuleCompressor {
+    label: String,
+    keep: Option<RegexSet>,
+    drop: Option<RegexSet>,
+    summary: Vec<(Regex, Option<String>)>,
+    sections: Vec<(String, Regex, Option<usize>)>,
+    dedup: bool,
+    max_lines: Option<usize>,
+    truncate: Option<Strategy>,
+}
+
+impl RuleCompressor {
+    /// Compile a rule's regexes; the error names the rule and the bad pattern.
+    pub fn compile(spec: &RuleSpec) -> Result<Self, String> {
+        let label = spec.label().to_string();
+        let err = |e: regex::Error| format!("rule `{label}`: {e}");
+        let set = |patterns: &[String]| -> Result<Option<RegexSet>, String> {
+            if patterns.is_empty() {
+                return Ok(None);
+            }
+            RegexSet::new(patterns).map(Some).map_err(err)
+        };
+
+        Ok(Self {
+            keep: set(&spec.keep)?,
+            drop: set(&spec.drop)?,
+            summary: spec
+                .summary
+                .iter()
+                .map(|s| Ok((Regex::new(&s.pattern).map_err(err)?, s.template.clone())))
+                .collect::<Result<_, String>>()?,
+            sections: spec
+                .section
+                .iter()
+                .map(|s| Ok((s.name.clone(), Regex::new(&s.pattern).map_err(err)?, s.max)))
+                .collect::<Result<_, String>>()?,
+            dedup: spec.dedup,
+            max_lines: spec.max_lines,
+            truncate: spec.truncate,
+            label,
+        })
+    }
+}
+
+impl Compressor for RuleCompressor {
+    fn compress(&self, raw: &str, _sub: Option<&str>, ctx: &CompressCtx) -> String {
+        let mut summary: Vec<String> = Vec::new();
+        for line in raw.lines() {
+            for (re, template) in &self.summary {
+                if let Some(caps) = re.captures(line) {
+                    let mut text = String::new();
+                    match template {
+                        Some(t) => caps.expand(t, &mut text),
+                        None => text.push_str(&caps[0]),
+                    }
+                    summary.push(text);
+                }
+            }
+        }
+
+        let kept: Vec<&str> = raw
+            .lines()
+            .filter(|l| !self.drop.as_ref().is_some_and(|d| d.is_match(l)))
+            .filter(|l| self.keep.as_ref().is_none_or(|k| k.is_match(l)))
+            .collect();
+
+        // Each kept line goes to the first section it matches, or the body.
+        let mut grouped: Vec<Vec<&str>> = vec![Vec::new(); self.sections.len()];
+        let mut body: Vec<&str> = Vec::new();
+        for line in kept {
+            match self
+                .sections
+                .iter()
+                .position(|(_, re, _)| re.is_match(line))
+            {
+                Some(i) => grouped[i].push(line),
+                None => body.push(line),
+            }
+        }
+
+        let mut out: Vec<String> = summary.into_iter().take(ctx.max_items).collect();
+        for ((name, _, max), lines) in self.sections.iter().zip(&grouped) {
+            if lines.is_empty() {
+                continue;
+            }
+            let lines = if self.dedup {
+                dedup(lines)
+            } else {
+                to_owned(lines)
+            };
+            let max = max.unwrap_or(ctx.max_hits);
+            out.push(format!("[{name}: {}]", lines.len()));
+            out.extend(lines.iter().take(max).map(|l| format!("  {}", l.trim())));
+            if lines.len() > max {
+                out.push(format!("  … +{} more", lines.len() - max));
+            }
+        }
+
+        let body = if self.dedup {
+            dedup(&body)
+        } else {
+            to_owned(&body)
+        };
+        if !body.is_empty() {
+            let strategy = self.truncate.or(ctx.truncate).unwrap_or(Strategy::HeadTail);
+            let max_lines = self.max_lines.unwrap_or(ctx.max_lines);
+            out.push(truncate_by(
+                &body.join("\n"),
+                max_lines,
+                ctx.max_line_len,
+                strategy,
+            ));
+        }
+
+        if out.is_empty() {
+            return format!("[{}] no output", self.label);
+        }
+        out.join("\n")
+    }
+}
+
+fn to_owned(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+}
+
+/// Collapse runs of identical lines into `line  (×N)`.
+fn dedup(lines: &[&str]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let run = lines[i..].iter().take_while(|l| **l == lines[i]).count();
+        if run > 1 {
+            out.push(format!("{}  (×{run})", lines[i]));
+        } else {
+            out.push(lines[i].to_string());
+        }
+        i += run;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
+    fn spec(toml_str: &str) -> RuleSpec {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_matches_program_and_subcommand() {
+        let rule = spec("program = \"terraform\"\nsubcommand = \"plan\"");
+        assert!(rule.matches("terraform", &args(&["plan"])));
+        assert!(rule.matches("/usr/local/bin/terraform", &args(&["plan", "-out=x"])));
+        assert!(!rule.matches("terraform", &args(&["apply"])));
+        assert!(!rule.matches("terraform", &[]));
+        assert!(!rule.matches("tf", &args(&["plan"])));
+
+        let any = spec("program = \"deploy\"");
+        assert!(any.matches("deploy", &[]));
+        assert!(any.matches("./deploy", &args(&["--prod"])));
+    }
+
+    #[test]
+    fn test_apply_args_injects_missing_after_subcommand() {
+        let rule = spec(
+            "program = \"tf\"\nsubcommand = \"plan\"\nargs = [\"-no-color\", \"-input=false\"]",
+        );
+        assert_eq!(
+            rule.apply_args(&args(&["plan", "-input=false", "dir"])),
+            args(&["plan", "-no-color", "-input=false", "dir"])
+        );
+        let plain = spec("program = \"lint\"\nargs = [\"--quiet\"]");
+        assert_eq!(plain.apply_args(&args(&["src"])), args(&["--quiet", "src"]));
+    }
+
+    #[test]
+    fn test_keep_and_drop() {
+        let rule = spec("program = \"x\"\ndrop = ['^debug']\nkeep = ['^(info|debug|error)']");
+        let c = RuleCompressor::compile(&rule).unwrap();
+        let out = c.compress("info: a\ndebug: b\nnoise\nerror: c", None, &ctx());
+        assert_eq!(out, "info: a\nerror: c");
+    }
+
+    #[test]
+    fn test_summary_templates() {
+        let rule = spec(
+            r#"
+program = "terraform"
+[[summary]]
+pattern = 'Plan: (\d+) to add, (\d+) to change, (?P<del>\d+) to destroy'
+template = "plan: +$1 ~$2 -${del}"
+[[summary]]
+pattern = 'Apply complete!'
+"#,
+        );
+        let c = RuleCompressor::compile(&rule).unwrap();
+        let raw = "Refreshing…\nPlan: 3 to add, 1 to change, 0 to destroy.\nApply complete! Resources: 3 added.";
+        let out = c.compress(raw, None, &ctx());
+        assert!(out.starts_with("plan: +3 ~1 -0\nApply complete!\n"));
+    }
+
+    #[test]
+    fn test_sections_limit_and_dedup() {
+        let rule = spec(
+            r#"
+program = "build"
+dedup = true
+[[section]]
+name = "warnings"
+match = '^WARN'
+max = 2
+"#,
+        );
+        let c = RuleCompressor::compile(&rule).unwrap();
+        let raw = "start\nWARN a\nWARN a\nWARN b\nWARN c\nWARN d\ndone\ndone";
+        let out = c.compress(raw, None, &ctx());
+        assert_eq!(
+            out,
+            "[warnings: 4]\n  WARN a  (×2)\n  WARN b\n  … +2 more\nstart\ndone  (×2)"
+        );
+    }
+
+    #[test]
+    fn test_max_lines_and_truncate() {
+        let rule = spec("program = \"x\"\nmax_lines = 2\ntruncate = \"tail\"");
+        let c = RuleCompressor::compile(&rule).unwrap();
+        let out = c.compress("a\nb\nc\nd", None, &ctx());
+        assert!(out.ends_with("c\nd"));
+        assert!(!out.contains("\nb"));
+    }
+
+    #[test]
+    fn test_invalid_regex_names_rule() {
+        let rule = spec("name = \"mine\"\nprogram = \"x\"\nkeep = ['(']");
+        let err = RuleCompressor::compile(&rule).err().unwrap();
+        assert!(err.starts_with("rule `mine`:"));
+    }
+
+    #[test]
+    fn test_empty_output() {
+        let c = RuleCompressor::compile(&spec("program = \"x\"")).unwrap();
+        assert_eq!(c.compress("", None, &ctx()), "[x] no output");
+    }
+}
@@ -0,0 +1,231 @@
+//! Typed summaries emitted by `--format json`, shared across compressors.
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// A compiler / linter diagnostic with its location when known.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    /// Replacement the tool suggests for the flagged code, when it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    /// Attach a `file:line[:col]` location.
+    pub fn at(mut self, loc: Location) -> Self {
+        self.file = Some(loc.file);
+        self.line = loc.line;
+        self.column = loc.column;
+        self
+    }
+}
+
+/// Slowest tests kept in a report with per-test timings.
+pub const SLOWEST_TESTS: usize = 5;
+
+/// Test run counts + failing tests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TestReport {
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    /// Collection / setup errors (pytest), distinct from assertion failures.
+    pub errors: u32,
+    /// Expected failures (pytest `xfail`), not counted as skipped.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub xfailed: u32,
+    pub failures: Vec<TestFailure>,
+    /// Counts per test binary / suite, when the runner reports them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suites: Vec<SuiteReport>,
+    /// Slowest tests first, when the runner reports durations.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub slowest: Vec<TestTiming>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TestFailure {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Where the test failed (the panic / assertion location).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SuiteReport {
+    pub name: String,
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestTiming {
+    pub name: String,
+    pub secs: f64,
+}
+
+impl TestReport {
+    /// Keep the `n` slowest tests, slowest first.
+    pub fn keep_slowest(&mut self, n: usize) {
+        self.slowest.sort_by(|a, b| b.secs.total_cmp(&a.secs));
+        self.slowest.truncate(n);
+    }
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// A `file:line[:col]` source location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub file: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// Parse a leading `file:line[:col]` location, returning it and the remainder
+/// after the trailing `: ` (if any). The line number is required.
+pub fn parse_location(s: &str) -> Option<(Location, &str)> {
+    let (file, after_file) = s.split_once(':')?;
+    let file = file.trim();
+    if file.is_empty() || file.contains(' ') {
+        return None;
+    }
+
+    let (line, after_line) = after_file.split_once(':').unwrap_or((after_file, ""));
+    let line: u32 = line.trim().parse().ok()?;
+
+    let (column, rest) = match after_line.split_once(':') {
+        Some((col, rest)) if col.trim().parse::<u32>().is_ok() => (col.trim().parse().ok(), rest),
+        _ => match after_line.trim().parse::<u32>() {
+            Ok(col) => (Some(col), ""),
+            Err(_) => (None, after_line),
+        },
+    };
+
+    Some((
+        Location {
+            file: file.to_string(),
+            line: Some(line),
+            column,
+        },
+        rest.trim_start(),
+    ))
+}
+
+/// The message and location of the first Rust panic in `block`.
+///
+/// Understands both `panicked at src/lib.rs:1:1:` followed by the (possibly
+/// multi-line) message, and the pre-1.73 `panicked at 'msg', src/lib.rs:1:1`.
+pub fn parse_panic(block: &[&str]) -> Option<(String, Option<Location>)> {
+    let mut lines = block.iter();
+    while let Some(line) = lines.next() {
+        let Some((_, rest)) = line.split_once("panicked at ") else {
+            continue;
+        };
+        if let Some(quoted) = rest.strip_prefix('\'') {
+            return Some(match quoted.rsplit_once("', ") {
+                Some((msg, loc)) => (msg.to_string(), parse_location(loc).map(|(l, _)| l)),
+                None => (quoted.trim_end_matches('\'').to_string(), None),
+            });
+        }
+        let loc = parse_location(rest.trim_end().trim_end_matches(':')).map(|(l, _)| l);
+        let message: Vec<&str> = lines
+            .take_while(|l| {
+                !l.trim().is_empty()
+                    && !l.starts_with("note: ")
+                    && !l.starts_with("stack backtrace:")
+            })
+            .map(|l| l.trim_end())
+            .collect();
+        return Some((message.join("\n"), loc));
+    }
+    None
+}
+
+/// Sum `N <word>` counts in a summary line, e.g. `3 passed; 1 failed`.
+pub fn count_of(line: &str, word: &str) -> u32 {
+    let tokens: Vec<&str> = line
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter(|t| !t.is_empty())
+        .collect();
+    tokens
+        .windows(2)
+        .filter(|w| w[1].trim_end_matches('.') == word)
+        .filter_map(|w| w[0].parse::<u32>().ok())
+        .sum()
+}
+
+/// Fallback summary: the compressed text, one array entry per line.
+pub fn lines(compressed: &str) -> Value {
+    json!({ "lines": compressed.lines().collect::<Vec<_>>() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_location_with_column() {
+        let (loc, rest) = parse_location("src/main.py:10:5: E501 Line too long").unwrap();
+        assert_eq!(loc.file, "src/main.py");
+        assert_eq!(loc.line, Some(10));
+        assert_eq!(loc.column, Some(5));
+        assert_eq!(rest, "E501 Line too long");
+    }
+
+    #[test]
+    fn test_parse_location_without_column() {
+        let (loc, rest) = parse_location("src/main.py:10: error: Bad type").unwrap();
+        assert_eq!(loc.file, "src/main.py");
+        assert_eq!(loc.line, Some(10));
+        assert_eq!(loc.column, None);
+        assert_eq!(rest, "error: Bad type");
+    }
+
+    #[test]
+    fn test_parse_location_bare() {
+        let (loc, rest) = parse_location("src/lib.rs:3:1").unwrap();
+        assert_eq!(loc.column, Some(1));
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn test_parse_location_rejects_prose() {
+        assert!(parse_location("error: could not compile").is_none());
+        assert!(parse_location("Found 3 errors: oops").is_none());
+    }
+
+    #[test]
+    fn test_count_of() {
+        let line = "test result: FAILED. 3 passed; 1 failed; 2 ignored; 0 measured";
+        assert_eq!(count_of(line, "passed"), 3);
+        assert_eq!(count_of(line, "failed"), 1);
+        assert_eq!(count_of(line, "ignored"), 2);
+        assert_eq!(count_of("1 failed, 2 passed in 0.05s", "passed"), 2);
+        assert_eq!(count_of("nothing here", "passed"), 0);
+    }
+
+    #[test]
+    fn test_lines_fallback() {
+        let v = lines("a\nb");
+        assert_eq!(v["lines"][1], "b");
+    }
+}
@@ -1,40 +1,169 @@
 //! Shared truncation utilities — the building blocks for all compressors.
 
-const MAX_LINES: usize = 150;
-const MAX_LINE_LEN: usize = 300;
+use super::CompressCtx;
+use serde::Deserialize;
 
-/// Truncate output: cap total lines and per-line length.
-pub fn truncate(raw: &str) -> String {
-    truncate_with(raw, MAX_LINES, MAX_LINE_LEN)
+/// Context lines kept around each match by `Strategy::Errors`.
+const ERROR_CONTEXT: usize = 2;
+
+/// Which lines survive when output exceeds `max_lines`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// The first lines.
+    #[default]
+    Head,
+    /// The last lines: final errors and summaries.
+    Tail,
+    /// The first and last lines, with the middle elided.
+    HeadTail,
+    /// Lines mentioning errors / warnings / failures, plus context.
+    Errors,
+}
+
+/// Truncate output: cap total lines and per-line length from the context,
+/// using the configured strategy (head by default).
+pub fn truncate(raw: &str, ctx: &CompressCtx) -> String {
+    truncate_by(
+        raw,
+        ctx.max_lines,
+        ctx.max_line_len,
+        ctx.truncate.unwrap_or_default(),
+    )
 }
 
-/// Truncate with custom limits.
+/// Truncate with custom limits, keeping the first lines.
 pub fn truncate_with(raw: &str, max_lines: usize, max_line_len: usize) -> String {
+    truncate_by(raw, max_lines, max_line_len, Strategy::Head)
+}
+
+/// Truncate with custom limits and an explicit strategy.
+pub fn truncate_by(raw: &str, max_lines: usize, max_line_len: usize, strategy: Strategy) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     let total = lines.len();
-    let mut out = Vec::with_capacity(max_lines.min(total) + 1);
-
-    for line in lines.iter().take(max_lines) {
-        if line.len() > max_line_len {
-            let mut s = line[..max_line_len].to_string();
-            s.push_str(" …");
-            out.push(s);
+    let shorten = |line: &str| {
+        let kept = cut(line, max_line_len);
+        if kept.len() < line.len() {
+            format!("{kept} …")
         } else {
-            out.push(line.to_string());
+            line.to_string()
         }
+    };
+
+    if total <= max_lines {
+        return lines
+            .iter()
+            .map(|l| shorten(l))
+            .collect::<Vec<_>>()
+            .join("\n");
     }
 
-    if total > max_lines {
-        out.push(format!(
-            "\n[cx] … {total} lines total, showing first {max_lines}"
-        ));
+    let mut out: Vec<String> = Vec::with_capacity(max_lines + 2);
+    match strategy {
+        Strategy::Head => {
+            out.extend(lines[..max_lines].iter().map(|l| shorten(l)));
+            out.push(format!(
+                "\n[cx] … {total} lines total, showing first {max_lines}"
+            ));
+        }
+        Strategy::Tail => {
+            out.push(format!(
+                "[cx] … {total} lines total, showing last {max_lines}\n"
+            ));
+            out.extend(lines[total - max_lines..].iter().map(|l| shorten(l)));
+        }
+        Strategy::HeadTail => {
+            // The end of a run (final error, summary) matters most: it gets the odd line.
+            let head = max_lines / 2;
+            let tail = max_lines - head;
+            out.extend(lines[..head].iter().map(|l| shorten(l)));
+            out.push(format!(
+                "[cx] … {} lines omitted ({total} total)",
+                total - head - tail
+            ));
+            out.extend(lines[total - tail..].iter().map(|l| shorten(l)));
+        }
+        Strategy::Errors => {
+            let keep = error_lines(&lines);
+            if keep.is_empty() {
+                return truncate_by(raw, max_lines, max_line_len, Strategy::HeadTail);
+            }
+            let mut prev: Option<usize> = None;
+            for &i in keep.iter().take(max_lines) {
+                if prev.is_some_and(|p| i > p + 1) || (prev.is_none() && i > 0) {
+                    out.push("  …".into());
+                }
+                out.push(shorten(lines[i]));
+                prev = Some(i);
+            }
+            out.push(format!(
+                "\n[cx] … {total} lines total, showing {} around errors / warnings",
+                keep.len().min(max_lines)
+            ));
+        }
     }
     out.join("\n")
 }
 
+/// Indices of lines matching error / warning / failure keywords, widened by
+/// `ERROR_CONTEXT` lines on each side.
+fn error_lines(lines: &[&str]) -> Vec<usize> {
+    let mut keep = vec![false; lines.len()];
+    for (i, line) in lines.iter().enumerate() {
+        let lower = line.to_lowercase();
+        if ["error", "warn", "fail", "panic", "traceback", "exception"]
+            .iter()
+            .any(|k| lower.contains(k))
+        {
+            let from = i.saturating_sub(ERROR_CONTEXT);
+            let to = (i + ERROR_CONTEXT).min(lines.len() - 1);
+            keep[from..=to].iter_mut().for_each(|k| *k = true);
+        }
+    }
+    keep.iter()
+        .enumerate()
+        .filter(|(_, k)| **k)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Cut `s` to at most `max_chars` characters. Never splits a UTF-8 sequence,
+/// and backs off rather than separating combining marks, variation selectors
+/// or ZWJ emoji sequences from their base character.
+pub fn cut(s: &str, max_chars: usize) -> &str {
+    let Some((idx, _)) = s.char_indices().nth(max_chars) else {
+        return s;
+    };
+    let mut end = idx;
+    while let (Some(next), Some(prev)) = (s[end..].chars().next(), s[..end].chars().next_back()) {
+        if !extends_cluster(next) && prev != '\u{200d}' {
+            break;
+        }
+        end -= prev.len_utf8();
+    }
+    // A single cluster longer than the limit: fall back to a plain char cut.
+    if end == 0 { &s[..idx] } else { &s[..end] }
+}
+
+/// Characters that attach to the preceding one in a grapheme cluster.
+fn extends_cluster(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036f}'     // combining diacritics
+        | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{3099}'..='\u{309a}'   // kana voicing marks
+        | '\u{fe00}'..='\u{fe0f}'   // variation selectors
+        | '\u{fe20}'..='\u{fe2f}'
+        | '\u{200c}'..='\u{200d}'   // ZWNJ / ZWJ
+        | '\u{1f3fb}'..='\u{1f3ff}' // skin tone modifiers
+        | '\u{e0020}'..='\u{e007f}' // tag sequences (flags)
+    )
+}
+
 /// Keep only lines matching a predicate, then truncate.
 #[allow(dead_code)]
-pub fn filter_and_truncate<F>(raw: &str, keep: F) -> String
+pub fn filter_and_truncate<F>(raw: &str, ctx: &CompressCtx, keep: F) -> String
 where
     F: Fn(&str) -> bool,
 {
@@ -43,11 +172,11 @@ where
         .filter(|l| keep(l))
         .collect::<Vec<_>>()
         .join("\n");
-    truncate(&filtered)
+    truncate(&filtered, ctx)
 }
 
 /// Deduplicate consecutive identical lines, showing counts.
-pub fn dedup_lines(raw: &str) -> String {
+pub fn dedup_lines(raw: &str, ctx: &CompressCtx) -> String {
     let lines: Vec<&str> = raw.lines().collect();
     if lines.is_empty() {
         return String::new();
@@ -76,17 +205,21 @@ pub fn dedup_lines(raw: &str) -> String {
         out.push(current.to_string());
     }
 
-    truncate(&out.join("\n"))
+    truncate(&out.join("\n"), ctx)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn ctx() -> CompressCtx {
+        CompressCtx::default()
+    }
+
     #[test]
     fn test_truncate_short_input() {
         let raw = "line1\nline2\nline3";
-        let result = truncate(raw);
+        let result = truncate(raw, &ctx());
         assert_eq!(result, "line1\nline2\nline3");
     }
 
@@ -96,7 +229,7 @@ mod tests {
             .map(|i| format!("line {i}"))
             .collect::<Vec<_>>()
             .join("\n");
-        let result = truncate(&raw);
+        let result = truncate(&raw, &ctx());
         let lines: Vec<&str> = result.lines().collect();
         // 150 lines + 1 blank + 1 footer
         assert!(lines.len() <= 153);
@@ -106,7 +239,7 @@ mod tests {
     #[test]
     fn test_truncate_caps_long_lines() {
         let long = "x".repeat(500);
-        let result = truncate(&long);
+        let result = truncate(&long, &ctx());
         assert!(result.contains("…"));
         assert!(result.len() < 500);
     }
@@ -130,20 +263,162 @@ mod tests {
         assert!(result.contains("this_line_ …"));
     }
 
+    #[test]
+    fn test_truncate_with_non_ascii_line() {
+        let raw = "é".repeat(20);
+        let result = truncate_with(&raw, 10, 5);
+        assert_eq!(result, "ééééé …");
+    }
+
+    #[test]
+    fn test_cut_counts_chars_not_bytes() {
+        assert_eq!(cut("héllo wörld", 5), "héllo");
+        assert_eq!(cut("日本語のテキスト", 3), "日本語");
+        assert_eq!(cut("short", 10), "short");
+        assert_eq!(cut("", 3), "");
+        assert_eq!(cut("abc", 0), "");
+    }
+
+    #[test]
+    fn test_cut_keeps_clusters_together() {
+        // e + combining acute: cutting after the `e` would orphan the accent.
+        assert_eq!(cut("cafe\u{301}s", 4), "caf");
+        // 👍🏽 is thumbs up + skin tone modifier.
+        assert_eq!(cut("ok 👍🏽!", 4), "ok ");
+        // 👩‍💻 is woman + ZWJ + laptop.
+        assert_eq!(cut("a👩\u{200d}💻b", 2), "a");
+        assert_eq!(cut("a👩\u{200d}💻b", 3), "a");
+        assert_eq!(cut("a👩\u{200d}💻b", 4), "a👩\u{200d}💻");
+    }
+
+    /// Property: for every input and every limit, `cut` returns a prefix of at
+    /// most `max` chars on a char boundary, and `truncate_with` never panics.
+    #[test]
+    fn test_cut_property_non_ascii() {
+        let samples = [
+            "Fix: gérer les accents dans les messages de commit — déjà fait",
+            "修正: 日本語のコミットメッセージを正しく切り詰める",
+            "🚀 deploy 👩\u{200d}👩\u{200d}👧 family 🇫🇷 flag 👍🏽 done",
+            "e\u{301}e\u{301}e\u{301} combining",
+            "mixed ascii and ü and ß and Ω and 한국어 text",
+            "\u{feff}bom\u{200b}zero-width",
+        ];
+        for s in samples {
+            let total = s.chars().count();
+            for max in 0..=total + 2 {
+                let c = cut(s, max);
+                assert!(s.starts_with(c), "{s:?} / {max}");
+                assert!(c.chars().count() <= max, "{s:?} / {max}");
+                let t = truncate_with(s, 1, max);
+                assert!(t.starts_with(c), "{s:?} / {max}");
+            }
+        }
+    }
+
+    fn numbered(n: usize) -> String {
+        (0..n)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_strategy_tail_keeps_last_lines() {
+        let result = truncate_by(&numbered(10), 3, 100, Strategy::Tail);
+        assert!(result.starts_with("[cx] … 10 lines total, showing last 3"));
+        assert!(result.ends_with("line 7\nline 8\nline 9"));
+        assert!(!result.contains("line 6"));
+    }
+
+    #[test]
+    fn test_strategy_head_tail_elides_middle() {
+        let result = truncate_by(&numbered(100), 5, 100, Strategy::HeadTail);
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "line 0",
+                "line 1",
+                "[cx] … 95 lines omitted (100 total)",
+                "line 97",
+                "line 98",
+                "line 99"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strategy_errors_keeps_context() {
+        let mut lines: Vec<String> = (0..50).map(|i| format!("step {i}")).collect();
+        lines[30] = "error: linker failed".into();
+        let result = truncate_by(&lines.join("\n"), 10, 100, Strategy::Errors);
+        assert!(
+            result.starts_with("  …\nstep 28\nstep 29\nerror: linker failed\nstep 31\nstep 32")
+        );
+        assert!(!result.contains("step 27"));
+        assert!(!result.contains("step 33"));
+        assert!(result.contains("50 lines total, showing 5 around errors / warnings"));
+    }
+
+    #[test]
+    fn test_strategy_errors_without_matches_falls_back_to_head_tail() {
+        let result = truncate_by(&numbered(20), 4, 100, Strategy::Errors);
+        assert!(result.contains("line 0"));
+        assert!(result.contains("line 19"));
+        assert!(result.contains("lines omitted"));
+    }
+
+    #[test]
+    fn test_strategy_irrelevant_under_limit() {
+        for strategy in [
+            Strategy::Head,
+            Strategy::Tail,
+            Strategy::HeadTail,
+            Strategy::Errors,
+        ] {
+            assert_eq!(truncate_by("a\nb", 5, 100, strategy), "a\nb");
+        }
+    }
+
+    #[test]
+    fn test_truncate_uses_ctx_strategy() {
+        let ctx = CompressCtx {
+            max_lines: 2,
+            truncate: Some(Strategy::Tail),
+            ..CompressCtx::default()
+        };
+        assert!(truncate(&numbered(5), &ctx).ends_with("line 3\nline 4"));
+    }
+
     #[test]
     fn test_truncate_exact_limit_no_footer() {
         let raw = (0..150)
             .map(|i| format!("L{i}"))
             .collect::<Vec<_>>()
             .join("\n");
-        let result = truncate(&raw);
+        let result = truncate(&raw, &ctx());
         assert!(!result.contains("lines total"));
     }
 
+    #[test]
+    fn test_truncate_honors_ctx_limits() {
+        let raw = (0..20)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let ctx = CompressCtx {
+            max_lines: 5,
+            ..CompressCtx::default()
+        };
+        let result = truncate(&raw, &ctx);
+        assert!(result.contains("20 lines total, showing first 5"));
+        assert!(!result.contains("line 5"));
+    }
+
     #[test]
     fn test_filter_and_truncate() {
         let raw = "error: bad\ninfo: ok\nerror: worse\ninfo: fine";
-        let result = filter_and_truncate(raw, |l| l.starts_with("error"));
+        let result = filter_and_truncate(raw, &ctx(), |l| l.starts_with("error"));
         assert!(result.contains("error: bad"));
         assert!(result.contains("error: worse"));
         assert!(!result.contains("info:"));
@@ -152,14 +427,14 @@ mod tests {
     #[test]
     fn test_filter_and_truncate_nothing_kept() {
         let raw = "info: ok\ninfo: fine";
-        let result = filter_and_truncate(raw, |l| l.starts_with("error"));
+        let result = filter_and_truncate(raw, &ctx(), |l| l.starts_with("error"));
         assert_eq!(result, "");
     }
 
     #[test]
     fn test_dedup_lines_no_dupes() {
         let raw = "a\nb\nc";
-        let result = dedup_lines(raw);
+        let result = dedup_lines(raw, &ctx());
         assert!(result.contains("a"));
         assert!(result.contains("b"));
         assert!(result.contains("c"));
@@ -169,7 +444,7 @@ mod tests {
     #[test]
     fn test_dedup_lines_consecutive_dupes() {
         let raw = "log entry\nlog entry\nlog entry\nother\nother";
-        let result = dedup_lines(raw);
+        let result = dedup_lines(raw, &ctx());
         assert!(result.contains("log entry  (×3)"));
         assert!(result.contains("other  (×2)"));
     }
@@ -177,20 +452,20 @@ mod tests {
     #[test]
     fn test_dedup_lines_single_line() {
         let raw = "only line";
-        let result = dedup_lines(raw);
+        let result = dedup_lines(raw, &ctx());
         assert_eq!(result, "only line");
     }
 
     #[test]
     fn test_dedup_lines_empty() {
-        let result = dedup_lines("");
+        let result = dedup_lines("", &ctx());
         assert_eq!(result, "");
     }
 
     #[test]
     fn test_dedup_lines_non_consecutive_not_merged() {
         let raw = "a\nb\na";
-        let result = dedup_lines(raw);
+        let result = dedup_lines(raw, &ctx());
         assert!(!result.contains("×"));
     }
 }
@@ -1,4 +1,9 @@
+use crate::compress::CompressCtx;
+use crate::compress::rules::RuleSpec;
+use crate::compress::truncate::Strategy;
+use crate::policy::{Action, PolicyRule};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 /// Global + per-project configuration.
@@ -9,22 +14,111 @@ pub struct Config {
     pub max_lines: usize,
     /// Maximum characters per line.
     pub max_line_len: usize,
+    /// Maximum entries per listing (branches, tags, lints, containers, …).
+    pub max_items: usize,
+    /// Maximum entries in package listings.
+    pub max_packages: usize,
+    /// Maximum hits kept per group (grep matches per file, build warnings, …).
+    pub max_hits: usize,
+    /// Maximum lines kept per detail block (test failures, stash list, …).
+    pub max_block_lines: usize,
+    /// Shrink output to about this many tokens, dropping low-priority sections first.
+    pub token_budget: Option<usize>,
+    /// Lines kept by plain truncation: head, tail, head-tail or errors.
+    /// Unset, `cx run` keeps head + tail and other tools keep the head.
+    pub truncate: Option<Strategy>,
+    /// Output format: compressed text or a JSON summary.
+    pub format: OutputFormat,
     /// Show timing footer on each command.
     pub show_footer: bool,
+    /// Kill commands still running after this many seconds.
+    pub timeout_secs: Option<u64>,
+    /// Bytes of stdout / stderr retained per run; the middle of larger outputs is dropped.
+    pub max_output_bytes: usize,
+    /// Runs kept in the raw output archive (`cx last`, `cx show`); 0 disables it.
+    pub archive_size: usize,
+    /// Replay an identical command's result for this many seconds while its
+    /// inputs are unchanged; `None` disables the cache.
+    pub cache_ttl_secs: Option<u64>,
+    /// Print only what changed since the previous run of the same command.
+    pub delta: bool,
+    /// Log each run's raw and compressed sizes for `cx stats`.
+    pub stats: bool,
     /// Directories to skip in `cx ls`.
     pub ls_skip: Vec<String>,
     /// Max depth for `cx ls`.
     pub ls_max_depth: usize,
     /// Max entries for `cx ls`.
     pub ls_max_entries: usize,
+    /// `cx git diff` shows compact hunks rather than a `--stat` summary.
+    pub git_diff_patch: bool,
+    /// Per-tool limit overrides, keyed by tool name (`git`, `cargo`, …).
+    pub tools: BTreeMap<String, ToolLimits>,
+    /// User-defined compressors (`[[compressor]]`), used by `cx run`; first match wins.
+    #[serde(rename = "compressor")]
+    pub compressors: Vec<RuleSpec>,
+    /// Safety rules (`[[policy]]`), tried before the built-in ones; first match wins.
+    pub policy: Vec<PolicyRule>,
+}
+
+/// How `cx` prints results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Compressed, human-readable text.
+    #[default]
+    Text,
+    /// One JSON object per run: typed summary + exit code + timing.
+    Json,
+}
+
+/// Limit overrides for a single tool (`[tools.<name>]` in TOML).
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ToolLimits {
+    pub max_lines: Option<usize>,
+    pub max_line_len: Option<usize>,
+    pub max_items: Option<usize>,
+    pub max_packages: Option<usize>,
+    pub max_hits: Option<usize>,
+    pub max_block_lines: Option<usize>,
+    pub token_budget: Option<usize>,
+    pub truncate: Option<Strategy>,
+}
+
+impl ToolLimits {
+    /// Apply the limits `other` sets on top of these, field by field.
+    fn merge(&mut self, other: ToolLimits) {
+        self.max_lines = other.max_lines.or(self.max_lines);
+        self.max_line_len = other.max_line_len.or(self.max_line_len);
+        self.max_items = other.max_items.or(self.max_items);
+        self.max_packages = other.max_packages.or(self.max_packages);
+        self.max_hits = other.max_hits.or(self.max_hits);
+        self.max_block_lines = other.max_block_lines.or(self.max_block_lines);
+        self.token_budget = other.token_budget.or(self.token_budget);
+        self.truncate = other.truncate.or(self.truncate);
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
+        let limits = CompressCtx::default();
         Self {
-            max_lines: 150,
-            max_line_len: 300,
+            max_lines: limits.max_lines,
+            max_line_len: limits.max_line_len,
+            max_items: limits.max_items,
+            max_packages: limits.max_packages,
+            max_hits: limits.max_hits,
+            max_block_lines: limits.max_block_lines,
+            token_budget: limits.token_budget,
+            truncate: limits.truncate,
+            format: OutputFormat::Text,
             show_footer: true,
+            timeout_secs: None,
+            max_output_bytes: 16 * 1024 * 1024,
+            archive_size: 20,
+            cache_ttl_secs: None,
+            delta: false,
+            stats: true,
             ls_skip: vec![
                 "target".into(),
                 "node_modules".into(),
@@ -41,6 +135,10 @@ impl Default for Config {
             ],
             ls_max_depth: 4,
             ls_max_entries: 200,
+            git_diff_patch: false,
+            tools: BTreeMap::new(),
+            compressors: Vec::new(),
+            policy: Vec::new(),
         }
     }
 }
@@ -65,6 +163,30 @@ impl Config {
         config
     }
 
+    /// Resolve compression limits for a tool: `[tools.<name>]` > global keys.
+    pub fn compress_ctx(&self, tool: &str) -> CompressCtx {
+        let o = self.tools.get(tool).cloned().unwrap_or_default();
+        CompressCtx {
+            max_lines: o.max_lines.unwrap_or(self.max_lines),
+            max_line_len: o.max_line_len.unwrap_or(self.max_line_len),
+            max_items: o.max_items.unwrap_or(self.max_items),
+            max_packages: o.max_packages.unwrap_or(self.max_packages),
+            max_hits: o.max_hits.unwrap_or(self.max_hits),
+            max_block_lines: o.max_block_lines.unwrap_or(self.max_block_lines),
+            token_budget: o.token_budget.or(self.token_budget),
+            truncate: o.truncate.or(self.truncate),
+            exit_code: 0,
+        }
+    }
+
+    /// Apply a `--budget` flag: it wins over global and per-tool config.
+    pub fn set_token_budget(&mut self, budget: usize) {
+        self.token_budget = Some(budget);
+        for limits in self.tools.values_mut() {
+            limits.token_budget = None;
+        }
+    }
+
     /// Generate a default config file content.
     pub fn default_toml() -> &'static str {
         r#"# cx-proxy configuration
@@ -74,9 +196,51 @@ impl Config {
 max_lines = 150
 max_line_len = 300
 
+# Per-section caps used by compressors
+max_items = 30        # branches, tags, lints, containers, …
+max_packages = 50     # pip list / freeze
+max_hits = 10         # grep hits per file, build warnings
+max_block_lines = 20  # lines per test failure, patched file, …
+
+# Token budget: shrink output to ~N estimated tokens, dropping
+# passing tests / progress, then warnings, before errors (same as --budget)
+# token_budget = 2000
+
+# Lines kept when output is over max_lines: "head", "tail", "head-tail"
+# (elide the middle) or "errors" (error / warning / failure lines + context).
+# Unset: `cx run` keeps head + tail, other fallbacks keep the head.
+# truncate = "head-tail"
+
+# Output format: "text" or "json" (same as --format)
+format = "text"
+
 # Show timing footer after each command
 show_footer = true
 
+# Kill a command (and its child processes) after N seconds and show
+# its partial output (same as --timeout)
+# timeout_secs = 600
+
+# Bytes of stdout / stderr kept per run; beyond that, the middle is dropped
+max_output_bytes = 16777216
+
+# Keep the raw output of the last N runs in ~/.cache/cx/runs
+# (`cx last --raw`, `cx show <id>`); 0 disables the archive
+archive_size = 20
+
+# Answer a repeated read-only command (git status / diff / log, cargo check /
+# clippy / build, ls) from its last result while the files it depends on are
+# unchanged, for up to N seconds (bypass with --no-cache)
+# cache_ttl_secs = 300
+
+# Print only the diagnostics, failing tests or status entries that are new
+# or resolved since the previous run of the same command (same as --delta)
+delta = false
+
+# Append each run's raw / compressed size, duration and exit code to
+# ~/.cache/cx/stats.jsonl, reported by `cx stats`
+stats = true
+
 # Directories to skip in `cx ls`
 ls_skip = [
     "target", "node_modules", ".git", "__pycache__",
@@ -87,6 +251,39 @@ ls_skip = [
 # Tree listing limits
 ls_max_depth = 4
 ls_max_entries = 200
+
+# `cx git diff`: compact hunks (changed lines, 1 line of context, per-file
+# budget) instead of a --stat summary (same as `cx git diff --patch`)
+git_diff_patch = false
+
+# Per-tool overrides (git, cargo, python, node, go, make, docker, grep, run)
+# [tools.cargo]
+# max_items = 60
+# [tools.run]
+# truncate = "errors"
+
+# Teach `cx run` about other commands (first matching rule wins)
+# [[compressor]]
+# name = "terraform"
+# program = "terraform"
+# subcommand = "plan"
+# args = ["-no-color"]
+# drop = ['^\s*$', 'Refreshing state']
+# summary = [{ pattern = 'Plan: (\d+) to add, (\d+) to change, (\d+) to destroy', template = "plan: +$1 ~$2 -$3" }]
+# section = [{ name = "destroy", match = 'will be destroyed', max = 5 }]
+
+# Safety rules, tried before the built-in ones (git reset --hard, push --force,
+# docker system prune, rm -rf, …); the first match wins. "confirm" blocks unless
+# --allow-destructive is given, "deny" always blocks, "allow" exempts a command.
+# [[policy]]
+# tool = "git"             # or "*" for every tool
+# args = '^push\b.*--force\b'
+# action = "allow"
+# [[policy]]
+# tool = "run"
+# args = '^terraform\s+destroy\b'
+# action = "deny"
+# reason = "destroys infrastructure"
 "#
     }
 }
@@ -96,29 +293,92 @@ ls_max_entries = 200
 struct PartialConfig {
     max_lines: Option<usize>,
     max_line_len: Option<usize>,
+    max_items: Option<usize>,
+    max_packages: Option<usize>,
+    max_hits: Option<usize>,
+    max_block_lines: Option<usize>,
+    token_budget: Option<usize>,
+    truncate: Option<Strategy>,
+    format: Option<OutputFormat>,
     show_footer: Option<bool>,
+    timeout_secs: Option<u64>,
+    max_output_bytes: Option<usize>,
+    archive_size: Option<usize>,
+    cache_ttl_secs: Option<u64>,
+    delta: Option<bool>,
+    stats: Option<bool>,
     ls_skip: Option<Vec<String>>,
     ls_max_depth: Option<usize>,
     ls_max_entries: Option<usize>,
+    git_diff_patch: Option<bool>,
+    tools: Option<BTreeMap<String, ToolLimits>>,
+    compressor: Option<Vec<RuleSpec>>,
+    policy: Option<Vec<PolicyRule>>,
 }
 
-fn global_config_path() -> Option<PathBuf> {
+pub fn global_config_path() -> Option<PathBuf> {
     dirs::config_dir().map(|d| d.join("cx").join("config.toml"))
 }
 
+/// Settings from a config file; `None` when it is missing. A file that fails
+/// to parse is skipped with a warning on stderr: the defaults and built-in
+/// policy rules still apply.
 fn load_file(path: &Path) -> Option<PartialConfig> {
     let content = std::fs::read_to_string(path).ok()?;
-    toml::from_str(&content).ok()
+    match toml::from_str(&content) {
+        Ok(partial) => Some(partial),
+        Err(e) => {
+            eprintln!(
+                "[cx] warning: ignoring {}: {}",
+                path.display(),
+                e.to_string().trim_end()
+            );
+            None
+        }
+    }
 }
 
 fn merge(base: Config, partial: PartialConfig) -> Config {
+    let mut tools = base.tools;
+    for (name, limits) in partial.tools.unwrap_or_default() {
+        tools.entry(name).or_default().merge(limits);
+    }
+    // Rules from the file merged last (the project) are tried first.
+    let mut compressors = partial.compressor.unwrap_or_default();
+    compressors.extend(base.compressors);
+    // Except `deny` rules from earlier files: a project config, which an agent
+    // can write, must not be able to allow what the user's global one denies.
+    let (mut policy, allowable): (Vec<_>, Vec<_>) = base
+        .policy
+        .into_iter()
+        .partition(|rule| rule.action == Action::Deny);
+    policy.extend(partial.policy.unwrap_or_default());
+    policy.extend(allowable);
+
     Config {
         max_lines: partial.max_lines.unwrap_or(base.max_lines),
         max_line_len: partial.max_line_len.unwrap_or(base.max_line_len),
+        max_items: partial.max_items.unwrap_or(base.max_items),
+        max_packages: partial.max_packages.unwrap_or(base.max_packages),
+        max_hits: partial.max_hits.unwrap_or(base.max_hits),
+        max_block_lines: partial.max_block_lines.unwrap_or(base.max_block_lines),
+        token_budget: partial.token_budget.or(base.token_budget),
+        truncate: partial.truncate.or(base.truncate),
+        format: partial.format.unwrap_or(base.format),
         show_footer: partial.show_footer.unwrap_or(base.show_footer),
+        timeout_secs: partial.timeout_secs.or(base.timeout_secs),
+        max_output_bytes: partial.max_output_bytes.unwrap_or(base.max_output_bytes),
+        archive_size: partial.archive_size.unwrap_or(base.archive_size),
+        cache_ttl_secs: partial.cache_ttl_secs.or(base.cache_ttl_secs),
+        delta: partial.delta.unwrap_or(base.delta),
+        stats: partial.stats.unwrap_or(base.stats),
         ls_skip: partial.ls_skip.unwrap_or(base.ls_skip),
         ls_max_depth: partial.ls_max_depth.unwrap_or(base.ls_max_depth),
         ls_max_entries: partial.ls_max_entries.unwrap_or(base.ls_max_entries),
+        git_diff_patch: partial.git_diff_patch.unwrap_or(base.git_diff_patch),
+        tools,
+        compressors,
+        policy,
     }
 }
 
@@ -196,15 +456,33 @@ mod tests {
         let partial = PartialConfig {
             max_lines: Some(50),
             max_line_len: None,
+            max_items: None,
+            max_packages: None,
+            max_hits: None,
+            max_block_lines: None,
+            token_budget: None,
+            truncate: None,
+            format: None,
             show_footer: Some(false),
+            timeout_secs: None,
+            max_output_bytes: None,
+            archive_size: None,
+            cache_ttl_secs: None,
+            delta: None,
+            stats: None,
             ls_skip: None,
             ls_max_depth: None,
             ls_max_entries: None,
+            git_diff_patch: None,
+            tools: None,
+            compressor: None,
+            policy: None,
         };
         let merged = merge(base, partial);
         assert_eq!(merged.max_lines, 50);
         assert_eq!(merged.max_line_len, 300); // kept default
         assert!(!merged.show_footer);
+        assert_eq!(merged.archive_size, 20);
     }
 
     #[test]
@@ -219,6 +497,146 @@ show_footer = false
         assert!(partial.max_line_len.is_none());
     }
 
+    #[test]
+    fn test_compress_ctx_uses_globals() {
+        let config = Config {
+            max_lines: 40,
+            max_items: 5,
+            ..Config::default()
+        };
+        let ctx = config.compress_ctx("git");
+        assert_eq!(ctx.max_lines, 40);
+        assert_eq!(ctx.max_items, 5);
+        assert_eq!(ctx.max_line_len, 300);
+    }
+
+    #[test]
+    fn test_compress_ctx_tool_override() {
+        let toml_str = r#"
+max_items = 20
+
+[tools.cargo]
+max_items = 60
+max_hits = 3
+"#;
+        let partial: PartialConfig = toml::from_str(toml_str).unwrap();
+        let config = merge(Config::default(), partial);
+
+        let cargo = config.compress_ctx("cargo");
+        assert_eq!(cargo.max_items, 60);
+        assert_eq!(cargo.max_hits, 3);
+
+        let git = config.compress_ctx("git");
+        assert_eq!(git.max_items, 20);
+        assert_eq!(git.max_hits, 10);
+    }
+
+    #[test]
+    fn test_merge_tools_project_overrides_global() {
+        let global: PartialConfig = toml::from_str("[tools.git]\nmax_items = 5\n").unwrap();
+        let project: PartialConfig = toml::from_str("[tools.git]\nmax_items = 7\n").unwrap();
+        let config = merge(merge(Config::default(), global), project);
+        assert_eq!(config.compress_ctx("git").max_items, 7);
+    }
+
+    #[test]
+    fn test_merge_tools_per_field() {
+        let global: PartialConfig = toml::from_str(
+            "[tools.git]
+max_items = 5
+max_hits = 3
+",
+        )
+        .unwrap();
+        let project: PartialConfig = toml::from_str(
+            "[tools.git]
+max_items = 7
+[tools.cargo]
+max_lines = 40
+",
+        )
+        .unwrap();
+        let config = merge(merge(Config::default(), global), project);
+        let git = config.compress_ctx("git");
+        assert_eq!((git.max_items, git.max_hits), (7, 3));
+        assert_eq!(config.compress_ctx("cargo").max_lines, 40);
+    }
+
+    #[test]
+    fn test_token_budget_cli_wins_over_tool() {
+        let partial: PartialConfig =
+            toml::from_str("token_budget = 500\n[tools.cargo]\ntoken_budget = 900\n").unwrap();
+        let mut config = merge(Config::default(), partial);
+        assert_eq!(config.compress_ctx("git").token_budget, Some(500));
+        assert_eq!(config.compress_ctx("cargo").token_budget, Some(900));
+
+        config.set_token_budget(100);
+        assert_eq!(config.compress_ctx("cargo").token_budget, Some(100));
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        let partial: PartialConfig = toml::from_str("timeout_secs = 30\n").unwrap();
+        let config = merge(Config::default(), partial);
+        assert_eq!(config.timeout_secs, Some(30));
+        assert_eq!(Config::default().timeout_secs, None);
+    }
+
+    #[test]
+    fn test_truncate_strategy_global_and_per_tool() {
+        let partial: PartialConfig =
+            toml::from_str("truncate = \"tail\"\n[tools.run]\ntruncate = \"errors\"\n").unwrap();
+        let config = merge(Config::default(), partial);
+        assert_eq!(config.compress_ctx("git").truncate, Some(Strategy::Tail));
+        assert_eq!(config.compress_ctx("run").truncate, Some(Strategy::Errors));
+        assert_eq!(Config::default().compress_ctx("run").truncate, None);
+
+        let head_tail: PartialConfig = toml::from_str("truncate = \"head-tail\"\n").unwrap();
+        assert_eq!(head_tail.truncate, Some(Strategy::HeadTail));
+    }
+
+    #[test]
+    fn test_project_rules_tried_before_global() {
+        let global: PartialConfig =
+            toml::from_str("[[compressor]]\nname = \"g\"\nprogram = \"make\"\n").unwrap();
+        let project: PartialConfig =
+            toml::from_str("[[compressor]]\nname = \"p\"\nprogram = \"make\"\nkeep = ['error']\n")
+                .unwrap();
+        let config = merge(merge(Config::default(), global), project);
+        let names: Vec<&str> = config.compressors.iter().map(|r| r.label()).collect();
+        assert_eq!(names, ["p", "g"]);
+        assert_eq!(config.compressors[0].keep, ["error"]);
+    }
+
+    #[test]
+    fn test_global_deny_beats_project_allow() {
+        let global: PartialConfig = toml::from_str(
+            "[[policy]]\ntool = \"run\"\nargs = 'deploy'\naction = \"deny\"\n\
+             [[policy]]\ntool = \"git\"\nargs = 'push'\naction = \"confirm\"\n",
+        )
+        .unwrap();
+        let project: PartialConfig =
+            toml::from_str("[[policy]]\ntool = \"*\"\nargs = '.'\naction = \"allow\"\n").unwrap();
+        let config = merge(merge(Config::default(), global), project);
+        let actions: Vec<Action> = config.policy.iter().map(|r| r.action).collect();
+        assert_eq!(actions, [Action::Deny, Action::Allow, Action::Confirm]);
+    }
+
+    #[test]
+    fn test_unparsable_file_is_skipped() {
+        let path = std::env::temp_dir().join(format!("cx-bad-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "max_lines = \"many\"\n").unwrap();
+        assert!(load_file(&path).is_none());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        let partial: PartialConfig = toml::from_str("format = \"json\"\n").unwrap();
+        let config = merge(Config::default(), partial);
+        assert_eq!(config.format, OutputFormat::Json);
+    }
+
     #[test]
     fn test_default_toml_is_valid() {
         let toml_str = Config::default_toml();
@@ -0,0 +1,424 @@
+//! `--delta`: print only what changed since the previous run of the same
+//! command. The items of a run's summary (diagnostics, failing tests, status
+//! entries) are kept under the user cache dir and compared with the next
+//! run's, so an agent iterating on fixes sees `+2 errors, -5 warnings
+//! resolved, 35 unchanged` instead of the same 40 warnings again.
+
+use crate::compress::CompressCtx;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Status entry kinds of `git status`, in display order.
+const STATUS_KINDS: &[&str] = &["conflicted", "staged", "modified", "renamed", "untracked"];
+
+/// One comparable entry of a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    /// `error`, `warning`, `failing test`, `modified`, …
+    pub kind: String,
+    /// Identity across runs: what the entry says, not where (line numbers
+    /// shift as code is edited above it).
+    pub key: String,
+    /// How the entry is shown.
+    pub text: String,
+}
+
+/// The comparable entries of a run and the kind of summary they came from
+/// (`diagnostics`, `tests`, `status`): runs are only compared with a previous
+/// run of the same kind, so a test run that failed to build is not read as
+/// every failing test resolved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub summary: String,
+    pub items: Vec<Item>,
+}
+
+/// The entries of a run compared with the previous run's.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Delta {
+    pub new: Vec<Item>,
+    pub resolved: Vec<Item>,
+    pub unchanged: usize,
+}
+
+/// The comparable entries of a `summarize` result; `None` for summaries
+/// without any (plain `lines`, docker tables, …) and for test runs that
+/// report no results at all.
+pub fn items(summary: &Value) -> Option<Snapshot> {
+    let snapshot = |kind: &str, items: Vec<Item>| {
+        Some(Snapshot {
+            summary: kind.to_string(),
+            items,
+        })
+    };
+    // cargo build / check / clippy, and a cargo test that failed to build
+    if let (Some(errors), Some(warnings)) =
+        (summary["errors"].as_array(), summary["warnings"].as_array())
+    {
+        return snapshot(
+            "diagnostics",
+            errors.iter().chain(warnings).map(diagnostic).collect(),
+        );
+    }
+    // ruff, mypy
+    if let Some(diagnostics) = summary["diagnostics"].as_array() {
+        return snapshot("diagnostics", diagnostics.iter().map(diagnostic).collect());
+    }
+    // cargo test, pytest, go test, …
+    if let Some(failures) = summary["failures"].as_array() {
+        let ran = ["passed", "failed", "skipped", "errors"]
+            .iter()
+            .any(|count| summary[*count].as_u64().is_some_and(|n| n > 0));
+        if !ran && failures.is_empty() {
+            return None;
+        }
+        return snapshot("tests", failures.iter().map(failure).collect());
+    }
+    // git status
+    if summary["branch"].is_string() && summary["staged"].is_array() {
+        let items = STATUS_KINDS.iter().flat_map(|kind| {
+            summary[*kind]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(|entry| Item {
+                    kind: kind.to_string(),
+                    key: entry.to_string(),
+                    text: entry.to_string(),
+                })
+        });
+        return snapshot("status", items.collect());
+    }
+    None
+}
+
+/// `error[E0308] mismatched types (src/main.rs:12)`, keyed without the line.
+fn diagnostic(d: &Value) -> Item {
+    let level = d["level"].as_str().unwrap_or("error");
+    let code = d["code"]
+        .as_str()
+        .map(|c| format!("[{c}]"))
+        .unwrap_or_default();
+    let message = d["message"].as_str().unwrap_or_default();
+    let file = d["file"].as_str().unwrap_or_default();
+    let location = match (file, d["line"].as_u64()) {
+        ("", _) => String::new(),
+        (file, Some(line)) => format!(" ({file}:{line})"),
+        (file, None) => format!(" ({file})"),
+    };
+    Item {
+        kind: level.to_string(),
+        key: format!("{level}{code} {message} {file}"),
+        text: format!("{level}{code} {message}{location}"),
+    }
+}
+
+/// A failing test, keyed by name; the first line of its message is shown.
+fn failure(f: &Value) -> Item {
+    let name = f["name"].as_str().unwrap_or_default();
+    let text = match f["message"].as_str().and_then(|m| m.lines().next()) {
+        Some(message) => format!("{name}: {message}"),
+        None => name.to_string(),
+    };
+    Item {
+        kind: "failing test".into(),
+        key: name.to_string(),
+        text,
+    }
+}
+
+/// Compare two runs. Entries are matched by kind and key, counting
+/// duplicates, so the same warning in three places resolves one at a time.
+pub fn diff(previous: &[Item], current: &[Item]) -> Delta {
+    let mut remaining: HashMap<(&str, &str), usize> = HashMap::new();
+    for item in previous {
+        *remaining.entry((&item.kind, &item.key)).or_default() += 1;
+    }
+    let mut delta = Delta::default();
+    for item in current {
+        match remaining.get_mut(&(item.kind.as_str(), item.key.as_str())) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                delta.unchanged += 1;
+            }
+            _ => delta.new.push(item.clone()),
+        }
+    }
+    for item in previous {
+        if let Some(n) = remaining.get_mut(&(item.kind.as_str(), item.key.as_str()))
+            && *n > 0
+        {
+            *n -= 1;
+            delta.resolved.push(item.clone());
+        }
+    }
+    delta
+}
+
+impl Delta {
+    /// `[delta] +2 errors, -5 warnings resolved, 35 unchanged`, then the new
+    /// (`+`) and resolved (`-`) entries, capped at `max_items`.
+    pub fn render(&self, ctx: &CompressCtx) -> String {
+        let mut parts: Vec<String> = counts(&self.new)
+            .into_iter()
+            .map(|(kind, n)| format!("+{n} {}", plural(kind, n)))
+            .collect();
+        let resolved: Vec<String> = counts(&self.resolved)
+            .into_iter()
+            .map(|(kind, n)| format!("-{n} {}", plural(kind, n)))
+            .collect();
+        if !resolved.is_empty() {
+            parts.push(format!("{} resolved", resolved.join(", ")));
+        }
+        let mut out = if parts.is_empty() {
+            format!("[delta] no changes, {} unchanged\n", self.unchanged)
+        } else {
+            format!(
+                "[delta] {}, {} unchanged\n",
+                parts.join(", "),
+                self.unchanged
+            )
+        };
+
+        let entries: Vec<String> = self
+            .new
+            .iter()
+            .map(|item| format!("  + {}", item.text))
+            .chain(
+                self.resolved
+                    .iter()
+                    .map(|item| format!("  - {}", item.text)),
+            )
+            .collect();
+        for entry in entries.iter().take(ctx.max_items) {
+            out.push_str(entry);
+            out.push('\n');
+        }
+        if entries.len() > ctx.max_items {
+            out.push_str(&format!("  … +{} more\n", entries.len() - ctx.max_items));
+        }
+        out
+    }
+}
+
+/// Items per kind, in order of first appearance.
+fn counts(items: &[Item]) -> Vec<(&str, usize)> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for item in items {
+        match counts.iter_mut().find(|(kind, _)| *kind == item.kind) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((&item.kind, 1)),
+        }
+    }
+    counts
+}
+
+/// `errors`, `failing tests`; status kinds (`modified`, …) stay as they are.
+fn plural(kind: &str, n: usize) -> String {
+    match kind {
+        "error" | "warning" | "note" | "failing test" if n != 1 => format!("{kind}s"),
+        _ => kind.to_string(),
+    }
+}
+
+/// Directory of `<key>.json` item lists, one per command and directory.
+#[derive(Debug, Clone)]
+pub struct DeltaStore {
+    dir: PathBuf,
+}
+
+impl DeltaStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// The store under `~/.cache/cx/delta`, or `None` when delta mode is off.
+    pub fn open(enabled: bool) -> Option<Self> {
+        if !enabled {
+            return None;
+        }
+        Some(Self::new(dirs::cache_dir()?.join("cx").join("delta")))
+    }
+
+    /// Record this run's items for `command` and compare them with the
+    /// previous run's; `None` on the first run, when the summary has no
+    /// comparable items, or when the previous run's summary was of another
+    /// kind (tests, then a build failure).
+    pub fn compare(&self, command: &[&str], summary: &Value) -> Option<Delta> {
+        let current = items(summary)?;
+        let path = self.dir.join(format!("{:016x}.json", key(command)));
+        let previous: Option<Snapshot> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+        // Best effort, like the archive: the next run just shows everything.
+        let _ = self.store(&path, &current);
+        let previous = previous.filter(|p| p.summary == current.summary)?;
+        Some(diff(&previous.items, &current.items))
+    }
+
+    fn store(&self, path: &Path, snapshot: &Snapshot) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string(snapshot).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+/// The command and the directory it ran in.
+fn key(command: &[&str]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    command.hash(&mut hasher);
+    std::env::current_dir().ok().hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ctx() -> CompressCtx {
+        CompressCtx {
+            max_items: 3,
+            ..CompressCtx::default()
+        }
+    }
+
+    fn warning(message: &str, line: u32) -> Value {
+        json!({"level": "warning", "message": message, "file": "src/lib.rs", "line": line})
+    }
+
+    #[test]
+    fn test_diagnostics_match_across_line_shifts() {
+        let before = json!({"errors": [], "warnings": [warning("unused variable `x`", 4)]});
+        let after = json!({
+            "errors": [{"level": "error", "code": "E0308", "message": "mismatched types", "file": "src/main.rs", "line": 12}],
+            "warnings": [warning("unused variable `x`", 9)],
+        });
+        let delta = diff(
+            &items(&before).unwrap().items,
+            &items(&after).unwrap().items,
+        );
+        assert_eq!(delta.unchanged, 1);
+        assert_eq!(delta.resolved, vec![]);
+        assert_eq!(
+            delta.new[0].text,
+            "error[E0308] mismatched types (src/main.rs:12)"
+        );
+    }
+
+    #[test]
+    fn test_duplicates_resolve_one_at_a_time() {
+        let item = |kind: &str, key: &str| Item {
+            kind: kind.into(),
+            key: key.into(),
+            text: key.into(),
+        };
+        let previous = vec![
+            item("warning", "w"),
+            item("warning", "w"),
+            item("warning", "v"),
+        ];
+        let current = vec![item("warning", "w"), item("error", "e")];
+        let delta = diff(&previous, &current);
+        assert_eq!(delta.unchanged, 1);
+        assert_eq!(delta.new.len(), 1);
+        assert_eq!(delta.resolved.len(), 2);
+        assert_eq!(
+            delta.render(&ctx()),
+            "[delta] +1 error, -2 warnings resolved, 1 unchanged\n  + e\n  - w\n  - v\n"
+        );
+    }
+
+    #[test]
+    fn test_items_of_tests_and_status() {
+        let report = json!({"passed": 3, "failed": 1, "errors": 0, "failures": [
+            {"name": "tests/test_api.py::test_login", "message": "assert 401 == 200\nmore"},
+        ]});
+        let items = items(&report).unwrap().items;
+        assert_eq!(items[0].kind, "failing test");
+        assert_eq!(
+            items[0].text,
+            "tests/test_api.py::test_login: assert 401 == 200"
+        );
+
+        let status = json!({"branch": "main", "staged": ["a.rs (new)"], "modified": [], "untracked": ["b.txt"]});
+        let kinds: Vec<String> = super::items(&status)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|i| format!("{} {}", i.kind, i.text))
+            .collect();
+        assert_eq!(kinds, ["staged a.rs (new)", "untracked b.txt"]);
+
+        assert_eq!(super::items(&json!({"lines": ["x"]})), None);
+        let nothing_ran =
+            json!({"passed": 0, "failed": 0, "skipped": 0, "errors": 0, "failures": []});
+        assert_eq!(super::items(&nothing_ran), None);
+    }
+
+    #[test]
+    fn test_render_caps_entries_and_reports_no_changes() {
+        let current: Vec<Item> = (0..5)
+            .map(|i| Item {
+                kind: "modified".into(),
+                key: format!("f{i}"),
+                text: format!("f{i}"),
+            })
+            .collect();
+        let delta = diff(&[], &current);
+        assert!(
+            delta
+                .render(&ctx())
+                .starts_with("[delta] +5 modified, 0 unchanged\n  + f0\n")
+        );
+        assert!(delta.render(&ctx()).ends_with("  + f2\n  … +2 more\n"));
+        assert_eq!(
+            diff(&current, &current).render(&ctx()),
+            "[delta] no changes, 5 unchanged\n"
+        );
+    }
+
+    #[test]
+    fn test_store_compares_with_previous_run() {
+        let dir = std::env::temp_dir().join(format!("cx-delta-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = DeltaStore::new(dir.clone());
+        let command = ["cargo", "check"];
+        let run = |warnings: Vec<Value>| json!({"errors": [], "warnings": warnings});
+
+        assert_eq!(store.compare(&command, &run(vec![warning("a", 1)])), None);
+        let delta = store
+            .compare(&command, &run(vec![warning("a", 1), warning("b", 2)]))
+            .unwrap();
+        assert_eq!((delta.new.len(), delta.unchanged), (1, 1));
+        assert_eq!(store.compare(&["cargo", "clippy"], &run(vec![])), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_tests_then_build_failure_is_not_a_delta() {
+        let dir = std::env::temp_dir().join(format!("cx-delta-kinds-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = DeltaStore::new(dir.clone());
+        let command = ["cargo", "test"];
+        let tests = json!({"passed": 3, "failed": 1, "skipped": 0, "errors": 0, "failures": [{"name": "api::get"}]});
+        let build_failure = json!({
+            "errors": [{"level": "error", "code": "E0308", "message": "mismatched types", "file": "src/main.rs", "line": 12}],
+            "warnings": [],
+        });
+
+        assert_eq!(store.compare(&command, &tests), None);
+        assert_eq!(store.compare(&command, &build_failure), None);
+        let delta = store.compare(&command, &build_failure).unwrap();
+        assert_eq!((delta.new.len(), delta.unchanged), (0, 1));
+        assert_eq!(store.compare(&command, &tests), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
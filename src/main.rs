@@ -1,17 +1,37 @@
+mod archive;
+mod cache;
 mod cli;
 mod compress;
 mod config;
+mod delta;
+mod policy;
 mod runner;
+mod stats;
 mod tools;
 
 use clap::Parser;
 use cli::{Cli, Commands};
 use config::Config;
 use tools::Tool;
+use tools::make::Runner;
+use tools::node::PackageManager;
 
 fn main() {
-    let cli = Cli::parse();
-    let cfg = Config::load();
+    let mut cli = Cli::parse();
+    let allow_destructive = cli.take_allow_destructive();
+    let mut cfg = Config::load();
+    if let Some(format) = cli.format {
+        cfg.format = format;
+    }
+    if let Some(budget) = cli.budget {
+        cfg.set_token_budget(budget);
+    }
+    if let Some(timeout) = cli.timeout {
+        cfg.timeout_secs = Some(timeout);
+    }
+    if cli.delta {
+        cfg.delta = true;
+    }
 
     match cli.command {
         Commands::Info => {
@@ -21,9 +41,21 @@ fn main() {
             create_config(global);
         }
         command => {
+            if let Some((tool, args)) = command.proxied() {
+                // Checked as it will run, with `[[compressor]]` args injected.
+                let args = match tool {
+                    "run" => tools::generic::resolved_command(args, &cfg.compressors),
+                    _ => args.to_vec(),
+                };
+                if let Some(outcome) = policy::guard(&cfg.policy, tool, &args, allow_destructive) {
+                    println!("{}", outcome.render(cfg.format));
+                    std::process::exit(outcome.exit_code);
+                }
+            }
+
             let tool: Box<dyn Tool> = match command {
-                Commands::Git { args } => Box::new(tools::git::GitTool::new(args)),
-                Commands::Cargo { args } => Box::new(tools::cargo::CargoTool::new(args)),
+                Commands::Git { args } => Box::new(tools::git::GitTool::new(args, &cfg)),
+                Commands::Cargo { args } => Box::new(tools::cargo::CargoTool::new(args, &cfg)),
                 Commands::Ls { path } => Box::new(tools::fs::FsTool::new(
                     path.unwrap_or_else(|| ".".into()),
                     &cfg,
@@ -32,15 +64,67 @@ fn main() {
                     pattern,
                     path.unwrap_or_else(|| ".".into()),
                     rg,
+                    &cfg,
                 )),
-                Commands::Python { args } => Box::new(tools::python::PythonTool::new(args)),
-                Commands::Docker { args } => Box::new(tools::docker::DockerTool::new(args)),
-                Commands::Run { args } => Box::new(tools::generic::GenericTool::new(args)),
+                Commands::Python { args } => Box::new(tools::python::PythonTool::new(args, &cfg)),
+                Commands::Node { args } => Box::new(tools::node::NodeTool::new(None, args, &cfg)),
+                Commands::Npm { args } => Box::new(tools::node::NodeTool::new(
+                    Some(PackageManager::Npm),
+                    args,
+                    &cfg,
+                )),
+                Commands::Pnpm { args } => Box::new(tools::node::NodeTool::new(
+                    Some(PackageManager::Pnpm),
+                    args,
+                    &cfg,
+                )),
+                Commands::Yarn { args } => Box::new(tools::node::NodeTool::new(
+                    Some(PackageManager::Yarn),
+                    args,
+                    &cfg,
+                )),
+                Commands::Bun { args } => Box::new(tools::node::NodeTool::new(
+                    Some(PackageManager::Bun),
+                    args,
+                    &cfg,
+                )),
+                Commands::Go { args } => Box::new(tools::go::GoTool::new(args, &cfg)),
+                Commands::Make { args } => {
+                    Box::new(tools::make::MakeTool::new(Runner::Make, args, &cfg))
+                }
+                Commands::Just { args } => {
+                    Box::new(tools::make::MakeTool::new(Runner::Just, args, &cfg))
+                }
+                Commands::Task { args } => {
+                    Box::new(tools::make::MakeTool::new(Runner::Task, args, &cfg))
+                }
+                Commands::Docker { args } => Box::new(tools::docker::DockerTool::new(args, &cfg)),
+                Commands::Run { args } => Box::new(tools::generic::GenericTool::new(args, &cfg)),
+                Commands::Last { raw, lines, grep } => Box::new(tools::archive::ArchiveTool::new(
+                    None, raw, lines, grep, &cfg,
+                )),
+                Commands::Show { id, lines, grep } => Box::new(tools::archive::ArchiveTool::new(
+                    Some(id),
+                    true,
+                    lines,
+                    grep,
+                    &cfg,
+                )),
+                Commands::Stats { since, by } => {
+                    Box::new(tools::stats::StatsTool::new(since, by, &cfg))
+                }
                 Commands::Info | Commands::Init { .. } => unreachable!(),
             };
 
-            let output = tool.run();
-            println!("{output}");
+            // A replayed result would compare against a stale baseline.
+            let cache =
+                cache::Cache::open(cfg.cache_ttl_secs).filter(|_| !cli.no_cache && !cfg.delta);
+            let outcome = match cache {
+                Some(cache) => cache.run(tool.as_ref()),
+                None => tool.run(),
+            };
+            println!("{}", outcome.render(cfg.format));
+            std::process::exit(outcome.exit_code);
         }
     }
 }
@@ -57,10 +141,59 @@ fn print_info(cfg: &Config) {
     }
     println!("  max_lines: {}", cfg.max_lines);
     println!("  max_line_len: {}", cfg.max_line_len);
+    println!("  max_items: {}", cfg.max_items);
+    println!("  max_packages: {}", cfg.max_packages);
+    println!("  max_hits: {}", cfg.max_hits);
+    println!("  max_block_lines: {}", cfg.max_block_lines);
+    match cfg.token_budget {
+        Some(b) => println!("  token_budget: {b}"),
+        None => println!("  token_budget: (none)"),
+    }
+    match cfg.truncate {
+        Some(s) => println!("  truncate: {s:?}"),
+        None => println!("  truncate: (per tool default)"),
+    }
+    println!("  format: {:?}", cfg.format);
+    match cfg.timeout_secs {
+        Some(t) => println!("  timeout_secs: {t}"),
+        None => println!("  timeout_secs: (none)"),
+    }
+    println!("  max_output_bytes: {}", cfg.max_output_bytes);
     println!("  show_footer: {}", cfg.show_footer);
+    match cfg.cache_ttl_secs {
+        Some(t) => println!("  cache_ttl_secs: {t}"),
+        None => println!("  cache_ttl_secs: (disabled)"),
+    }
+    println!("  delta: {}", cfg.delta);
+    match stats::StatsLog::open(cfg.stats) {
+        Some(log) => println!("  stats: {}", log.path().display()),
+        None => println!("  stats: (disabled)"),
+    }
+    match crate::archive::Archive::open(cfg.archive_size) {
+        Some(archive) => println!(
+            "  archive: {} runs in {}",
+            cfg.archive_size,
+            archive.dir().display()
+        ),
+        None => println!("  archive: (disabled)"),
+    }
     println!("  ls_max_depth: {}", cfg.ls_max_depth);
     println!("  ls_max_entries: {}", cfg.ls_max_entries);
     println!("  ls_skip: {:?}", cfg.ls_skip);
+    println!("  git_diff_patch: {}", cfg.git_diff_patch);
+    println!("  policy: {} rules (+ built-in)", cfg.policy.len());
+    for tool in cfg.tools.keys() {
+        let ctx = cfg.compress_ctx(tool);
+        println!(
+            "  [{tool}] max_lines={} max_line_len={} max_items={} max_packages={} max_hits={} max_block_lines={}",
+            ctx.max_lines,
+            ctx.max_line_len,
+            ctx.max_items,
+            ctx.max_packages,
+            ctx.max_hits,
+            ctx.max_block_lines
+        );
+    }
 }
 
 fn create_config(global: bool) {
@@ -0,0 +1,411 @@
+//! Safety policy: commands `cx` refuses to run on an agent's behalf.
+//!
+//! A rule matches a tool and a regex over its arguments (joined by spaces).
+//! `confirm` rules block until the command is repeated with
+//! `--allow-destructive`, `deny` rules always block, and `allow` rules exempt
+//! a command from the rules after them. The first matching rule wins:
+//! `[[policy]]` tables from the config (project, then global, but global
+//! `deny` rules before the project's), then the built-in rules.
+//!
+//! ```toml
+//! [[policy]]
+//! tool = "git"
+//! args = '^push\b.*--force\b'
+//! action = "allow"
+//! ```
+//!
+//! `cx run` commands are also checked against the rules of the program they
+//! run, so `cx run git reset --hard` is guarded like `cx git reset --hard`.
+//! Rules see commands without what comes before the part that matters: git's
+//! global options (`git -C . reset --hard` is `reset --hard`), `env` / `sudo`
+//! wrappers and the program's directory (`cx run env /bin/rm -rf /` is
+//! `rm -rf /`).
+
+use crate::tools::Outcome;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Exit code for commands the policy blocks (sysexits `EX_NOPERM`).
+pub const EXIT_BLOCKED: i32 = 77;
+
+/// What happens to a command a rule matches.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Run it, whatever the rules after this one say.
+    Allow,
+    /// Block it unless `--allow-destructive` is given.
+    Confirm,
+    /// Always block it.
+    Deny,
+}
+
+/// A `[[policy]]` table as written in TOML.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicyRule {
+    /// Tool the rule applies to (`git`, `docker`, `run`, …), or `*` for all.
+    pub tool: String,
+    /// Regex matched against the arguments, joined by spaces.
+    pub args: String,
+    pub action: Action,
+    /// Why the command is guarded, shown when it is blocked.
+    pub reason: Option<String>,
+}
+
+/// Git global options that take a separate value (`-C <dir>`, `-c <k=v>`, …).
+const GIT_VALUE_OPTIONS: &[&str] = &[
+    "-C",
+    "-c",
+    "--git-dir",
+    "--work-tree",
+    "--namespace",
+    "--config-env",
+    "--super-prefix",
+];
+
+/// `sudo` options that take a separate value (`-u <user>`, …).
+const SUDO_VALUE_OPTIONS: &[&str] = &["-u", "-g", "-h", "-p", "-C", "-D", "-r", "-t", "-U"];
+
+/// Built-in rules, tried after the configured ones: tool, args, action, reason.
+const BUILTIN: &[(&str, &str, Action, &str)] = &[
+    (
+        "git",
+        r"^reset\b.*\s--hard\b",
+        Action::Confirm,
+        "discards uncommitted changes",
+    ),
+    (
+        "git",
+        r"^push\b.*\s(-f|--force)(\s|$)",
+        Action::Confirm,
+        "overwrites the remote branch",
+    ),
+    (
+        "git",
+        r"^push\b.*\s(-d|--delete)(\s|$)",
+        Action::Confirm,
+        "deletes a remote branch",
+    ),
+    (
+        "git",
+        r"^clean\b.*\s(-\w*f\w*|--force)(\s|$)",
+        Action::Confirm,
+        "deletes untracked files",
+    ),
+    (
+        "git",
+        r"^branch\b.*\s-\w*D\w*(\s|$)",
+        Action::Confirm,
+        "deletes a branch even if unmerged",
+    ),
+    (
+        "git",
+        r"^(checkout|restore)\b.*\s\.(\s|$)",
+        Action::Confirm,
+        "discards changes to every file",
+    ),
+    (
+        "git",
+        r"^stash\s+(clear|drop)\b",
+        Action::Confirm,
+        "deletes stashed changes",
+    ),
+    (
+        "docker",
+        r"^(system|volume|image|container|network|builder)\s+prune\b",
+        Action::Confirm,
+        "deletes docker resources",
+    ),
+    (
+        "docker",
+        r"^volume\s+rm\b",
+        Action::Confirm,
+        "deletes volume data",
+    ),
+    (
+        "run",
+        r"^(sudo\s+)?rm\s(.*\s)?(/|/\*|~/?|\$HOME/?|/root/?|/home/[^/\s]+/?)(\s|$)",
+        Action::Deny,
+        "deletes the root or home directory",
+    ),
+    (
+        "run",
+        r"^(sudo\s+)?rm\s(.*\s)?(-\w*[rR]\w*|--recursive)(\s|$)",
+        Action::Confirm,
+        "deletes files recursively",
+    ),
+    (
+        "run",
+        r"^(sudo\s+)?(mkfs(\.\w+)?\s|dd\s.*\bof=/dev/)",
+        Action::Deny,
+        "overwrites a disk",
+    ),
+];
+
+/// Compiled rules, configured ones first.
+pub struct Policy {
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    tool: String,
+    args: Regex,
+    action: Action,
+    reason: Option<String>,
+}
+
+impl Policy {
+    /// Compile the configured rules followed by the built-ins; the error
+    /// names the bad pattern.
+    pub fn new(configured: &[PolicyRule]) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for rule in configured {
+            rules.push(Rule {
+                tool: rule.tool.clone(),
+                args: Regex::new(&rule.args)
+                    .map_err(|e| format!("policy rule for `{}`: {e}", rule.tool))?,
+                action: rule.action,
+                reason: rule.reason.clone(),
+            });
+        }
+        for (tool, args, action, reason) in BUILTIN {
+            rules.push(Rule {
+                tool: tool.to_string(),
+                args: Regex::new(args).expect("built-in policy patterns are valid"),
+                action: *action,
+                reason: Some(reason.to_string()),
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// The first rule matching `tool args…`, with its action and reason.
+    fn matching(&self, tool: &str, args: &[String]) -> Option<(Action, Option<&str>)> {
+        let mut commands = vec![(tool.to_string(), subcommand_args(tool, args).join(" "))];
+        // `cx run git …` is also held to the rules for `git`.
+        if tool == "run" {
+            let command = unwrap_command(args);
+            commands[0].1 = command.join(" ");
+            if let Some((program, rest)) = command.split_first() {
+                commands.push((program.clone(), subcommand_args(program, rest).join(" ")));
+            }
+        }
+        self.rules.iter().find_map(|rule| {
+            commands
+                .iter()
+                .any(|(tool, args)| {
+                    (rule.tool == "*" || rule.tool == *tool) && rule.args.is_match(args)
+                })
+                .then_some((rule.action, rule.reason.as_deref()))
+        })
+    }
+
+    /// Why `tool args…` must not run, as the message to print; `None` when
+    /// it may.
+    pub fn check(&self, tool: &str, args: &[String], allow_destructive: bool) -> Option<String> {
+        let (action, reason) = self.matching(tool, args)?;
+        let command = match tool {
+            "run" => args.join(" "),
+            _ => format!("{tool} {}", args.join(" ")),
+        };
+        let reason = reason.unwrap_or("matches a policy rule");
+        match action {
+            Action::Allow => None,
+            Action::Confirm if allow_destructive => None,
+            Action::Confirm => Some(format!(
+                "[cx] blocked: {command} ({reason}); add --allow-destructive to run it"
+            )),
+            Action::Deny => Some(format!(
+                "[cx] blocked: {command} ({reason}); denied by policy"
+            )),
+        }
+    }
+}
+
+/// The arguments from the subcommand on: git's global options are dropped.
+fn subcommand_args<'a>(tool: &str, args: &'a [String]) -> &'a [String] {
+    if tool != "git" {
+        return args;
+    }
+    let mut rest = args;
+    while let Some((first, tail)) = rest.split_first()
+        && first.starts_with('-')
+    {
+        let takes_value = GIT_VALUE_OPTIONS.contains(&first.as_str());
+        rest = if takes_value {
+            tail.get(1..).unwrap_or(&[])
+        } else {
+            tail
+        };
+    }
+    rest
+}
+
+/// The command `env` / `sudo` end up running, with the program reduced to
+/// its file name: `/usr/bin/env FOO=1 sudo -u root /bin/rm -rf /` → `rm -rf /`.
+fn unwrap_command(args: &[String]) -> Vec<String> {
+    let mut rest = args;
+    while let Some((program, tail)) = rest.split_first() {
+        let name = std::path::Path::new(program)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(program);
+        let value_options: &[&str] = match name {
+            "env" => &["-u", "-C", "-S"],
+            "sudo" => SUDO_VALUE_OPTIONS,
+            _ => {
+                let mut command = vec![name.to_string()];
+                command.extend(tail.iter().cloned());
+                return command;
+            }
+        };
+        rest = tail;
+        while let Some((first, tail)) = rest.split_first() {
+            if value_options.contains(&first.as_str()) {
+                rest = tail.get(1..).unwrap_or(&[]);
+            } else if first.starts_with('-') || (name == "env" && first.contains('=')) {
+                rest = tail;
+            } else {
+                break;
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Check a command against the policy before it runs: `None` to go ahead,
+/// or the outcome to report instead.
+pub fn guard(
+    configured: &[PolicyRule],
+    tool: &str,
+    args: &[String],
+    allow_destructive: bool,
+) -> Option<Outcome> {
+    match Policy::new(configured) {
+        Ok(policy) => policy
+            .check(tool, args, allow_destructive)
+            .map(|message| Outcome::new(message, EXIT_BLOCKED)),
+        Err(e) => Some(Outcome::usage(format!("[cx] error: {e}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    fn check(tool: &str, command: &str) -> Option<String> {
+        Policy::new(&[]).unwrap().check(tool, &args(command), false)
+    }
+
+    #[test]
+    fn test_builtin_rules_block_destructive_commands() {
+        for (tool, command) in [
+            ("git", "reset --hard HEAD~1"),
+            ("git", "push --force origin main"),
+            ("git", "push origin main -f"),
+            ("git", "clean -fdx"),
+            ("git", "branch -D feature"),
+            ("git", "checkout -- ."),
+            ("docker", "system prune -a"),
+            ("run", "rm -rf build"),
+            ("run", "git reset --hard"),
+            ("git", "-C . reset --hard"),
+            ("git", "-c x=y push --force"),
+            ("git", "--no-pager --git-dir=.git clean -fd"),
+            ("run", "git -C /tmp/repo branch -D feature"),
+            ("run", "/bin/rm -rf build"),
+            ("run", "sudo -u root /usr/bin/rm -r build"),
+        ] {
+            assert!(check(tool, command).is_some(), "{tool} {command}");
+        }
+    }
+
+    #[test]
+    fn test_builtin_rules_let_safe_commands_through() {
+        for (tool, command) in [
+            ("git", "reset HEAD~1"),
+            ("git", "push --force-with-lease origin main"),
+            ("git", "clean -n"),
+            ("git", "branch -d feature"),
+            ("git", "checkout main"),
+            ("git", "log --format=%H"),
+            ("docker", "ps -a"),
+            ("run", "rm notes.txt"),
+            ("run", "ls -r"),
+        ] {
+            assert_eq!(check(tool, command), None, "{tool} {command}");
+        }
+    }
+
+    #[test]
+    fn test_confirm_vs_deny() {
+        let policy = Policy::new(&[]).unwrap();
+        let message = policy.check("git", &args("reset --hard"), false).unwrap();
+        assert_eq!(
+            message,
+            "[cx] blocked: git reset --hard (discards uncommitted changes); add --allow-destructive to run it"
+        );
+        assert_eq!(policy.check("git", &args("reset --hard"), true), None);
+
+        for command in [
+            "rm -rf /",
+            "/bin/rm -rf ~",
+            "/bin/rm -rf /home/dev/",
+            "env rm -rf /",
+            "/usr/bin/env -u HOME FOO=1 sudo rm -rf /",
+        ] {
+            let denied = policy.check("run", &args(command), true).unwrap();
+            assert!(
+                denied.ends_with("(deletes the root or home directory); denied by policy"),
+                "{command}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_configured_rules_come_first() {
+        let configured = vec![
+            PolicyRule {
+                tool: "git".into(),
+                args: r"^push\b.*--force\b".into(),
+                action: Action::Allow,
+                reason: None,
+            },
+            PolicyRule {
+                tool: "*".into(),
+                args: r"\bprod\b".into(),
+                action: Action::Deny,
+                reason: Some("touches production".into()),
+            },
+        ];
+        let policy = Policy::new(&configured).unwrap();
+        assert_eq!(policy.check("git", &args("push --force"), false), None);
+        let message = policy.check("make", &args("deploy prod"), true).unwrap();
+        assert!(message.contains("(touches production)"));
+    }
+
+    #[test]
+    fn test_guard_reports_bad_patterns() {
+        let bad = vec![PolicyRule {
+            tool: "git".into(),
+            args: "(".into(),
+            action: Action::Deny,
+            reason: None,
+        }];
+        let outcome = guard(&bad, "git", &args("status"), false).unwrap();
+        assert_eq!(outcome.exit_code, crate::tools::EXIT_USAGE);
+        assert!(
+            outcome
+                .text
+                .starts_with("[cx] error: policy rule for `git`")
+        );
+
+        let blocked = guard(&[], "git", &args("reset --hard"), false).unwrap();
+        assert_eq!(blocked.exit_code, EXIT_BLOCKED);
+        assert!(guard(&[], "git", &args("status"), false).is_none());
+    }
+}
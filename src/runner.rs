@@ -1,5 +1,15 @@
-use std::process::Command;
-use std::time::Instant;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Exit code reported when the program could not be found (same as POSIX shells).
+pub const EXIT_NOT_FOUND: i32 = 127;
+/// Exit code reported when the program exists but could not be started.
+pub const EXIT_CANNOT_EXECUTE: i32 = 126;
 
 /// Result of executing a command: raw output + metadata.
 pub struct RunResult {
@@ -7,6 +17,8 @@ pub struct RunResult {
     pub stderr: String,
     pub exit_code: i32,
     pub elapsed_ms: u128,
+    /// Set when the command was killed after running this long.
+    pub timed_out: Option<Duration>,
 }
 
 impl RunResult {
@@ -21,28 +33,506 @@ impl RunResult {
         }
         buf
     }
+}
+
+/// The command could not be spawned at all.
+#[derive(Debug)]
+pub struct ExecError {
+    pub message: String,
+    pub kind: io::ErrorKind,
+}
+
+impl ExecError {
+    /// Exit code `cx` should report for this failure.
+    pub fn exit_code(&self) -> i32 {
+        match self.kind {
+            io::ErrorKind::NotFound => EXIT_NOT_FOUND,
+            _ => EXIT_CANNOT_EXECUTE,
+        }
+    }
+}
+
+impl std::fmt::Display for ExecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Exit code reported when the command was killed for exceeding its timeout (as `timeout(1)`).
+pub const EXIT_TIMEOUT: i32 = 124;
+
+/// How long the wait loop sleeps between checks on the child.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How often the live progress line is redrawn.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// How long output is still collected after the child exits, for processes it
+/// left running in the background that hold the pipes open.
+const DRAIN_GRACE: Duration = Duration::from_millis(500);
+
+/// Limits applied while a command runs.
+#[derive(Debug, Clone)]
+pub struct ExecOpts {
+    /// Kill the command (and its process group) after this long. Only then
+    /// does the command get a process group of its own; otherwise it stays in
+    /// the terminal's. Either way Ctrl-C reaches it: signals `cx` gets while
+    /// the group runs are forwarded to it.
+    pub timeout: Option<Duration>,
+    /// Bytes retained per stream; the middle of larger outputs is dropped.
+    pub max_output_bytes: usize,
+    /// Redraw a one-line progress indicator on stderr while waiting.
+    pub progress: bool,
+}
 
-    pub fn success(&self) -> bool {
-        self.exit_code == 0
+impl Default for ExecOpts {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            max_output_bytes: 16 * 1024 * 1024,
+            progress: false,
+        }
     }
 }
 
-/// Spawn a process, capture stdout/stderr separately, measure time.
-pub fn exec(program: &str, args: &[String]) -> Result<RunResult, String> {
+/// Spawn a process, stream stdout/stderr separately, measure time.
+/// On timeout the whole process group is killed and the partial output kept.
+/// Output is collected for at most `DRAIN_GRACE` after the child exits, so a
+/// background process holding the pipes open cannot hang `cx`.
+pub fn exec(program: &str, args: &[String], opts: &ExecOpts) -> Result<RunResult, ExecError> {
     let start = Instant::now();
 
-    let output = Command::new(program)
+    let mut command = Command::new(program);
+    command
         .args(args)
-        .output()
-        .map_err(|e| format!("failed to run `{program}`: {e}"))?;
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    if opts.timeout.is_some() {
+        use std::os::unix::process::CommandExt;
+        // Own process group, so a timeout also kills grandchildren (test binaries, …).
+        command.process_group(0);
+    }
+    let mut child = command.spawn().map_err(|e| ExecError {
+        message: format!("failed to run `{program}`: {e}"),
+        kind: e.kind(),
+    })?;
+    let _forward = opts.timeout.is_some().then(|| SignalForward::new(&child));
+
+    let lines = Arc::new(AtomicUsize::new(0));
+    let stdout = read_stream(child.stdout.take(), opts.max_output_bytes, &lines);
+    let stderr = read_stream(child.stderr.take(), opts.max_output_bytes, &lines);
+
+    let mut timed_out = false;
+    let mut last_progress = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) => {}
+            Err(_) => break None,
+        }
+        if opts.timeout.is_some_and(|t| start.elapsed() >= t) {
+            kill_group(&mut child);
+            timed_out = true;
+            break child.wait().ok();
+        }
+        if opts.progress && last_progress.elapsed() >= PROGRESS_INTERVAL {
+            draw_progress(program, start.elapsed(), lines.load(Ordering::Relaxed));
+            last_progress = Instant::now();
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    if opts.progress {
+        clear_progress();
+    }
 
-    let elapsed_ms = start.elapsed().as_millis();
-    let exit_code = output.status.code().unwrap_or(-1);
+    let mut deadline = Instant::now() + DRAIN_GRACE;
+    if let Some(timeout) = opts.timeout.filter(|_| !timed_out) {
+        deadline = deadline.min((start + timeout).max(Instant::now()));
+    }
+    while !(stdout.done() && stderr.done()) && Instant::now() < deadline {
+        thread::sleep(POLL_INTERVAL);
+    }
+    if !(stdout.done() && stderr.done()) && opts.timeout.is_some() {
+        // Whatever the child left behind in its group still holds the pipes.
+        kill_group(&mut child);
+    }
+    let stdout = stdout.take();
+    let stderr = stderr.take();
 
     Ok(RunResult {
-        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
-        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
-        exit_code,
-        elapsed_ms,
+        stdout: stdout.into_string(),
+        stderr: stderr.into_string(),
+        exit_code: match (timed_out, status) {
+            (true, _) => EXIT_TIMEOUT,
+            (false, Some(status)) => exit_code(status),
+            (false, None) => 1,
+        },
+        elapsed_ms: start.elapsed().as_millis(),
+        timed_out: timed_out.then(|| opts.timeout.unwrap_or_default()),
     })
 }
+
+/// A pipe drained on its own thread, so neither stream can block the other.
+/// What was read so far can be taken without waiting for the writer to close.
+struct Stream {
+    buf: Arc<Mutex<CappedBuf>>,
+    reader: JoinHandle<()>,
+}
+
+impl Stream {
+    /// Whether the pipe reached end of file.
+    fn done(&self) -> bool {
+        self.reader.is_finished()
+    }
+
+    /// The output read so far; a reader still blocked on the pipe is left
+    /// behind and ends with the process.
+    fn take(self) -> CappedBuf {
+        let mut buf = self.buf.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *buf)
+    }
+}
+
+fn read_stream<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    max_bytes: usize,
+    lines: &Arc<AtomicUsize>,
+) -> Stream {
+    let lines = Arc::clone(lines);
+    let buf = Arc::new(Mutex::new(CappedBuf::new(max_bytes)));
+    let shared = Arc::clone(&buf);
+    let reader = thread::spawn(move || {
+        let Some(mut pipe) = pipe else {
+            return;
+        };
+        let mut chunk = [0u8; 8192];
+        loop {
+            match pipe.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let newlines = chunk[..n].iter().filter(|b| **b == b'\n').count();
+                    lines.fetch_add(newlines, Ordering::Relaxed);
+                    shared
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(&chunk[..n]);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    });
+    Stream { buf, reader }
+}
+
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    // The child leads its own group (`process_group(0)`), so its pid is the pgid.
+    if let Ok(pgid) = i32::try_from(child.id()) {
+        // SAFETY: kill(2) has no memory-safety preconditions.
+        unsafe {
+            libc::kill(-pgid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Process group that SIGINT, SIGTERM and SIGHUP sent to `cx` are passed on
+/// to; 0 when none is running.
+#[cfg(unix)]
+static FORWARD_GROUP: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+/// While alive, signals that would end `cx` go to the child's own process
+/// group instead, which is outside the terminal's and would otherwise be left
+/// running. `cx` then reports the child's exit as usual (130 for Ctrl-C).
+struct SignalForward {
+    #[cfg(unix)]
+    pgid: i32,
+}
+
+#[cfg(unix)]
+impl SignalForward {
+    fn new(child: &Child) -> Self {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+                // SAFETY: the handler only calls async-signal-safe functions.
+                unsafe {
+                    libc::signal(signal, forward_signal as *const () as libc::sighandler_t);
+                }
+            }
+        });
+        // The child leads its own group (`process_group(0)`), so its pid is the pgid.
+        let pgid = i32::try_from(child.id()).unwrap_or(0);
+        FORWARD_GROUP.store(pgid, Ordering::SeqCst);
+        Self { pgid }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SignalForward {
+    fn drop(&mut self) {
+        let _ = FORWARD_GROUP.compare_exchange(self.pgid, 0, Ordering::SeqCst, Ordering::SeqCst);
+    }
+}
+
+#[cfg(not(unix))]
+impl SignalForward {
+    fn new(_child: &Child) -> Self {
+        Self {}
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn forward_signal(signal: libc::c_int) {
+    let pgid = FORWARD_GROUP.load(Ordering::SeqCst);
+    // SAFETY: kill(2), signal(2) and raise(3) are async-signal-safe.
+    unsafe {
+        if pgid > 0 {
+            libc::kill(-pgid, signal);
+        } else {
+            // No group running: die of the signal as if never handled.
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+}
+
+fn draw_progress(program: &str, elapsed: Duration, lines: usize) {
+    let mut err = io::stderr().lock();
+    let _ = write!(
+        err,
+        "\r\x1b[K[cx] {program} … {}s, {lines} lines",
+        elapsed.as_secs()
+    );
+    let _ = err.flush();
+}
+
+fn clear_progress() {
+    let mut err = io::stderr().lock();
+    let _ = write!(err, "\r\x1b[K");
+    let _ = err.flush();
+}
+
+/// Output buffer that keeps the first and last `max / 2` bytes of a stream.
+#[derive(Debug, Default)]
+struct CappedBuf {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    max: usize,
+    dropped: usize,
+}
+
+impl CappedBuf {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            ..Self::default()
+        }
+    }
+
+    fn push(&mut self, mut bytes: &[u8]) {
+        let head_room = (self.max / 2).saturating_sub(self.head.len());
+        if head_room > 0 && self.tail.is_empty() {
+            let n = head_room.min(bytes.len());
+            self.head.extend_from_slice(&bytes[..n]);
+            bytes = &bytes[n..];
+        }
+        self.tail.extend(bytes);
+        let tail_max = self.max - self.max / 2;
+        if self.tail.len() > tail_max {
+            let excess = self.tail.len() - tail_max;
+            self.tail.drain(..excess);
+            self.dropped += excess;
+        }
+    }
+
+    fn into_string(self) -> String {
+        let mut out = String::from_utf8_lossy(&self.head).into_owned();
+        let tail: Vec<u8> = self.tail.into_iter().collect();
+        if self.dropped == 0 {
+            out.push_str(&String::from_utf8_lossy(&tail));
+            return out;
+        }
+        // Resume the tail on a line boundary rather than mid-line.
+        let tail = match tail.iter().position(|b| *b == b'\n') {
+            Some(i) => &tail[i + 1..],
+            None => &tail[..],
+        };
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "[cx] … {} bytes of output omitted\n",
+            self.dropped
+        ));
+        out.push_str(&String::from_utf8_lossy(tail));
+        out
+    }
+}
+
+/// Map a process status to a shell-style exit code (128 + signal when killed).
+fn exit_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec_missing_program_is_not_found() {
+        let err = exec(
+            "cx-definitely-not-a-real-program",
+            &[],
+            &ExecOpts::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.exit_code(), EXIT_NOT_FOUND);
+        assert!(err.to_string().contains("failed to run"));
+    }
+
+    #[test]
+    fn test_permission_denied_cannot_execute() {
+        let err = ExecError {
+            message: "denied".into(),
+            kind: io::ErrorKind::PermissionDenied,
+        };
+        assert_eq!(err.exit_code(), EXIT_CANNOT_EXECUTE);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_propagates_exit_code() {
+        let result = exec("sh", &["-c".into(), "exit 3".into()], &ExecOpts::default()).unwrap();
+        assert_eq!(result.exit_code, 3);
+        assert!(result.timed_out.is_none());
+    }
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["-c".into(), script.into()]
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_captures_both_streams() {
+        let result = exec("sh", &sh("echo out; echo err >&2"), &ExecOpts::default()).unwrap();
+        assert_eq!(result.stdout, "out\n");
+        assert_eq!(result.stderr, "err\n");
+    }
+
+    /// Serializes the tests that run a command in its own process group, so
+    /// the forwarding test signals its own child.
+    #[cfg(unix)]
+    static GROUP: Mutex<()> = Mutex::new(());
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signals_are_forwarded_to_the_group() {
+        let _group = GROUP.lock().unwrap_or_else(|e| e.into_inner());
+        let opts = ExecOpts {
+            timeout: Some(Duration::from_secs(60)),
+            ..ExecOpts::default()
+        };
+        let run = thread::spawn(move || exec("sh", &sh("echo started; sleep 30"), &opts));
+        let start = Instant::now();
+        while FORWARD_GROUP.load(Ordering::SeqCst) == 0 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(POLL_INTERVAL);
+        }
+        // What Ctrl-C does: the terminal signals `cx`, not the child's group.
+        // SAFETY: kill(2) has no memory-safety preconditions.
+        unsafe {
+            libc::kill(libc::getpid(), libc::SIGINT);
+        }
+        let result = run.join().unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(result.exit_code, 128 + libc::SIGINT);
+        assert_eq!(result.stdout, "started\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_timeout_kills_group_and_keeps_partial_output() {
+        let _group = GROUP.lock().unwrap_or_else(|e| e.into_inner());
+        let opts = ExecOpts {
+            timeout: Some(Duration::from_millis(300)),
+            ..ExecOpts::default()
+        };
+        let start = Instant::now();
+        // The backgrounded sleep holds the pipe open: only a group kill ends it.
+        let result = exec("sh", &sh("echo started; sleep 30 & sleep 30"), &opts).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(result.exit_code, EXIT_TIMEOUT);
+        assert_eq!(result.timed_out, Some(Duration::from_millis(300)));
+        assert_eq!(result.stdout, "started\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_returns_when_a_background_process_holds_the_pipes() {
+        let _group = GROUP.lock().unwrap_or_else(|e| e.into_inner());
+        for timeout in [None, Some(Duration::from_secs(60))] {
+            let opts = ExecOpts {
+                timeout,
+                ..ExecOpts::default()
+            };
+            let start = Instant::now();
+            let result = exec("sh", &sh("sleep 30 & echo hi"), &opts).unwrap();
+            assert!(start.elapsed() < Duration::from_secs(10), "{timeout:?}");
+            assert_eq!(result.exit_code, 0);
+            assert_eq!(result.stdout, "hi\n");
+            assert!(result.timed_out.is_none());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_caps_retained_output() {
+        let opts = ExecOpts {
+            max_output_bytes: 1000,
+            ..ExecOpts::default()
+        };
+        let result = exec("sh", &sh("seq 1 100000"), &opts).unwrap();
+        assert!(result.stdout.len() < 1200);
+        assert!(result.stdout.starts_with("1\n2\n"));
+        assert!(result.stdout.ends_with("100000\n"));
+        assert!(result.stdout.contains("bytes of output omitted"));
+    }
+
+    #[test]
+    fn test_capped_buf_under_limit_is_verbatim() {
+        let mut buf = CappedBuf::new(100);
+        buf.push(b"hello ");
+        buf.push(b"world");
+        assert_eq!(buf.into_string(), "hello world");
+    }
+
+    #[test]
+    fn test_capped_buf_keeps_head_and_tail() {
+        let mut buf = CappedBuf::new(20);
+        for i in 0..50 {
+            buf.push(format!("line{i}\n").as_bytes());
+        }
+        let out = buf.into_string();
+        assert!(out.starts_with("line0\n"));
+        assert!(out.ends_with("line49\n"));
+        assert!(out.contains("[cx] … "));
+    }
+}
@@ -0,0 +1,424 @@
+//! Append-only log of how much each run was compressed (`stats`), and the
+//! `cx stats` report built from it: totals, per-tool or per-subcommand
+//! ratios, and the commands that compress worst.
+//!
+//! Only commands `cx` runs are logged. Results replayed from the cache and
+//! `cx`'s own commands (`ls`, `last`, `show`, `stats`, `make` target
+//! listings) have no raw output to measure and are left out.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Commands listed as compressing worst.
+const WORST_COMMANDS: usize = 5;
+
+/// Commands whose output averages less than this are left out of the worst
+/// list: there is little to gain from compressing them.
+const MIN_RAW_BYTES: u64 = 1024;
+
+/// One run, as logged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Unix timestamp (seconds) of the run.
+    pub timestamp: u64,
+    pub tool: String,
+    pub sub: Option<String>,
+    pub command: Vec<String>,
+    /// stdout + stderr as captured.
+    pub raw_bytes: u64,
+    /// What `cx` printed, footer included.
+    pub compressed_bytes: u64,
+    pub raw_tokens: u64,
+    pub compressed_tokens: u64,
+    pub elapsed_ms: u128,
+    pub exit_code: i32,
+}
+
+/// `~/.cache/cx/stats.jsonl`, one record per line.
+#[derive(Debug, Clone)]
+pub struct StatsLog {
+    path: PathBuf,
+}
+
+impl StatsLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The log under `~/.cache/cx`, or `None` when disabled (`stats = false`).
+    pub fn open(enabled: bool) -> Option<Self> {
+        if !enabled {
+            return None;
+        }
+        Some(Self::new(dirs::cache_dir()?.join("cx").join("stats.jsonl")))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a run, stamping it with the current time.
+    pub fn append(&self, mut record: Record) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            crate::archive::create_private_dir(dir)?;
+        }
+        record.timestamp = now_secs();
+        let mut line = serde_json::to_string(&record).map_err(io::Error::other)?;
+        line.push('\n');
+        // One write per record keeps concurrent runs from interleaving.
+        let mut options = fs::OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&self.path)?.write_all(line.as_bytes())
+    }
+
+    /// Records since the Unix timestamp `since`; unreadable lines (a
+    /// truncated last write, an older format) are skipped.
+    pub fn read(&self, since: u64) -> Vec<Record> {
+        let Ok(content) = fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Record>(line).ok())
+            .filter(|r| r.timestamp >= since)
+            .collect()
+    }
+}
+
+/// A period back from now, parsed from `30m`, `12h`, `7d` or `2w`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Since {
+    pub secs: u64,
+    label: String,
+}
+
+impl std::str::FromStr for Since {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid period `{s}` (e.g. 30m, 12h, 7d, 2w)");
+        let split = s.len().saturating_sub(1);
+        let (n, unit) = (s.get(..split).ok_or_else(invalid)?, &s[split..]);
+        let unit_secs = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86_400,
+            "w" => 7 * 86_400,
+            _ => return Err(invalid()),
+        };
+        let n: u64 = n.parse().map_err(|_| invalid())?;
+        Ok(Self {
+            secs: n.saturating_mul(unit_secs),
+            label: s.to_string(),
+        })
+    }
+}
+
+impl Since {
+    /// The Unix timestamp the period starts at.
+    pub fn start(&self) -> u64 {
+        now_secs().saturating_sub(self.secs)
+    }
+}
+
+impl std::fmt::Display for Since {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+/// How `cx stats` groups runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// `cargo`, `git`, …
+    #[default]
+    Tool,
+    /// `cargo test`, `git status`, …
+    Sub,
+}
+
+/// Totals over a set of runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Group {
+    pub key: String,
+    pub runs: u64,
+    pub failed: u64,
+    pub raw_bytes: u64,
+    pub compressed_bytes: u64,
+    pub raw_tokens: u64,
+    pub compressed_tokens: u64,
+    pub elapsed_ms: u128,
+    /// `raw_bytes / compressed_bytes`.
+    pub ratio: f64,
+}
+
+impl Group {
+    fn new(key: String) -> Self {
+        Self {
+            key,
+            ..Self::default()
+        }
+    }
+
+    fn add(&mut self, r: &Record) {
+        self.runs += 1;
+        self.failed += u64::from(r.exit_code != 0);
+        self.raw_bytes += r.raw_bytes;
+        self.compressed_bytes += r.compressed_bytes;
+        self.raw_tokens += r.raw_tokens;
+        self.compressed_tokens += r.compressed_tokens;
+        self.elapsed_ms += r.elapsed_ms;
+        self.ratio = self.raw_bytes as f64 / self.compressed_bytes.max(1) as f64;
+    }
+
+    /// `58 runs, 2.1 MB → 98 KB (21.9x), ~500k → ~23k tokens, avg 3.4s, 12 failed`
+    fn line(&self) -> String {
+        let mut line = format!(
+            "{} run{}, {} → {} ({:.1}x), ~{} → ~{} tokens, avg {}",
+            self.runs,
+            if self.runs == 1 { "" } else { "s" },
+            format_bytes(self.raw_bytes),
+            format_bytes(self.compressed_bytes),
+            self.ratio,
+            format_count(self.raw_tokens),
+            format_count(self.compressed_tokens),
+            format_ms(self.elapsed_ms / u128::from(self.runs.max(1))),
+        );
+        if self.failed > 0 {
+            line.push_str(&format!(", {} failed", self.failed));
+        }
+        line
+    }
+}
+
+/// What `cx stats` prints.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Report {
+    pub total: Group,
+    /// By tool or subcommand, most raw output first.
+    pub groups: Vec<Group>,
+    /// Commands with the lowest ratio, worst first.
+    pub worst: Vec<Group>,
+}
+
+/// Aggregate `records`, grouped by `by`.
+pub fn report(records: &[Record], by: GroupBy) -> Report {
+    let mut report = Report {
+        total: Group::new("total".into()),
+        ..Report::default()
+    };
+    let mut commands: Vec<Group> = Vec::new();
+    for r in records {
+        report.total.add(r);
+        let key = match (by, &r.sub) {
+            (GroupBy::Sub, Some(sub)) => format!("{} {sub}", r.tool),
+            _ => r.tool.clone(),
+        };
+        group(&mut report.groups, key).add(r);
+        group(&mut commands, r.command.join(" ")).add(r);
+    }
+    report
+        .groups
+        .sort_by_key(|g| std::cmp::Reverse(g.raw_bytes));
+
+    commands.retain(|c| c.raw_bytes / c.runs >= MIN_RAW_BYTES);
+    commands.sort_by(|a, b| a.ratio.total_cmp(&b.ratio));
+    commands.truncate(WORST_COMMANDS);
+    report.worst = commands;
+    report
+}
+
+/// The group named `key`, added if missing.
+fn group(groups: &mut Vec<Group>, key: String) -> &mut Group {
+    match groups.iter().position(|g| g.key == key) {
+        Some(i) => &mut groups[i],
+        None => {
+            groups.push(Group::new(key));
+            groups.last_mut().expect("just pushed")
+        }
+    }
+}
+
+impl Report {
+    /// `[stats] …` totals, then `[by tool: N]` and `[worst: N]` sections.
+    pub fn render(&self, by: GroupBy, since: Option<&Since>, max_items: usize) -> String {
+        if self.total.runs == 0 {
+            return match since {
+                Some(since) => format!("[stats] no runs recorded in the last {since}"),
+                None => "[stats] no runs recorded".into(),
+            };
+        }
+        let period = since.map(|s| format!("last {s}: ")).unwrap_or_default();
+        let mut out = format!("[stats] {period}{}\n", self.total.line());
+        let by = match by {
+            GroupBy::Tool => "tool",
+            GroupBy::Sub => "sub",
+        };
+        out.push_str(&format!("[by {by}: {}]\n", self.groups.len()));
+        for g in self.groups.iter().take(max_items) {
+            out.push_str(&format!("  {}  {}\n", g.key, g.line()));
+        }
+        if self.groups.len() > max_items {
+            out.push_str(&format!("  … +{} more\n", self.groups.len() - max_items));
+        }
+        if !self.worst.is_empty() {
+            out.push_str(&format!("[worst: {}]\n", self.worst.len()));
+            for g in &self.worst {
+                out.push_str(&format!("  {}  {}\n", g.key, g.line()));
+            }
+        }
+        out.trim_end().to_string()
+    }
+}
+
+/// `512 B`, `98 KB`, `2.1 MB`.
+fn format_bytes(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    match bytes {
+        0..KB => format!("{bytes} B"),
+        KB..MB => format!("{} KB", bytes / KB),
+        _ => format!("{:.1} MB", bytes as f64 / MB as f64),
+    }
+}
+
+/// `812`, `52k`, `1.3M`.
+fn format_count(n: u64) -> String {
+    match n {
+        0..1000 => n.to_string(),
+        1000..1_000_000 => format!("{}k", n / 1000),
+        _ => format!("{:.1}M", n as f64 / 1e6),
+    }
+}
+
+/// `340ms`, `3.4s`.
+fn format_ms(ms: u128) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(command: &str, sub: Option<&str>, raw: u64, compressed: u64, exit: i32) -> Record {
+        Record {
+            timestamp: 0,
+            tool: command.split(' ').next().unwrap().into(),
+            sub: sub.map(String::from),
+            command: command.split(' ').map(String::from).collect(),
+            raw_bytes: raw,
+            compressed_bytes: compressed,
+            raw_tokens: raw / 4,
+            compressed_tokens: compressed / 4,
+            elapsed_ms: 1500,
+            exit_code: exit,
+        }
+    }
+
+    #[test]
+    fn test_since_parses_units() {
+        assert_eq!("7d".parse::<Since>().unwrap().secs, 7 * 86_400);
+        assert_eq!("30m".parse::<Since>().unwrap().secs, 1800);
+        assert_eq!("2w".parse::<Since>().unwrap().to_string(), "2w");
+        assert!("7".parse::<Since>().is_err());
+        assert!("d".parse::<Since>().is_err());
+        assert!("7y".parse::<Since>().is_err());
+    }
+
+    #[test]
+    fn test_report_groups_and_finds_worst() {
+        let records = [
+            record("cargo test", Some("test"), 40_000, 2_000, 101),
+            record("cargo build", Some("build"), 20_000, 1_000, 0),
+            record("docker logs api", Some("logs"), 10_000, 9_000, 0),
+            record("git push", Some("push"), 200, 150, 0),
+        ];
+        let by_tool = report(&records, GroupBy::Tool);
+        assert_eq!(by_tool.total.runs, 4);
+        assert_eq!(by_tool.groups[0].key, "cargo");
+        assert_eq!((by_tool.groups[0].runs, by_tool.groups[0].failed), (2, 1));
+        assert_eq!(by_tool.groups[0].ratio, 20.0);
+        // `git push` is too small to matter.
+        let worst: Vec<&str> = by_tool.worst.iter().map(|g| g.key.as_str()).collect();
+        assert_eq!(worst, ["docker logs api", "cargo test", "cargo build"]);
+
+        let by_sub = report(&records, GroupBy::Sub);
+        assert_eq!(by_sub.groups[0].key, "cargo test");
+        assert_eq!(by_sub.groups.len(), 4);
+    }
+
+    #[test]
+    fn test_render() {
+        let records = [
+            record("cargo test", Some("test"), 40_960, 2_048, 101),
+            record("git status", Some("status"), 512, 512, 0),
+        ];
+        let since = "7d".parse().unwrap();
+        let text = report(&records, GroupBy::Tool).render(GroupBy::Tool, Some(&since), 1);
+        assert_eq!(
+            text,
+            "[stats] last 7d: 2 runs, 40 KB → 2 KB (16.2x), ~10k → ~640 tokens, avg 1.5s, 1 failed\n\
+             [by tool: 2]\n  \
+             cargo  1 run, 40 KB → 2 KB (20.0x), ~10k → ~512 tokens, avg 1.5s, 1 failed\n  \
+             … +1 more\n\
+             [worst: 1]\n  \
+             cargo test  1 run, 40 KB → 2 KB (20.0x), ~10k → ~512 tokens, avg 1.5s, 1 failed"
+        );
+        assert_eq!(
+            report(&[], GroupBy::Tool).render(GroupBy::Tool, Some(&since), 5),
+            "[stats] no runs recorded in the last 7d"
+        );
+    }
+
+    #[test]
+    fn test_log_appends_and_filters_by_time() {
+        let dir = std::env::temp_dir().join(format!("cx-stats-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log = StatsLog::new(dir.join("stats.jsonl"));
+        log.append(record("git status", None, 10, 5, 0)).unwrap();
+        log.append(record("git log", None, 10, 5, 0)).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(log.path())
+            .unwrap()
+            .write_all(b"{\"truncated\n")
+            .unwrap();
+
+        let records = log.read(0);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].command, ["git", "log"]);
+        assert!(records[0].timestamp > 0);
+        assert!(log.read(now_secs() + 60).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_log_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("cx-stats-private-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log = StatsLog::new(dir.join("cx").join("stats.jsonl"));
+        log.append(record("git status", None, 10, 5, 0)).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(log.path()), 0o600);
+        assert_eq!(mode(&dir.join("cx")), 0o700);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
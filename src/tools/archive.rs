@@ -0,0 +1,131 @@
+use super::{Outcome, Tool, footer, with_footer};
+use crate::archive::{Archive, LineRange};
+use crate::compress::normalize::normalize;
+use crate::config::{Config, OutputFormat};
+use serde_json::json;
+
+/// Archive tool: replays an archived run (no external command needed).
+pub struct ArchiveTool {
+    /// Run to show; `None` for the most recent one.
+    id: Option<u64>,
+    raw: bool,
+    lines: Option<LineRange>,
+    grep: Option<String>,
+    archive: Option<Archive>,
+    max_lines: usize,
+    format: OutputFormat,
+}
+
+impl ArchiveTool {
+    pub fn new(
+        id: Option<u64>,
+        raw: bool,
+        lines: Option<LineRange>,
+        grep: Option<String>,
+        cfg: &Config,
+    ) -> Self {
+        Self {
+            id,
+            raw,
+            lines,
+            grep,
+            archive: Archive::open(cfg.archive_size),
+            max_lines: cfg.max_lines,
+            format: cfg.format,
+        }
+    }
+
+    /// Numbered lines of `raw`, filtered by `--lines` / `--grep`.
+    fn select(&self, raw: &str) -> Vec<(usize, String)> {
+        raw.lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l))
+            .filter(|(n, _)| self.lines.is_none_or(|r| r.contains(*n)))
+            .filter(|(_, l)| self.grep.as_ref().is_none_or(|p| l.contains(p.as_str())))
+            .map(|(n, l)| (n, l.to_string()))
+            .collect()
+    }
+}
+
+impl Tool for ArchiveTool {
+    fn run(&self) -> Outcome {
+        let Some(archive) = &self.archive else {
+            return Outcome::usage("[cx] archive disabled (archive_size = 0)");
+        };
+        let entry = match self.id {
+            Some(id) => archive.load(id),
+            None => archive.last(),
+        };
+        let Some(entry) = entry else {
+            let what = self
+                .id
+                .map_or("no runs".into(), |id| format!("run {id} not"));
+            return Outcome::new(format!("[cx] {what} found in archive"), 1);
+        };
+
+        let command = entry.command.join(" ");
+        // `cx last` without filters replays the compressed view.
+        if !self.raw && self.lines.is_none() && self.grep.is_none() {
+            let footer = footer(
+                &entry.tool,
+                entry.exit_code,
+                entry.elapsed_ms,
+                Some(entry.id),
+            );
+            let mut outcome = Outcome::new(with_footer(&entry.compressed, &footer), 0);
+            if self.format == OutputFormat::Json {
+                outcome.json = Some(json!({
+                    "tool": entry.tool,
+                    "id": entry.id,
+                    "command": entry.command,
+                    "exit_code": entry.exit_code,
+                    "text": entry.compressed,
+                }));
+            }
+            return outcome;
+        }
+
+        // Terminal escapes are noise here too; line numbers refer to the cleaned text.
+        let raw = normalize(&entry.raw());
+        let selected = self.select(&raw);
+        // An explicit range is what the caller asked for; only cap unbounded views.
+        let shown = match self.lines {
+            Some(_) => selected.len(),
+            None => selected.len().min(self.max_lines),
+        };
+        let mut body: Vec<String> = selected[..shown]
+            .iter()
+            .map(|(n, l)| format!("{n:>5}│ {l}"))
+            .collect();
+        if let Some((next, _)) = selected.get(shown) {
+            body.push(format!(
+                "[cx] … {} more lines, page with --lines {next}..",
+                selected.len() - shown
+            ));
+        }
+        let total = raw.lines().count();
+        let header = format!(
+            "[cx show {}] {command} (exit {}, {} of {total} lines)",
+            entry.id,
+            entry.exit_code,
+            selected.len()
+        );
+
+        let mut outcome = Outcome::new(format!("{header}\n{}", body.join("\n")), 0);
+        if self.format == OutputFormat::Json {
+            outcome.json = Some(json!({
+                "tool": entry.tool,
+                "id": entry.id,
+                "command": entry.command,
+                "exit_code": entry.exit_code,
+                "total_lines": total,
+                "truncated": selected.len() - shown,
+                "lines": selected[..shown]
+                    .iter()
+                    .map(|(n, l)| json!({ "n": n, "text": l }))
+                    .collect::<Vec<_>>(),
+            }));
+        }
+        outcome
+    }
+}
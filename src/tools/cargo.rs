@@ -1,16 +1,22 @@
-use super::{Tool, footer};
-use crate::compress::Compressor;
+use super::{Outcome, RunOpts, Tool, run_compressed};
+use crate::cache::{CacheSpec, Input};
 use crate::compress::cargo::CargoCompressor;
-use crate::runner;
+use crate::config::Config;
+use std::path::PathBuf;
 
 /// Cargo tool: runs cargo sub-commands with smart defaults, compresses output.
 pub struct CargoTool {
     args: Vec<String>,
+    opts: RunOpts,
 }
 
 impl CargoTool {
-    pub fn new(args: Vec<String>) -> Self {
-        Self { args }
+    pub fn new(args: Vec<String>, cfg: &Config) -> Self {
+        let mut opts = RunOpts::new(cfg, "cargo");
+        if args.first().is_some_and(|a| a == "nextest") {
+            opts.report = Some(nextest_junit_path(&args[1..]));
+        }
+        Self { args, opts }
     }
 
     /// Inject sensible defaults per sub-command.
@@ -31,9 +37,10 @@ impl CargoTool {
                 }
                 out.extend(rest.iter().cloned());
             }
-            "clippy" => {
+            "build" | "check" | "clippy" => {
+                // Structured diagnostics: location, lint code and suggested fix.
                 if !rest.iter().any(|a| a.starts_with("--message-format")) {
-                    out.push("--message-format=short".into());
+                    out.push("--message-format=json".into());
                 }
                 out.extend(rest.iter().cloned());
             }
@@ -52,23 +59,67 @@ impl CargoTool {
     }
 }
 
+/// Where nextest writes its JUnit report when the profile enables one
+/// (`[profile.<name>.junit] path = "junit.xml"`): exact per-test results and
+/// timings that its human output only partly carries.
+fn nextest_junit_path(args: &[String]) -> PathBuf {
+    let mut profile = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = Some(name.to_string());
+        } else if arg == "--profile" || arg == "-P" {
+            profile = args.next().cloned();
+        }
+    }
+    let profile = profile
+        .or_else(|| std::env::var("NEXTEST_PROFILE").ok())
+        .unwrap_or_else(|| "default".into());
+    let target = std::env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".into());
+    [target.as_str(), "nextest", &profile, "junit.xml"]
+        .iter()
+        .collect()
+}
+
 impl Tool for CargoTool {
-    fn run(&self) -> String {
+    fn run(&self) -> Outcome {
         if self.args.is_empty() {
-            return "[cargo] error: needs a subcommand (build, test, clippy, …)".into();
+            return Outcome::usage("[cargo] error: needs a subcommand (build, test, clippy, …)");
         }
 
         let sub = &self.args[0];
         let args = self.build_args();
 
-        match runner::exec("cargo", &args) {
-            Ok(result) => {
-                let raw = result.combined();
-                let compressor = CargoCompressor;
-                let compressed = compressor.compress(&raw, Some(sub));
-                format!("{compressed}{}", footer("cargo", &result))
-            }
-            Err(e) => format!("[cargo] error: {e}"),
+        run_compressed(
+            "cargo",
+            "cargo",
+            &args,
+            &CargoCompressor,
+            Some(sub),
+            &self.opts,
+        )
+    }
+
+    /// Diagnostics only change with the sources of the workspace; tests and
+    /// runs can depend on anything, so they always run.
+    fn cache_spec(&self) -> Option<CacheSpec> {
+        if !matches!(
+            self.args.first().map(String::as_str),
+            Some("check" | "clippy" | "build")
+        ) {
+            return None;
         }
+        let cwd = std::env::current_dir().ok()?;
+        let root = cwd
+            .ancestors()
+            .find(|dir| dir.join("Cargo.lock").exists())
+            .unwrap_or(&cwd);
+        Some(CacheSpec {
+            env: &["CARGO", "RUST"],
+            inputs: vec![Input::Tree {
+                root: root.to_path_buf(),
+                skip: vec!["target".into(), ".git".into(), "node_modules".into()],
+            }],
+        })
     }
 }
@@ -1,35 +1,37 @@
-use super::{Tool, footer};
-use crate::compress::Compressor;
+use super::{Outcome, RunOpts, Tool, run_compressed};
 use crate::compress::docker::DockerCompressor;
-use crate::runner;
+use crate::config::Config;
 
 /// Docker tool: runs docker sub-commands, compresses output.
 pub struct DockerTool {
     args: Vec<String>,
+    opts: RunOpts,
 }
 
 impl DockerTool {
-    pub fn new(args: Vec<String>) -> Self {
-        Self { args }
+    pub fn new(args: Vec<String>, cfg: &Config) -> Self {
+        Self {
+            args,
+            opts: RunOpts::new(cfg, "docker"),
+        }
     }
 }
 
 impl Tool for DockerTool {
-    fn run(&self) -> String {
+    fn run(&self) -> Outcome {
         if self.args.is_empty() {
-            return "[docker] error: needs a subcommand (ps, images, logs, …)".into();
+            return Outcome::usage("[docker] error: needs a subcommand (ps, images, logs, …)");
         }
 
         let sub = &self.args[0];
 
-        match runner::exec("docker", &self.args) {
-            Ok(result) => {
-                let raw = result.combined();
-                let compressor = DockerCompressor;
-                let compressed = compressor.compress(&raw, Some(sub));
-                format!("{compressed}{}", footer("docker", &result))
-            }
-            Err(e) => format!("[docker] error: {e}"),
-        }
+        run_compressed(
+            "docker",
+            "docker",
+            &self.args,
+            &DockerCompressor,
+            Some(sub),
+            &self.opts,
+        )
     }
 }
@@ -1,7 +1,9 @@
-use super::Tool;
-use crate::config::Config;
+use super::{Outcome, Tool};
+use crate::cache::{CacheSpec, Input};
+use crate::config::{Config, OutputFormat};
+use serde_json::json;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// FS tool: compact tree listing (no external command needed).
 pub struct FsTool {
@@ -9,6 +11,7 @@ pub struct FsTool {
     max_depth: usize,
     max_entries: usize,
     skip: Vec<String>,
+    format: OutputFormat,
 }
 
 impl FsTool {
@@ -18,18 +21,19 @@ impl FsTool {
             max_depth: cfg.ls_max_depth,
             max_entries: cfg.ls_max_entries,
             skip: cfg.ls_skip.clone(),
+            format: cfg.format,
         }
     }
 }
 
 impl Tool for FsTool {
-    fn run(&self) -> String {
+    fn run(&self) -> Outcome {
         let root = Path::new(&self.path);
         if !root.exists() {
-            return format!("[ls] error: `{}` does not exist", self.path);
+            return Outcome::usage(format!("[ls] error: `{}` does not exist", self.path));
         }
         if root.is_file() {
-            return format!("[ls] {} (file)", self.path);
+            return Outcome::new(format!("[ls] {} (file)", self.path), 0);
         }
 
         let mut lines = Vec::new();
@@ -48,7 +52,30 @@ impl Tool for FsTool {
             ));
         }
 
-        format!("[ls] {} ({count} entries)\n{}", self.path, lines.join("\n"))
+        let mut outcome = Outcome::new(
+            format!("[ls] {} ({count} entries)\n{}", self.path, lines.join("\n")),
+            0,
+        );
+        if self.format == OutputFormat::Json {
+            outcome.json = Some(json!({
+                "tool": "ls",
+                "path": self.path,
+                "exit_code": 0,
+                "summary": { "entries": count, "tree": lines },
+            }));
+        }
+        outcome
+    }
+
+    /// The listing changes when anything under the path does.
+    fn cache_spec(&self) -> Option<CacheSpec> {
+        Some(CacheSpec {
+            env: &[],
+            inputs: vec![Input::Tree {
+                root: PathBuf::from(&self.path),
+                skip: self.skip.clone(),
+            }],
+        })
     }
 }
 
@@ -1,37 +1,88 @@
-use super::{Tool, footer};
-use crate::compress::Compressor;
+use super::{Outcome, RunOpts, Tool, run_compressed};
 use crate::compress::generic::GenericCompressor;
-use crate::runner;
+use crate::compress::rules::{RuleCompressor, RuleSpec};
+use crate::config::Config;
 
-/// Generic fallback tool: execute any command, truncate output.
+/// Generic fallback tool: execute any command, compress with the first
+/// matching `[[compressor]]` rule, or just truncate.
 pub struct GenericTool {
     args: Vec<String>,
+    rules: Vec<RuleSpec>,
+    opts: RunOpts,
 }
 
 impl GenericTool {
-    pub fn new(args: Vec<String>) -> Self {
-        Self { args }
+    pub fn new(args: Vec<String>, cfg: &Config) -> Self {
+        Self {
+            args,
+            rules: cfg.compressors.clone(),
+            opts: RunOpts::new(cfg, "run"),
+        }
     }
 }
 
 impl Tool for GenericTool {
-    fn run(&self) -> String {
+    fn run(&self) -> Outcome {
         if self.args.is_empty() {
-            return "[run] error: no command provided".into();
+            return Outcome::usage("[run] error: no command provided");
         }
 
         let program = &self.args[0];
         let cmd_args = &self.args[1..];
-        let cmd_args_owned: Vec<String> = cmd_args.to_vec();
-
-        match runner::exec(program, &cmd_args_owned) {
-            Ok(result) => {
-                let raw = result.combined();
-                let compressor = GenericCompressor;
-                let compressed = compressor.compress(&raw, None);
-                format!("{compressed}{}", footer("run", &result))
-            }
-            Err(e) => format!("[run] error: {e}"),
+
+        if let Some(rule) = rule_for(&self.rules, program, cmd_args) {
+            let compressor = match RuleCompressor::compile(rule) {
+                Ok(c) => c,
+                Err(e) => return Outcome::usage(format!("[run] error: {e}")),
+            };
+            let sub = rule.subcommand.as_deref();
+            let args = rule.apply_args(cmd_args);
+            return run_compressed(rule.label(), program, &args, &compressor, sub, &self.opts);
         }
+
+        run_compressed(
+            "run",
+            program,
+            cmd_args,
+            &GenericCompressor,
+            None,
+            &self.opts,
+        )
+    }
+}
+
+/// The first `[[compressor]]` rule for `program args…`.
+fn rule_for<'a>(rules: &'a [RuleSpec], program: &str, args: &[String]) -> Option<&'a RuleSpec> {
+    rules.iter().find(|r| r.matches(program, args))
+}
+
+/// What `cx run args…` actually runs: `args` plus the arguments the matching
+/// `[[compressor]]` rule injects, as the safety policy must see it.
+pub fn resolved_command(args: &[String], rules: &[RuleSpec]) -> Vec<String> {
+    let Some((program, rest)) = args.split_first() else {
+        return Vec::new();
+    };
+    let rest = match rule_for(rules, program, rest) {
+        Some(rule) => rule.apply_args(rest),
+        None => rest.to_vec(),
+    };
+    std::iter::once(program.clone()).chain(rest).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy;
+
+    #[test]
+    fn test_policy_sees_injected_args() {
+        let rule: RuleSpec =
+            toml::from_str("program = \"git\"\nsubcommand = \"push\"\nargs = [\"--force\"]")
+                .unwrap();
+        let args: Vec<String> = ["git", "push", "origin"].map(String::from).to_vec();
+        let command = resolved_command(&args, &[rule]);
+        assert_eq!(command, ["git", "push", "--force", "origin"]);
+        assert!(policy::guard(&[], "run", &command, false).is_some());
+        assert!(policy::guard(&[], "run", &args, false).is_none());
     }
 }
@@ -1,53 +1,146 @@
-use super::{Tool, footer};
-use crate::compress::Compressor;
-use crate::compress::git::GitCompressor;
-use crate::runner;
+use super::{Outcome, RunOpts, Tool, run_compressed};
+use crate::cache::{CacheSpec, Input};
+use crate::compress::git::{GitCompressor, LOG_FORMAT, MARKERS_HEADER, OPERATION_HEADER};
+use crate::config::Config;
+use crate::runner::{self, ExecOpts};
+use std::path::{Path, PathBuf};
+
+/// Worktree directories whose contents never change a cached status or diff.
+const WORKTREE_SKIP: &[&str] = &[".git", "target", "node_modules"];
+
+/// `cx` options of `git log`, removed before git sees the arguments.
+const LOG_FLAGS: &[&str] = &["--since-branch", "--by-author"];
 
 /// Git tool: builds git commands with smart defaults, compresses output.
 pub struct GitTool {
     args: Vec<String>,
+    opts: RunOpts,
+    /// `git_diff_patch`: compact hunks instead of `--stat` for `git diff`.
+    diff_patch: bool,
 }
 
 impl GitTool {
-    pub fn new(args: Vec<String>) -> Self {
-        Self { args }
+    pub fn new(args: Vec<String>, cfg: &Config) -> Self {
+        let mut opts = RunOpts::new(cfg, "git");
+        if matches!(
+            args.first().map(String::as_str),
+            Some("merge" | "rebase" | "cherry-pick" | "revert")
+        ) {
+            opts.follow_up = Some(operation_state);
+        }
+        Self {
+            args,
+            opts,
+            diff_patch: cfg.git_diff_patch,
+        }
     }
 
-    /// Inject sensible defaults per sub-command.
-    fn build_args(&self) -> (String, Vec<String>) {
+    /// Inject sensible defaults per sub-command. `base` is the merge-base
+    /// `git log --since-branch` starts from.
+    fn build_args(&self, base: Option<&str>) -> (String, Vec<String>) {
         if self.args.is_empty() {
-            return ("status".into(), vec!["status".into()]);
+            return (
+                "status".into(),
+                vec!["status".into(), "--porcelain=v2".into(), "--branch".into()],
+            );
         }
 
-        let sub = self.args[0].clone();
+        let mut sub = self.args[0].clone();
         let rest = &self.args[1..];
         let mut out = vec![sub.clone()];
 
         match sub.as_str() {
+            "status" => {
+                // Short and porcelain v1 lines (NUL-separated with `-z`) are
+                // already compact and not what the status parser reads.
+                if rest.iter().any(|a| {
+                    matches!(a.as_str(), "-s" | "--short" | "-z" | "--porcelain")
+                        || a == "--porcelain=v1"
+                        || (a.starts_with("-s") && !a.starts_with("--"))
+                }) {
+                    sub = "status-short".into();
+                } else if !rest.iter().any(|a| {
+                    matches!(a.as_str(), "--long" | "-v" | "--verbose")
+                        || a.starts_with("--porcelain")
+                }) {
+                    // Machine-readable status: the same in every locale and git version.
+                    out.push("--porcelain=v2".into());
+                    out.push("--branch".into());
+                }
+                out.extend(rest.iter().cloned());
+            }
             "log" => {
-                if !rest
+                let by_author = rest.iter().any(|a| a == "--by-author");
+                let rest: Vec<&String> = rest
                     .iter()
-                    .any(|a| a.starts_with("--format") || a.starts_with("--pretty"))
-                {
-                    out.push("--oneline".into());
+                    .filter(|a| !LOG_FLAGS.contains(&a.as_str()))
+                    .collect();
+                // Only the injected format can be parsed; patches, graphs and
+                // formats the user picked are passed through untouched.
+                if !rest.iter().any(|a| {
+                    matches!(
+                        a.as_str(),
+                        "--oneline" | "--graph" | "-p" | "-u" | "--patch" | "--stat"
+                    ) || a.starts_with("--format")
+                        || a.starts_with("--pretty")
+                        || a.starts_with("--name-")
+                }) {
+                    out.push(LOG_FORMAT.into());
+                    if !rest.iter().any(|a| a.starts_with("--date")) {
+                        out.push("--date=short".into());
+                    }
+                    if by_author {
+                        sub = "log-author".into();
+                    }
                 }
-                if !rest
-                    .iter()
-                    .any(|a| a.starts_with("-n") || a.starts_with("--max-count"))
+                if base.is_none()
+                    && !rest
+                        .iter()
+                        .any(|a| a.starts_with("-n") || a.starts_with("--max-count"))
                 {
                     out.push("-n30".into());
                 }
-                out.extend(rest.iter().cloned());
+                out.extend(rest.into_iter().cloned());
+                if let Some(base) = base {
+                    // Before `--`, after which git reads paths.
+                    let at = out.iter().position(|a| a == "--").unwrap_or(out.len());
+                    out.insert(at, format!("{base}..HEAD"));
+                }
             }
             "diff" => {
-                if !rest
+                let summary = rest
                     .iter()
-                    .any(|a| a == "--stat" || a == "--name-only" || a == "--cached")
-                {
-                    out.push("--stat".into());
+                    .any(|a| a == "--stat" || a == "--name-only" || a == "--name-status");
+                let patch = rest.iter().any(|a| a == "--patch" || a == "-p");
+                out.extend(rest.iter().cloned());
+                if !summary && (patch || self.diff_patch) {
+                    return ("patch".into(), out);
+                }
+                if !summary && !rest.iter().any(|a| a == "--cached") {
+                    out.insert(1, "--stat".into());
+                }
+            }
+            "show" => {
+                // `<rev>:<path>` prints a file (or tree) rather than a commit.
+                if rest.iter().any(|a| !a.starts_with('-') && a.contains(':')) {
+                    sub = "show-file".into();
+                } else if !rest.iter().any(|a| {
+                    a == "--oneline"
+                        || a == "--no-abbrev-commit"
+                        || a.starts_with("--format")
+                        || a.starts_with("--pretty")
+                }) {
+                    out.push("--abbrev-commit".into());
                 }
                 out.extend(rest.iter().cloned());
             }
+            "merge" | "rebase" | "cherry-pick" | "revert" => {
+                out.extend(rest.iter().cloned());
+                // `--continue` would otherwise wait in an editor for the message.
+                if rest.iter().any(|a| a == "--continue") {
+                    out.splice(0..0, ["-c".to_string(), "core.editor=true".to_string()]);
+                }
+            }
             "branch" => {
                 if rest.is_empty() {
                     out.push("-a".into());
@@ -93,17 +186,226 @@ impl GitTool {
 }
 
 impl Tool for GitTool {
-    fn run(&self) -> String {
-        let (sub, args) = self.build_args();
-
-        match runner::exec("git", &args) {
-            Ok(result) => {
-                let raw = result.combined();
-                let compressor = GitCompressor;
-                let compressed = compressor.compress(&raw, Some(&sub));
-                format!("{compressed}{}", footer("git", &result))
+    fn run(&self) -> Outcome {
+        let since_branch = self.args.first().is_some_and(|a| a == "log")
+            && self.args.iter().any(|a| a == "--since-branch");
+        let base = if since_branch {
+            match merge_base() {
+                Some(base) => Some(base),
+                None => {
+                    return Outcome::usage(
+                        "[git log] --since-branch: no default branch found (origin/HEAD, main or master)",
+                    );
+                }
             }
-            Err(e) => format!("[git] error: {e}"),
+        } else {
+            None
+        };
+        let (sub, args) = self.build_args(base.as_deref());
+
+        run_compressed("git", "git", &args, &GitCompressor, Some(&sub), &self.opts)
+    }
+
+    /// Read-only commands, invalidated by HEAD, the index and refs, plus the
+    /// worktree for those that compare against it.
+    fn cache_spec(&self) -> Option<CacheSpec> {
+        let rest = self.args.get(1..).unwrap_or_default();
+        let worktree = match self.args.first().map_or("status", String::as_str) {
+            "status" | "diff" | "blame" => true,
+            "log" | "show" => false,
+            // Listings only: with arguments these create or delete things.
+            "branch" | "tag" | "remote" if rest.is_empty() => false,
+            "stash" if rest.is_empty() || rest == ["list"] => false,
+            _ => return None,
+        };
+        let git_dir = git_dir()?;
+        let mut inputs: Vec<Input> = ["HEAD", "index", "packed-refs", "config", "logs/refs/stash"]
+            .iter()
+            .map(|name| Input::File(git_dir.join(name)))
+            .collect();
+        inputs.push(Input::Tree {
+            root: git_dir.join("refs"),
+            skip: Vec::new(),
+        });
+        if worktree {
+            inputs.push(Input::Tree {
+                root: git_dir.parent()?.to_path_buf(),
+                skip: WORKTREE_SKIP.iter().map(|s| s.to_string()).collect(),
+            });
+        }
+        Some(CacheSpec {
+            env: &["GIT_"],
+            inputs,
+        })
+    }
+}
+
+/// The `.git` directory of the repository around the cwd; `None` outside a
+/// repository and in linked worktrees, where `.git` is a file.
+fn git_dir() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(".git"))
+        .find(|git| git.exists())
+        .filter(|git| git.is_dir())
+}
+
+/// Where the current branch left the default branch: the merge-base of
+/// `HEAD` and `origin/HEAD`, or else of `HEAD` and the first of `main`,
+/// `master`, `origin/main`, `origin/master` that exists.
+fn merge_base() -> Option<String> {
+    let default = git_output(&[
+        "symbolic-ref",
+        "--quiet",
+        "--short",
+        "refs/remotes/origin/HEAD",
+    ])
+    .or_else(|| {
+        ["main", "master", "origin/main", "origin/master"]
+            .into_iter()
+            .find(|r| git_output(&["rev-parse", "--verify", "--quiet", r]).is_some())
+            .map(str::to_string)
+    })?;
+    git_output(&["merge-base", "HEAD", &default])
+}
+
+/// State of a merge / rebase / cherry-pick / revert the command left in
+/// progress, for the compressor: the operation (with the rebase step), the
+/// conflict markers left in each unmerged file, then
+/// `git status --porcelain=v2 --branch`. `None` when nothing is in progress.
+fn operation_state() -> Option<String> {
+    let dirs = git_output(&["rev-parse", "--absolute-git-dir", "--show-toplevel"])?;
+    let mut dirs = dirs.lines().map(Path::new);
+    let (git_dir, top) = (dirs.next()?, dirs.next()?);
+    let read = |name: &str| {
+        std::fs::read_to_string(git_dir.join(name))
+            .ok()
+            .map(|s| s.trim().to_string())
+    };
+    let rebase = |dir: &str, at: &str, of: &str| {
+        let step = (read(&format!("{dir}/{at}")), read(&format!("{dir}/{of}")));
+        let mut operation = match step {
+            (Some(n), Some(m)) => format!("rebase {n}/{m}"),
+            _ => "rebase".into(),
+        };
+        // HEAD is detached while rebasing: name the branch being rebased.
+        if let Some(head) = read(&format!("{dir}/head-name")) {
+            let branch = head.trim_start_matches("refs/heads/");
+            operation.push_str(&format!(" of {branch}"));
+        }
+        operation
+    };
+    let operation = if git_dir.join("rebase-merge").is_dir() {
+        rebase("rebase-merge", "msgnum", "end")
+    } else if git_dir.join("rebase-apply").is_dir() {
+        rebase("rebase-apply", "next", "last")
+    } else if git_dir.join("MERGE_HEAD").is_file() {
+        "merge".into()
+    } else if git_dir.join("CHERRY_PICK_HEAD").is_file() {
+        "cherry-pick".into()
+    } else if git_dir.join("REVERT_HEAD").is_file() {
+        "revert".into()
+    } else {
+        return None;
+    };
+
+    let status = git_output(&["status", "--porcelain=v2", "--branch"]).unwrap_or_default();
+    let mut state = format!("{OPERATION_HEADER}{operation}\n");
+    for line in status.lines().filter(|l| l.starts_with("u ")) {
+        let Some(path) = line.splitn(11, ' ').nth(10) else {
+            continue;
+        };
+        let markers = std::fs::read_to_string(top.join(path))
+            .map(|text| text.lines().filter(|l| l.starts_with("<<<<<<<")).count())
+            .unwrap_or(0);
+        state.push_str(&format!("{MARKERS_HEADER}{markers} {path}\n"));
+    }
+    state.push_str(&status);
+    Some(state)
+}
+
+/// Trimmed stdout of a git command that succeeded with some output.
+fn git_output(args: &[&str]) -> Option<String> {
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let result = runner::exec("git", &args, &ExecOpts::default()).ok()?;
+    let out = result.stdout.trim();
+    (result.exit_code == 0 && !out.is_empty()).then(|| out.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The sub-command and arguments `cx git <args>` runs.
+    fn built(args: &str, base: Option<&str>) -> (String, String) {
+        let args = args.split_whitespace().map(String::from).collect();
+        let (sub, out) = GitTool::new(args, &Config::default()).build_args(base);
+        (sub, out.join(" "))
+    }
+
+    #[test]
+    fn test_build_args_status() {
+        for (args, sub, command) in [
+            ("", "status", "status --porcelain=v2 --branch"),
+            ("status", "status", "status --porcelain=v2 --branch"),
+            (
+                "status -uno",
+                "status",
+                "status --porcelain=v2 --branch -uno",
+            ),
+            ("status -s", "status-short", "status -s"),
+            ("status -sb", "status-short", "status -sb"),
+            ("status --porcelain", "status-short", "status --porcelain"),
+            (
+                "status --porcelain=v2 -z",
+                "status-short",
+                "status --porcelain=v2 -z",
+            ),
+            ("status --porcelain=v2", "status", "status --porcelain=v2"),
+            ("status --long", "status", "status --long"),
+        ] {
+            assert_eq!(built(args, None), (sub.into(), command.into()), "{args}");
+        }
+    }
+
+    #[test]
+    fn test_build_args_log() {
+        let format = format!("{LOG_FORMAT} --date=short");
+        for (args, base, sub, command) in [
+            ("log", None, "log", format!("log {format} -n30")),
+            ("log -n5", None, "log", format!("log {format} -n5")),
+            (
+                "log --max-count=3 --by-author",
+                None,
+                "log-author",
+                format!("log {format} --max-count=3"),
+            ),
+            (
+                "log --since-branch",
+                Some("abc123"),
+                "log",
+                format!("log {format} abc123..HEAD"),
+            ),
+            (
+                "log --since-branch -- src/",
+                Some("abc123"),
+                "log",
+                format!("log {format} abc123..HEAD -- src/"),
+            ),
+            (
+                "log --oneline --by-author",
+                None,
+                "log",
+                "log -n30 --oneline".to_string(),
+            ),
+            (
+                "log --date=iso -n2",
+                None,
+                "log",
+                format!("log {LOG_FORMAT} --date=iso -n2"),
+            ),
+        ] {
+            assert_eq!(built(args, base), (sub.into(), command), "{args}");
         }
     }
 }
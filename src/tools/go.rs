@@ -0,0 +1,110 @@
+use super::{Outcome, RunOpts, Tool, run_compressed};
+use crate::compress::go::GoCompressor;
+use crate::config::Config;
+
+/// `go build` / `test` / `vet` flags that take a separate value argument.
+const VALUE_FLAGS: &[&str] = &[
+    "-C",
+    "-o",
+    "-p",
+    "-run",
+    "-skip",
+    "-bench",
+    "-benchtime",
+    "-count",
+    "-cpu",
+    "-parallel",
+    "-timeout",
+    "-tags",
+    "-coverprofile",
+    "-coverpkg",
+    "-covermode",
+    "-ldflags",
+    "-gcflags",
+    "-asmflags",
+    "-mod",
+    "-modfile",
+    "-exec",
+    "-vet",
+    "-shuffle",
+    "-fuzz",
+    "-fuzztime",
+];
+
+/// Go tool: runs go sub-commands with smart defaults, compresses output.
+pub struct GoTool {
+    args: Vec<String>,
+    opts: RunOpts,
+}
+
+impl GoTool {
+    pub fn new(args: Vec<String>, cfg: &Config) -> Self {
+        Self {
+            args,
+            opts: RunOpts::new(cfg, "go"),
+        }
+    }
+
+    /// Inject sensible defaults per sub-command.
+    fn build_args(&self) -> Vec<String> {
+        let sub = &self.args[0];
+        let rest = &self.args[1..];
+        let mut out = self.args.clone();
+
+        if matches!(sub.as_str(), "build" | "test" | "vet") && !names_package(rest) {
+            // Whole module by default, like `cargo check` covers the workspace.
+            let at = rest
+                .iter()
+                .position(|a| a == "-args")
+                .map_or(out.len(), |i| i + 1);
+            out.insert(at, "./...".into());
+        }
+        out
+    }
+
+    /// Compress sub-key: `mod tidy` / `mod download` share the `mod` compressor.
+    fn compress_key(&self) -> &str {
+        match (self.args[0].as_str(), self.args.get(1).map(String::as_str)) {
+            ("mod", Some("tidy" | "download")) => "mod",
+            ("mod", Some(other)) => other,
+            (sub, _) => sub,
+        }
+    }
+}
+
+/// Whether the arguments already name a package (a positional, non-flag argument).
+fn names_package(args: &[String]) -> bool {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-args" {
+            return false;
+        }
+        if !arg.starts_with('-') {
+            return true;
+        }
+        // Go accepts `-flag` and `--flag` alike.
+        let flag = format!("-{}", arg.trim_start_matches('-'));
+        if !arg.contains('=') && VALUE_FLAGS.contains(&flag.as_str()) {
+            args.next();
+        }
+    }
+    false
+}
+
+impl Tool for GoTool {
+    fn run(&self) -> Outcome {
+        if self.args.is_empty() {
+            return Outcome::usage("[go] error: needs a subcommand (build, test, vet, mod, …)");
+        }
+
+        let args = self.build_args();
+        run_compressed(
+            "go",
+            "go",
+            &args,
+            &GoCompressor,
+            Some(self.compress_key()),
+            &self.opts,
+        )
+    }
+}
@@ -1,21 +1,22 @@
-use super::{Tool, footer};
-use crate::compress::Compressor;
+use super::{Outcome, RunOpts, Tool, run_compressed};
 use crate::compress::grep::GrepCompressor;
-use crate::runner;
+use crate::config::Config;
 
 /// Grep tool: runs grep or ripgrep, compresses grouped output.
 pub struct GrepTool {
     pattern: String,
     path: String,
     use_rg: bool,
+    opts: RunOpts,
 }
 
 impl GrepTool {
-    pub fn new(pattern: String, path: String, use_rg: bool) -> Self {
+    pub fn new(pattern: String, path: String, use_rg: bool, cfg: &Config) -> Self {
         Self {
             pattern,
             path,
             use_rg,
+            opts: RunOpts::new(cfg, "grep"),
         }
     }
 
@@ -40,17 +41,9 @@ impl GrepTool {
 }
 
 impl Tool for GrepTool {
-    fn run(&self) -> String {
+    fn run(&self) -> Outcome {
         let (program, args) = self.build_args();
 
-        match runner::exec(program, &args) {
-            Ok(result) => {
-                let raw = result.combined();
-                let compressor = GrepCompressor;
-                let compressed = compressor.compress(&raw, None);
-                format!("{compressed}{}", footer("grep", &result))
-            }
-            Err(e) => format!("[grep] error: {e}"),
-        }
+        run_compressed("grep", program, &args, &GrepCompressor, None, &self.opts)
     }
 }
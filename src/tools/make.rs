@@ -0,0 +1,136 @@
+use super::{Outcome, RunOpts, Tool, run_compressed};
+use crate::compress::make::{self, MakeCompressor, RECIPES_HEADER};
+use crate::compress::normalize::normalize;
+use crate::config::{Config, OutputFormat};
+use crate::runner::{self, ExecOpts};
+use serde_json::json;
+use std::fs;
+
+/// Makefiles in the order GNU make looks for them.
+const MAKEFILES: &[&str] = &["GNUmakefile", "makefile", "Makefile"];
+
+/// Task runner behind `cx make` / `cx just` / `cx task`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Runner {
+    Make,
+    Just,
+    Task,
+}
+
+impl Runner {
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Make => "make",
+            Self::Just => "just",
+            Self::Task => "task",
+        }
+    }
+}
+
+/// Make tool: runs a target and summarizes the output with the compressor of
+/// the tool the recipe ran; lists targets when none is given.
+pub struct MakeTool {
+    runner: Runner,
+    args: Vec<String>,
+    opts: RunOpts,
+}
+
+impl MakeTool {
+    pub fn new(runner: Runner, args: Vec<String>, cfg: &Config) -> Self {
+        Self {
+            runner,
+            args,
+            opts: RunOpts::new(cfg, runner.program()),
+        }
+    }
+
+    /// The command lines the run will echo, from a dry run, so the compressor
+    /// can tell them from what the recipes print. `task` marks its echo
+    /// itself (`task: [name] …`).
+    fn dry_run(&self) -> Option<String> {
+        let flag = match self.runner {
+            Runner::Make => "-n",
+            Runner::Just => "--dry-run",
+            Runner::Task => return None,
+        };
+        let args: Vec<String> = std::iter::once(flag.to_string())
+            .chain(self.args.iter().cloned())
+            .collect();
+        let result = runner::exec(self.runner.program(), &args, &ExecOpts::default()).ok()?;
+        // make prints the commands on stdout, just on stderr.
+        let commands = match self.runner {
+            Runner::Make => result.stdout,
+            _ => result.stderr,
+        };
+        Some(format!("{RECIPES_HEADER}{}", normalize(&commands)))
+    }
+
+    /// `make` has no listing flag: read the targets from the Makefile itself.
+    fn list_makefile(&self) -> Outcome {
+        let Some((path, text)) = MAKEFILES
+            .iter()
+            .find_map(|p| fs::read_to_string(p).ok().map(|t| (p, t)))
+        else {
+            return Outcome::usage("[make] error: no Makefile in the current directory");
+        };
+
+        let targets = make::makefile_targets(&text);
+        let mut outcome = Outcome::new(
+            format!(
+                "[make] {path}\n{}",
+                make::format_targets(&targets, &self.opts.ctx).trim_end()
+            ),
+            0,
+        );
+        if self.opts.format == OutputFormat::Json {
+            outcome.json = Some(json!({
+                "tool": "make",
+                "sub": "targets",
+                "path": path,
+                "exit_code": 0,
+                "summary": { "targets": targets },
+            }));
+        }
+        outcome
+    }
+}
+
+impl Tool for MakeTool {
+    fn run(&self) -> Outcome {
+        let program = self.runner.program();
+        if self.args.is_empty() {
+            return match self.runner {
+                Runner::Make => self.list_makefile(),
+                Runner::Just => run_compressed(
+                    program,
+                    program,
+                    &["--list".into()],
+                    &MakeCompressor,
+                    Some("targets"),
+                    &self.opts,
+                ),
+                Runner::Task => run_compressed(
+                    program,
+                    program,
+                    &["--list-all".into()],
+                    &MakeCompressor,
+                    Some("targets"),
+                    &self.opts,
+                ),
+            };
+        }
+
+        let opts = RunOpts {
+            trailer: self.dry_run(),
+            ..self.opts.clone()
+        };
+        run_compressed(
+            program,
+            program,
+            &self.args,
+            &MakeCompressor,
+            Some("run"),
+            &opts,
+        )
+    }
+}
@@ -1,24 +1,368 @@
+pub mod archive;
 pub mod cargo;
 pub mod docker;
 pub mod fs;
 pub mod generic;
 pub mod git;
+pub mod go;
 pub mod grep;
+pub mod make;
+pub mod node;
 pub mod python;
+pub mod stats;
 
-use crate::runner::RunResult;
+use crate::archive::{Archive, Entry};
+use crate::cache::CacheSpec;
+use crate::compress::{CompressCtx, Compressor, budget, junit, normalize::normalize};
+use crate::config::{Config, OutputFormat};
+use crate::delta::DeltaStore;
+use crate::runner::{self, ExecOpts};
+use crate::stats::{Record, StatsLog};
+use serde_json::{Value, json};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Exit code for invocations `cx` rejects before running anything.
+pub const EXIT_USAGE: i32 = 2;
+
+/// Settings a tool needs at run time, resolved from `Config` for one tool.
+#[derive(Debug, Clone)]
+pub struct RunOpts {
+    pub ctx: CompressCtx,
+    pub format: OutputFormat,
+    /// Where raw output is kept for `cx last` / `cx show`; `None` when disabled.
+    pub archive: Option<Archive>,
+    /// Where the previous run's items are kept in delta mode; `None` when off.
+    pub delta: Option<DeltaStore>,
+    /// Where each run's sizes are logged for `cx stats`; `None` when disabled.
+    pub stats: Option<StatsLog>,
+    pub exec: ExecOpts,
+    /// Report file the command writes (a JUnit XML, …). When the run
+    /// refreshed it, its contents are handed to the compressor after the
+    /// command's output.
+    pub report: Option<PathBuf>,
+    /// Query run after the command (a status after a conflicted merge, …);
+    /// what it returns is handed to the compressor after the output.
+    pub follow_up: Option<fn() -> Option<String>>,
+    /// Text known before the run (the commands `make -n` would echo, …),
+    /// handed to the compressor after the output.
+    pub trailer: Option<String>,
+}
+
+impl RunOpts {
+    pub fn new(cfg: &Config, tool: &str) -> Self {
+        Self {
+            ctx: cfg.compress_ctx(tool),
+            format: cfg.format,
+            archive: Archive::open(cfg.archive_size),
+            delta: DeltaStore::open(cfg.delta),
+            stats: StatsLog::open(cfg.stats),
+            exec: ExecOpts {
+                timeout: cfg.timeout_secs.map(Duration::from_secs),
+                max_output_bytes: cfg.max_output_bytes,
+                progress: std::io::stderr().is_terminal(),
+            },
+            report: None,
+            follow_up: None,
+            trailer: None,
+        }
+    }
+}
+
+/// Outcome of a tool run: compressed text + the exit code `cx` should exit with.
+pub struct Outcome {
+    pub text: String,
+    pub exit_code: i32,
+    /// Structured result, filled when the output format is JSON.
+    pub json: Option<Value>,
+}
+
+impl Outcome {
+    pub fn new(text: String, exit_code: i32) -> Self {
+        Self {
+            text,
+            exit_code,
+            json: None,
+        }
+    }
+
+    /// Invalid invocation (missing subcommand, bad path, …).
+    pub fn usage(text: impl Into<String>) -> Self {
+        Self::new(text.into(), EXIT_USAGE)
+    }
+}
 
 /// A tool knows how to build a command and which compressor to apply.
 pub trait Tool {
-    /// Execute the tool and return compressed output.
-    fn run(&self) -> String;
+    /// Execute the tool and return compressed output + exit code.
+    fn run(&self) -> Outcome;
+
+    /// What a cached result of this command depends on; `None` (the default)
+    /// when it must always run, e.g. because it changes something.
+    fn cache_spec(&self) -> Option<CacheSpec> {
+        None
+    }
+}
+
+/// Execute a command, compress its combined output and append the footer.
+/// The child's exit code is propagated; spawn failures map to 126/127.
+/// Raw output is archived so the footer can point at `cx show <id>`.
+pub fn run_compressed(
+    label: &str,
+    program: &str,
+    args: &[String],
+    compressor: &dyn Compressor,
+    sub: Option<&str>,
+    opts: &RunOpts,
+) -> Outcome {
+    let command: Vec<&str> = std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .collect();
+
+    let started = SystemTime::now();
+    match runner::exec(program, args, &opts.exec) {
+        Ok(result) => {
+            let output = result.combined();
+            let mut raw = normalize(&output);
+            if let Some(report) = opts
+                .report
+                .as_deref()
+                .and_then(|p| fresh_report(p, started))
+            {
+                raw.push('\n');
+                raw.push_str(junit::REPORT_HEADER);
+                raw.push_str(&report);
+            }
+            if let Some(trailer) = &opts.trailer {
+                raw.push('\n');
+                raw.push_str(trailer);
+            }
+            if let Some(state) = opts.follow_up.and_then(|query| query()) {
+                raw.push('\n');
+                raw.push_str(&state);
+            }
+            let ctx = CompressCtx {
+                exit_code: result.exit_code,
+                ..opts.ctx.clone()
+            };
+            let mut compressed = compressor.compress(&raw, sub, &ctx);
+            let summary = (opts.delta.is_some() || opts.format == OutputFormat::Json)
+                .then(|| compressor.summarize(&raw, sub, &ctx));
+            // A partial run would report everything after the cut as resolved.
+            let delta = opts
+                .delta
+                .as_ref()
+                .zip(summary.as_ref())
+                .filter(|_| result.timed_out.is_none())
+                .and_then(|(store, summary)| store.compare(&command, summary));
+            if let Some(delta) = &delta {
+                compressed = delta.render(&opts.ctx);
+            }
+            if let Some(tokens) = opts.ctx.token_budget {
+                compressed = budget::fit(&compressed, tokens);
+            }
+            if let Some(limit) = result.timed_out {
+                compressed = with_footer(
+                    &compressed,
+                    &format!("[cx] timed out after {}s", limit.as_secs()),
+                );
+            }
+            // Archiving is best effort: a full disk must not fail the command.
+            let archive_id = opts.archive.as_ref().and_then(|a| {
+                a.save(Entry {
+                    id: 0,
+                    timestamp: 0,
+                    tool: label.to_string(),
+                    command: command.iter().map(|s| s.to_string()).collect(),
+                    exit_code: result.exit_code,
+                    elapsed_ms: result.elapsed_ms,
+                    stdout: result.stdout.clone(),
+                    stderr: result.stderr.clone(),
+                    compressed: compressed.clone(),
+                })
+                .ok()
+            });
+            let mut outcome = Outcome::new(
+                with_footer(
+                    &compressed,
+                    &footer(label, result.exit_code, result.elapsed_ms, archive_id),
+                ),
+                result.exit_code,
+            );
+            if opts.format == OutputFormat::Json {
+                outcome.json = Some(json!({
+                    "tool": label,
+                    "sub": sub,
+                    "command": command,
+                    "exit_code": result.exit_code,
+                    "elapsed_ms": result.elapsed_ms,
+                    "timed_out": result.timed_out.is_some(),
+                    "archive_id": archive_id,
+                    "summary": summary,
+                }));
+                if let (Some(json), Some(delta)) = (&mut outcome.json, &delta) {
+                    json["delta"] = json!(delta);
+                }
+            }
+            // Logging is best effort, like archiving.
+            if let Some(stats) = &opts.stats {
+                let printed = outcome.render(opts.format);
+                let _ = stats.append(Record {
+                    timestamp: 0,
+                    tool: label.to_string(),
+                    sub: sub.map(str::to_string),
+                    command: command.iter().map(|s| s.to_string()).collect(),
+                    raw_bytes: (result.stdout.len() + result.stderr.len()) as u64,
+                    compressed_bytes: printed.len() as u64,
+                    // The command's own output: not the report or follow-up
+                    // appended to `raw` for the compressor.
+                    raw_tokens: budget::estimate_tokens(&output) as u64,
+                    compressed_tokens: budget::estimate_tokens(&printed) as u64,
+                    elapsed_ms: result.elapsed_ms,
+                    exit_code: result.exit_code,
+                });
+            }
+            outcome
+        }
+        Err(e) => {
+            let mut outcome = Outcome::new(format!("[{label}] error: {e}"), e.exit_code());
+            if opts.format == OutputFormat::Json {
+                outcome.json = Some(json!({
+                    "tool": label,
+                    "command": command,
+                    "exit_code": outcome.exit_code,
+                    "error": e.to_string(),
+                }));
+            }
+            // Logged too, so a command that cannot start still counts as a
+            // failed run; it has no output of its own.
+            if let Some(stats) = &opts.stats {
+                let printed = outcome.render(opts.format);
+                let _ = stats.append(Record {
+                    timestamp: 0,
+                    tool: label.to_string(),
+                    sub: sub.map(str::to_string),
+                    command: command.iter().map(|s| s.to_string()).collect(),
+                    raw_bytes: 0,
+                    compressed_bytes: printed.len() as u64,
+                    raw_tokens: 0,
+                    compressed_tokens: budget::estimate_tokens(&printed) as u64,
+                    elapsed_ms: started.elapsed().map_or(0, |d| d.as_millis()),
+                    exit_code: outcome.exit_code,
+                });
+            }
+            outcome
+        }
+    }
 }
 
-/// Format a one-line footer with timing and exit code.
-pub fn footer(label: &str, result: &RunResult) -> String {
-    let status = if result.success() { "ok" } else { "FAIL" };
-    format!(
-        "[{label}] {status} ({}ms, exit {})",
-        result.elapsed_ms, result.exit_code
-    )
+/// Contents of `path` if it was written since `started`, so a report left
+/// over from an earlier run is never mistaken for this one's. Allows a second
+/// of slack for filesystems with coarse timestamps.
+fn fresh_report(path: &Path, started: SystemTime) -> Option<String> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let since = started
+        .checked_sub(Duration::from_secs(1))
+        .unwrap_or(started);
+    if modified < since {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+impl Outcome {
+    /// Render for printing: the JSON object in JSON mode, the text otherwise.
+    /// Outcomes without a structured result (usage errors, …) are wrapped.
+    pub fn render(&self, format: OutputFormat) -> String {
+        match (format, &self.json) {
+            (OutputFormat::Text, _) => self.text.clone(),
+            (OutputFormat::Json, Some(v)) => v.to_string(),
+            (OutputFormat::Json, None) => {
+                let key = if self.exit_code == 0 { "text" } else { "error" };
+                json!({ "exit_code": self.exit_code, key: self.text }).to_string()
+            }
+        }
+    }
+}
+
+/// Format a one-line footer with timing, exit code and the archive handle.
+pub fn footer(label: &str, exit_code: i32, elapsed_ms: u128, archive_id: Option<u64>) -> String {
+    let status = if exit_code == 0 { "ok" } else { "FAIL" };
+    let mut line = format!("[{label}] {status} ({elapsed_ms}ms, exit {exit_code})");
+    if let Some(id) = archive_id {
+        line.push_str(&format!(" → cx show {id}"));
+    }
+    line
+}
+
+/// Put the footer on its own line after the compressed body.
+pub fn with_footer(body: &str, footer: &str) -> String {
+    if body.is_empty() || body.ends_with('\n') {
+        format!("{body}{footer}")
+    } else {
+        format!("{body}\n{footer}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_footer_status_and_handle() {
+        assert_eq!(footer("git", 0, 12, None), "[git] ok (12ms, exit 0)");
+        assert_eq!(
+            footer("cargo", 101, 812, Some(7)),
+            "[cargo] FAIL (812ms, exit 101) → cx show 7"
+        );
+    }
+
+    #[test]
+    fn test_with_footer_separates_lines() {
+        assert_eq!(with_footer("body", "[x] ok"), "body\n[x] ok");
+        assert_eq!(with_footer("body\n", "[x] ok"), "body\n[x] ok");
+        assert_eq!(with_footer("", "[x] ok"), "[x] ok");
+    }
+
+    #[test]
+    fn test_stats_measure_the_command_output() {
+        let dir = std::env::temp_dir().join(format!("cx-run-stats-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let report = dir.join("report.xml");
+        let log = StatsLog::new(dir.join("stats.jsonl"));
+        let defaults = RunOpts::new(&Config::default(), "run");
+        let opts = RunOpts {
+            archive: None,
+            delta: None,
+            stats: Some(log.clone()),
+            format: OutputFormat::Text,
+            exec: ExecOpts {
+                progress: false,
+                ..defaults.exec.clone()
+            },
+            report: Some(report.clone()),
+            ..defaults
+        };
+        let compressor = crate::compress::generic::GenericCompressor;
+
+        let script = format!(
+            "echo '{}' > {}; echo 12345678",
+            "<testsuite>".repeat(100),
+            report.display()
+        );
+        run_compressed("sh", "sh", &["-c".into(), script], &compressor, None, &opts);
+        run_compressed("nope", "cx-no-such-program", &[], &compressor, None, &opts);
+
+        let records = log.read(0);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].raw_bytes, 9);
+        assert_eq!(
+            records[0].raw_tokens,
+            budget::estimate_tokens("12345678\n") as u64
+        );
+        assert_eq!((records[1].raw_bytes, records[1].raw_tokens), (0, 0));
+        assert_eq!(records[1].exit_code, runner::EXIT_NOT_FOUND);
+    }
 }
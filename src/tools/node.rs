@@ -0,0 +1,123 @@
+use super::{Outcome, RunOpts, Tool, run_compressed};
+use crate::compress::node::NodeCompressor;
+use crate::config::Config;
+use std::path::Path;
+
+/// Node package manager: the one named (`cx npm`, …), or for `cx node` the
+/// one whose lockfile is in the project directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageManager {
+    Npm,
+    Pnpm,
+    Yarn,
+    Bun,
+}
+
+impl PackageManager {
+    /// The manager whose lockfile is present in `dir` (npm when none is).
+    pub fn detect(dir: &Path) -> Self {
+        if dir.join("pnpm-lock.yaml").exists() {
+            Self::Pnpm
+        } else if dir.join("yarn.lock").exists() {
+            Self::Yarn
+        } else if dir.join("bun.lockb").exists() || dir.join("bun.lock").exists() {
+            Self::Bun
+        } else {
+            Self::Npm
+        }
+    }
+
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Pnpm => "pnpm",
+            Self::Yarn => "yarn",
+            Self::Bun => "bun",
+        }
+    }
+}
+
+/// Node tool: runs npm / pnpm / yarn / bun with smart defaults, compresses output.
+pub struct NodeTool {
+    args: Vec<String>,
+    manager: PackageManager,
+    opts: RunOpts,
+}
+
+impl NodeTool {
+    /// `manager` is `None` for `cx node`, which detects it from the lockfile.
+    pub fn new(manager: Option<PackageManager>, args: Vec<String>, cfg: &Config) -> Self {
+        Self {
+            args,
+            manager: manager.unwrap_or_else(|| PackageManager::detect(Path::new("."))),
+            opts: RunOpts::new(cfg, "node"),
+        }
+    }
+
+    /// Arguments for the package manager + the compress sub-key.
+    fn build_command(&self) -> (Vec<String>, &str) {
+        let sub = self.args[0].as_str();
+        let rest = &self.args[1..];
+        let with = |head: &[&str]| -> Vec<String> {
+            head.iter()
+                .map(|s| s.to_string())
+                .chain(rest.iter().cloned())
+                .collect()
+        };
+
+        match sub {
+            "install" | "i" | "ci" | "add" | "remove" | "rm" | "uninstall" | "update" | "up" => {
+                (self.args.clone(), "install")
+            }
+            "test" | "t" => match self.manager {
+                // `bun test` is bun's own runner, not the package's test script.
+                PackageManager::Bun => (with(&["run", "test"]), "test"),
+                _ => (with(&["test"]), "test"),
+            },
+            "run" => {
+                let key = if rest.first().is_some_and(|s| s == "test") {
+                    "test"
+                } else {
+                    "run"
+                };
+                (self.args.clone(), key)
+            }
+            "ls" | "list" => {
+                let has_depth = rest
+                    .iter()
+                    .any(|a| a.starts_with("--depth") || a == "--all");
+                let mut args = match self.manager {
+                    PackageManager::Bun => with(&["pm", "ls"]),
+                    _ => with(&[sub]),
+                };
+                if !has_depth && matches!(self.manager, PackageManager::Npm | PackageManager::Yarn)
+                {
+                    args.insert(1, "--depth=0".into());
+                }
+                (args, "ls")
+            }
+            "outdated" => (self.args.clone(), "outdated"),
+            _ => (self.args.clone(), sub),
+        }
+    }
+}
+
+impl Tool for NodeTool {
+    fn run(&self) -> Outcome {
+        if self.args.is_empty() {
+            return Outcome::usage(
+                "[node] error: needs a subcommand (install, run, test, ls, outdated, …)",
+            );
+        }
+
+        let (args, key) = self.build_command();
+        run_compressed(
+            "node",
+            self.manager.program(),
+            &args,
+            &NodeCompressor,
+            Some(key),
+            &self.opts,
+        )
+    }
+}
@@ -1,20 +1,41 @@
-use super::{Tool, footer};
-use crate::compress::Compressor;
+use super::{Outcome, RunOpts, Tool, run_compressed};
 use crate::compress::python::PythonCompressor;
-use crate::runner;
+use crate::config::Config;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Python/UV tool: dispatches to the right program and compresses output.
 pub struct PythonTool {
     args: Vec<String>,
+    opts: RunOpts,
+    /// Whether to ask pytest for a JUnit report in a temp file, created for
+    /// the run and removed after it (when the user gave no `--junitxml`).
+    junit_temp: bool,
 }
 
 impl PythonTool {
-    pub fn new(args: Vec<String>) -> Self {
-        Self { args }
+    pub fn new(args: Vec<String>, cfg: &Config) -> Self {
+        let mut opts = RunOpts::new(cfg, "python");
+        let mut junit_temp = false;
+        if matches!(args.first().map(String::as_str), Some("pytest" | "test")) {
+            // Exact counts (xfailed, errors, …) from pytest's JUnit report:
+            // the user's own `--junitxml` if given, else a temp file.
+            match junit_path(&args[1..]) {
+                Some(path) => opts.report = Some(path),
+                None => junit_temp = true,
+            }
+        }
+        Self {
+            args,
+            opts,
+            junit_temp,
+        }
     }
 
     /// Determine which program to run and what compress sub-key to use.
-    fn build_command(&self) -> (&str, Vec<String>, &str) {
+    fn build_command(&self, junit_temp: Option<&Path>) -> (&str, Vec<String>, &str) {
         if self.args.is_empty() {
             return ("python", vec!["--version".into()], "run");
         }
@@ -34,6 +55,9 @@ impl PythonTool {
                     args = Vec::new();
                 }
                 args.extend(rest);
+                if let Some(path) = junit_temp {
+                    args.push(format!("--junitxml={}", path.display()));
+                }
                 ("pytest", args, "pytest")
             }
             // Linting
@@ -74,18 +98,71 @@ impl PythonTool {
     }
 }
 
+/// The report path from a `--junitxml=path` / `--junit-xml path` argument.
+fn junit_path(args: &[String]) -> Option<PathBuf> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        for flag in ["--junitxml", "--junit-xml"] {
+            if let Some(path) = arg.strip_prefix(flag).and_then(|a| a.strip_prefix('=')) {
+                return Some(path.into());
+            }
+            if arg == flag {
+                return args.next().map(PathBuf::from);
+            }
+        }
+    }
+    None
+}
+
+/// A new, empty file for pytest's JUnit report. Created exclusively under a
+/// name no other run uses, so a file or symlink planted at a guessable path
+/// in the shared temp dir is never written through.
+fn create_junit_temp() -> io::Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    for attempt in 0u32..100 {
+        let path = std::env::temp_dir().join(format!(
+            "cx-pytest-{}-{nanos:09}-{attempt}.xml",
+            std::process::id()
+        ));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no free JUnit report name",
+    ))
+}
+
 impl Tool for PythonTool {
-    fn run(&self) -> String {
-        let (program, args, compress_key) = self.build_command();
+    fn run(&self) -> Outcome {
+        // Without a temp file pytest still runs; counts come from its summary.
+        let junit_temp = self.junit_temp.then(|| create_junit_temp().ok()).flatten();
+        let (program, args, compress_key) = self.build_command(junit_temp.as_deref());
 
-        match runner::exec(program, &args) {
-            Ok(result) => {
-                let raw = result.combined();
-                let compressor = PythonCompressor;
-                let compressed = compressor.compress(&raw, Some(compress_key));
-                format!("{compressed}{}", footer("python", &result))
-            }
-            Err(e) => format!("[python] error: {e}"),
+        let opts = RunOpts {
+            report: junit_temp.clone().or_else(|| self.opts.report.clone()),
+            ..self.opts.clone()
+        };
+        let outcome = run_compressed(
+            "python",
+            program,
+            &args,
+            &PythonCompressor,
+            Some(compress_key),
+            &opts,
+        );
+        if let Some(path) = &junit_temp {
+            let _ = fs::remove_file(path);
         }
+        outcome
     }
 }
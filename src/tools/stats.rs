@@ -0,0 +1,49 @@
+use super::{Outcome, Tool};
+use crate::config::{Config, OutputFormat};
+use crate::stats::{self, GroupBy, Since, StatsLog};
+use serde_json::json;
+
+/// Stats tool: reports compression ratios from the stats log.
+pub struct StatsTool {
+    since: Option<Since>,
+    by: GroupBy,
+    log: Option<StatsLog>,
+    max_items: usize,
+    format: OutputFormat,
+}
+
+impl StatsTool {
+    pub fn new(since: Option<Since>, by: GroupBy, cfg: &Config) -> Self {
+        Self {
+            since,
+            by,
+            log: StatsLog::open(cfg.stats),
+            max_items: cfg.max_items,
+            format: cfg.format,
+        }
+    }
+}
+
+impl Tool for StatsTool {
+    fn run(&self) -> Outcome {
+        let Some(log) = &self.log else {
+            return Outcome::usage("[cx] stats disabled (stats = false)");
+        };
+        let records = log.read(self.since.as_ref().map_or(0, Since::start));
+        let report = stats::report(&records, self.by);
+
+        let mut outcome = Outcome::new(
+            report.render(self.by, self.since.as_ref(), self.max_items),
+            0,
+        );
+        if self.format == OutputFormat::Json {
+            outcome.json = Some(json!({
+                "since": self.since.as_ref().map(Since::to_string),
+                "total": report.total,
+                "groups": report.groups,
+                "worst": report.worst,
+            }));
+        }
+        outcome
+    }
+}